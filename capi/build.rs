@@ -0,0 +1,7 @@
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    cbindgen::generate(&crate_dir)
+        .expect("failed to generate the C header")
+        .write_to_file(std::path::Path::new(&crate_dir).join("include/ktx2_tools.h"));
+}
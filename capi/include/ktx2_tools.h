@@ -0,0 +1,171 @@
+#ifndef KTX2_TOOLS_H
+#define KTX2_TOOLS_H
+
+/* Generated by cbindgen from capi/src/lib.rs. Don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum Ktx2Status {
+  KTX2_STATUS_OK = 0,
+  // A required pointer was null, or a value was out of range.
+  KTX2_STATUS_INVALID_ARGUMENT,
+  // The input isn't a valid KTX2 file.
+  KTX2_STATUS_PARSE_ERROR,
+  KTX2_STATUS_UNSUPPORTED_FORMAT,
+  KTX2_STATUS_UNSUPPORTED_SUPERCOMPRESSION,
+  KTX2_STATUS_INVALID_DIMENSIONS,
+  // A level is missing, out of range or the wrong size.
+  KTX2_STATUS_INVALID_LEVEL,
+  KTX2_STATUS_COMPRESSION_ERROR,
+  KTX2_STATUS_ENCODING_ERROR,
+  KTX2_STATUS_DECODING_ERROR,
+  KTX2_STATUS_INVALID_METADATA,
+  KTX2_STATUS_IO_ERROR,
+  // The key isn't in the key/value data.
+  KTX2_STATUS_NOT_FOUND,
+  // An internal error, which is a bug in the library.
+  KTX2_STATUS_PANIC,
+} Ktx2Status;
+
+// Bytes produced by the library, such as a written KTX2 file.
+typedef struct Ktx2Buffer Ktx2Buffer;
+
+// A parsed KTX2 file, for querying its header, levels and key/value data.
+typedef struct Ktx2Info Ktx2Info;
+
+// Builds a KTX2 file from uncompressed levels, checking each level's size against the format
+// and dimensions.
+typedef struct Ktx2Writer Ktx2Writer;
+
+typedef struct Ktx2Bc7Options {
+  // Whether the image is sRGB-encoded color data.
+  bool srgb;
+  bool generate_mipmaps;
+  // As for [`ktx2_writer_set_supercompression_scheme`].
+  uint32_t supercompression_scheme;
+  int32_t zstd_level;
+} Ktx2Bc7Options;
+
+typedef struct Ktx2Bc6hOptions {
+  // As for [`ktx2_writer_set_supercompression_scheme`].
+  uint32_t supercompression_scheme;
+  int32_t zstd_level;
+} Ktx2Bc6hOptions;
+
+typedef struct Ktx2Header {
+  // 0 if the format is only described by the DFD.
+  uint32_t vk_format;
+  uint32_t type_size;
+  uint32_t pixel_width;
+  uint32_t pixel_height;
+  uint32_t pixel_depth;
+  uint32_t layer_count;
+  uint32_t face_count;
+  uint32_t level_count;
+  uint32_t supercompression_scheme;
+} Ktx2Header;
+
+typedef struct Ktx2LevelInfo {
+  uint64_t byte_offset;
+  uint64_t byte_length;
+  uint64_t uncompressed_byte_length;
+} Ktx2LevelInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The message for the last failure on this thread, or null if nothing has failed. The string
+// is valid until the next failing call on the same thread.
+const char *ktx2_last_error_message(void);
+
+const uint8_t *ktx2_buffer_data(const struct Ktx2Buffer *buffer);
+
+uintptr_t ktx2_buffer_len(const struct Ktx2Buffer *buffer);
+
+void ktx2_buffer_free(struct Ktx2Buffer *buffer);
+
+// Create a writer. `pixel_depth`, `layer_count` and `face_count` are as in the KTX2 header: 0
+// for textures that aren't 3D or arrays, and 1 or 6 faces.
+enum Ktx2Status ktx2_writer_new(uint32_t vk_format,
+                                uint32_t pixel_width,
+                                uint32_t pixel_height,
+                                uint32_t pixel_depth,
+                                uint32_t layer_count,
+                                uint32_t face_count,
+                                struct Ktx2Writer **out);
+
+// Set the supercompression scheme, using the values from the KTX2 specification: 0 for none, 2
+// for Zstandard and 3 for ZLIB.
+enum Ktx2Status ktx2_writer_set_supercompression_scheme(struct Ktx2Writer *writer, uint32_t scheme);
+
+// Set the Zstandard compression level. 0 selects zstd's default level.
+enum Ktx2Status ktx2_writer_set_zstd_level(struct Ktx2Writer *writer, int32_t level);
+
+// Set the ZLIB compression level, from 0 to 9.
+enum Ktx2Status ktx2_writer_set_zlib_level(struct Ktx2Writer *writer, uint32_t level);
+
+// Add or replace a key/value pair. String values should include their NUL terminator.
+enum Ktx2Status ktx2_writer_set_key_value(struct Ktx2Writer *writer,
+                                          const char *key,
+                                          const uint8_t *value,
+                                          uintptr_t value_len);
+
+// The size in bytes that a level must be, covering all layers, faces and depth slices.
+enum Ktx2Status ktx2_writer_level_size(const struct Ktx2Writer *writer,
+                                       uint32_t level,
+                                       uint64_t *out);
+
+// Add the next level, largest first. The data is copied.
+enum Ktx2Status ktx2_writer_push_level(struct Ktx2Writer *writer,
+                                       const uint8_t *data,
+                                       uintptr_t len);
+
+// Supercompress the levels and write the file to a new buffer.
+enum Ktx2Status ktx2_writer_write(struct Ktx2Writer *writer, struct Ktx2Buffer **out);
+
+void ktx2_writer_free(struct Ktx2Writer *writer);
+
+struct Ktx2Bc7Options ktx2_bc7_options_default(void);
+
+// Encode a tightly packed RGBA8 image to a BC7 KTX2 file, as `compress-bc7` does.
+enum Ktx2Status ktx2_encode_bc7(const uint8_t *rgba,
+                                uint32_t width,
+                                uint32_t height,
+                                const struct Ktx2Bc7Options *options,
+                                struct Ktx2Buffer **out);
+
+struct Ktx2Bc6hOptions ktx2_bc6h_options_default(void);
+
+// Encode an uncompressed RGBA16F or RGBA32F KTX2 file to BC6H, as `ktx2-bc6h-compress` does.
+enum Ktx2Status ktx2_encode_bc6h(const uint8_t *ktx2_data,
+                                 uintptr_t ktx2_len,
+                                 const struct Ktx2Bc6hOptions *options,
+                                 struct Ktx2Buffer **out);
+
+// Parse a KTX2 file. The data is copied.
+enum Ktx2Status ktx2_info_new(const uint8_t *data, uintptr_t len, struct Ktx2Info **out);
+
+enum Ktx2Status ktx2_info_header(const struct Ktx2Info *info, struct Ktx2Header *out);
+
+// The position of a level in the file. Level 0 is the largest.
+enum Ktx2Status ktx2_info_level(const struct Ktx2Info *info,
+                                uint32_t level,
+                                struct Ktx2LevelInfo *out);
+
+// Look up a key/value pair. The value points into `info` and is valid until it is freed.
+enum Ktx2Status ktx2_info_key_value(const struct Ktx2Info *info,
+                                    const char *key,
+                                    const uint8_t **value_out,
+                                    uintptr_t *value_len_out);
+
+void ktx2_info_free(struct Ktx2Info *info);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KTX2_TOOLS_H */
@@ -0,0 +1,653 @@
+//! A C API for writing, encoding and inspecting KTX2 files. cbindgen generates the header,
+//! `include/ktx2_tools.h`, when the crate is built.
+//!
+//! Every function that can fail returns a [`Ktx2Status`], and a description of the last failure on
+//! the calling thread is available from [`ktx2_last_error_message`]. Handles are returned through
+//! out-pointers and must be released with the matching `_free` function. Data pointers must be
+//! valid for the lengths passed with them, and are only read during the call.
+#![allow(clippy::missing_safety_doc)]
+
+use ktx2_tools::{
+    encode_bc6h, encode_bc7, Bc6hOptions, Bc7Options, Error, TextureKind, WriterBuilder,
+    WriterOptions,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ktx2Status {
+    Ok = 0,
+    /// A required pointer was null, or a value was out of range.
+    InvalidArgument,
+    /// The input isn't a valid KTX2 file.
+    ParseError,
+    UnsupportedFormat,
+    UnsupportedSupercompression,
+    InvalidDimensions,
+    /// A level is missing, out of range or the wrong size.
+    InvalidLevel,
+    CompressionError,
+    EncodingError,
+    DecodingError,
+    InvalidMetadata,
+    IoError,
+    /// The key isn't in the key/value data.
+    NotFound,
+    /// An internal error, which is a bug in the library.
+    Panic,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct Failure {
+    status: Ktx2Status,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: Ktx2Status, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        let status = match error {
+            Error::UnsupportedSupercompression(_) => Ktx2Status::UnsupportedSupercompression,
+            Error::UnsupportedFormat(_) => Ktx2Status::UnsupportedFormat,
+            Error::InvalidDimensions(_) => Ktx2Status::InvalidDimensions,
+            Error::InconsistentLevelCount { .. }
+            | Error::LevelSizeMismatch { .. }
+            | Error::UnexpectedLevel(_)
+            | Error::MissingLevel(_)
+            | Error::PendingLimitExceeded { .. }
+            | Error::LevelOutOfRange(_)
+            | Error::SubresourceOutOfRange { .. } => Ktx2Status::InvalidLevel,
+            Error::Compression(_) => Ktx2Status::CompressionError,
+            Error::Encoding(_) => Ktx2Status::EncodingError,
+            Error::Decoding(_) => Ktx2Status::DecodingError,
+            Error::InvalidMetadata(_) => Ktx2Status::InvalidMetadata,
+            Error::Parse(_) => Ktx2Status::ParseError,
+            Error::Io(_) => Ktx2Status::IoError,
+        };
+
+        Self::new(status, error.to_string())
+    }
+}
+
+impl From<ktx2::ParseError> for Failure {
+    fn from(error: ktx2::ParseError) -> Self {
+        Error::from(error).into()
+    }
+}
+
+/// Run the body of an API function, recording any failure for [`ktx2_last_error_message`].
+fn run(body: impl FnOnce() -> Result<(), Failure>) -> Ktx2Status {
+    let failure = match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => return Ktx2Status::Ok,
+        Ok(Err(failure)) => failure,
+        Err(_) => Failure::new(Ktx2Status::Panic, "internal error"),
+    };
+
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() = CString::new(failure.message).ok();
+    });
+
+    failure.status
+}
+
+fn null_argument(name: &str) -> Failure {
+    Failure::new(
+        Ktx2Status::InvalidArgument,
+        format!("{} must not be null", name),
+    )
+}
+
+unsafe fn slice<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a [u8], Failure> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(null_argument(name)),
+        (false, _) => Ok(std::slice::from_raw_parts(data, len)),
+    }
+}
+
+unsafe fn reference<'a, T>(pointer: *const T, name: &str) -> Result<&'a T, Failure> {
+    pointer.as_ref().ok_or_else(|| null_argument(name))
+}
+
+unsafe fn reference_mut<'a, T>(pointer: *mut T, name: &str) -> Result<&'a mut T, Failure> {
+    pointer.as_mut().ok_or_else(|| null_argument(name))
+}
+
+unsafe fn string<'a>(string: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if string.is_null() {
+        return Err(null_argument(name));
+    }
+
+    CStr::from_ptr(string).to_str().map_err(|_| {
+        Failure::new(
+            Ktx2Status::InvalidArgument,
+            format!("{} isn't valid UTF-8", name),
+        )
+    })
+}
+
+/// Store a value in an out-pointer, checking it first so that nothing leaks if it's null.
+unsafe fn write_out<T>(
+    out: *mut T,
+    value: impl FnOnce() -> Result<T, Failure>,
+) -> Result<(), Failure> {
+    if out.is_null() {
+        return Err(null_argument("out"));
+    }
+
+    out.write(value()?);
+    Ok(())
+}
+
+fn supercompression_scheme(scheme: u32) -> Result<Option<ktx2::SupercompressionScheme>, Failure> {
+    match scheme {
+        0 => Ok(None),
+        scheme => ktx2::SupercompressionScheme::new(scheme)
+            .map(Some)
+            .ok_or_else(|| {
+                Failure::new(
+                    Ktx2Status::InvalidArgument,
+                    format!("unknown supercompression scheme {}", scheme),
+                )
+            }),
+    }
+}
+
+/// The message for the last failure on this thread, or null if nothing has failed. The string
+/// is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn ktx2_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Bytes produced by the library, such as a written KTX2 file.
+pub struct Ktx2Buffer {
+    data: Vec<u8>,
+}
+
+impl Ktx2Buffer {
+    fn into_raw(data: Vec<u8>) -> *mut Self {
+        Box::into_raw(Box::new(Self { data }))
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_buffer_data(buffer: *const Ktx2Buffer) -> *const u8 {
+    buffer
+        .as_ref()
+        .map_or(std::ptr::null(), |buffer| buffer.data.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_buffer_len(buffer: *const Ktx2Buffer) -> usize {
+    buffer.as_ref().map_or(0, |buffer| buffer.data.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_buffer_free(buffer: *mut Ktx2Buffer) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(buffer));
+    }
+}
+
+/// Builds a KTX2 file from uncompressed levels, checking each level's size against the format
+/// and dimensions.
+pub struct Ktx2Writer {
+    /// Always `Some`, but taken while the builder's chained setters run.
+    builder: Option<WriterBuilder<'static>>,
+    key_value_pairs: BTreeMap<String, Vec<u8>>,
+    options: WriterOptions,
+}
+
+impl Ktx2Writer {
+    fn builder(&mut self) -> &mut WriterBuilder<'static> {
+        self.builder.as_mut().unwrap()
+    }
+
+    fn update(&mut self, update: impl FnOnce(WriterBuilder<'static>) -> WriterBuilder<'static>) {
+        self.builder = self.builder.take().map(update);
+    }
+}
+
+/// Create a writer. `pixel_depth`, `layer_count` and `face_count` are as in the KTX2 header: 0
+/// for textures that aren't 3D or arrays, and 1 or 6 faces.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_new(
+    vk_format: u32,
+    pixel_width: u32,
+    pixel_height: u32,
+    pixel_depth: u32,
+    layer_count: u32,
+    face_count: u32,
+    out: *mut *mut Ktx2Writer,
+) -> Ktx2Status {
+    run(|| {
+        write_out(out, || {
+            let format = ktx2::Format::new(vk_format).ok_or_else(|| {
+                Failure::new(Ktx2Status::UnsupportedFormat, "vkFormat must not be 0")
+            })?;
+
+            let kind = TextureKind::from_counts(pixel_depth, layer_count, face_count).ok_or_else(
+                || {
+                    Failure::new(
+                        Ktx2Status::InvalidDimensions,
+                        "inconsistent depth, layer and face counts",
+                    )
+                },
+            )?;
+
+            let builder = WriterBuilder::new(format, pixel_width, pixel_height, kind)?;
+
+            Ok(Box::into_raw(Box::new(Ktx2Writer {
+                builder: Some(builder),
+                key_value_pairs: BTreeMap::new(),
+                options: WriterOptions::default(),
+            })))
+        })
+    })
+}
+
+/// Set the supercompression scheme, using the values from the KTX2 specification: 0 for none, 2
+/// for Zstandard and 3 for ZLIB.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_set_supercompression_scheme(
+    writer: *mut Ktx2Writer,
+    scheme: u32,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference_mut(writer, "writer")?;
+        let scheme = supercompression_scheme(scheme)?;
+        writer.update(|builder| builder.supercompression_scheme(scheme));
+        Ok(())
+    })
+}
+
+/// Set the Zstandard compression level. 0 selects zstd's default level.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_set_zstd_level(
+    writer: *mut Ktx2Writer,
+    level: i32,
+) -> Ktx2Status {
+    run(|| {
+        reference_mut(writer, "writer")?.options.zstd_level = level;
+        Ok(())
+    })
+}
+
+/// Set the ZLIB compression level, from 0 to 9.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_set_zlib_level(
+    writer: *mut Ktx2Writer,
+    level: u32,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference_mut(writer, "writer")?;
+
+        if level > 9 {
+            return Err(Failure::new(
+                Ktx2Status::InvalidArgument,
+                "ZLIB levels range from 0 to 9",
+            ));
+        }
+
+        writer.options.zlib_level = Some(level);
+        Ok(())
+    })
+}
+
+/// Add or replace a key/value pair. String values should include their NUL terminator.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_set_key_value(
+    writer: *mut Ktx2Writer,
+    key: *const c_char,
+    value: *const u8,
+    value_len: usize,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference_mut(writer, "writer")?;
+        let key = string(key, "key")?;
+        let value = slice(value, value_len, "value")?;
+
+        writer
+            .key_value_pairs
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    })
+}
+
+/// The size in bytes that a level must be, covering all layers, faces and depth slices.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_level_size(
+    writer: *const Ktx2Writer,
+    level: u32,
+    out: *mut u64,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference(writer, "writer")?;
+        write_out(out, || {
+            Ok(writer.builder.as_ref().unwrap().level_size(level))
+        })
+    })
+}
+
+/// Add the next level, largest first. The data is copied.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_push_level(
+    writer: *mut Ktx2Writer,
+    data: *const u8,
+    len: usize,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference_mut(writer, "writer")?;
+        let data = slice(data, len, "data")?;
+        writer.builder().push_level(data.to_vec())?;
+        Ok(())
+    })
+}
+
+/// Supercompress the levels and write the file to a new buffer.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_write(
+    writer: *mut Ktx2Writer,
+    out: *mut *mut Ktx2Buffer,
+) -> Ktx2Status {
+    run(|| {
+        let writer = reference_mut(writer, "writer")?;
+        let key_value_pairs = writer.key_value_pairs.clone();
+        let options = writer.options;
+        writer.update(|builder| builder.key_value_pairs(key_value_pairs).options(options));
+
+        write_out(out, || {
+            let mut bytes = Vec::new();
+            writer.builder().writer().write(&mut bytes)?;
+            Ok(Ktx2Buffer::into_raw(bytes))
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_writer_free(writer: *mut Ktx2Writer) {
+    if !writer.is_null() {
+        drop(Box::from_raw(writer));
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Ktx2Bc7Options {
+    /// Whether the image is sRGB-encoded color data.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    /// As for [`ktx2_writer_set_supercompression_scheme`].
+    pub supercompression_scheme: u32,
+    pub zstd_level: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn ktx2_bc7_options_default() -> Ktx2Bc7Options {
+    let options = Bc7Options::default();
+
+    Ktx2Bc7Options {
+        srgb: options.srgb,
+        generate_mipmaps: options.generate_mipmaps,
+        supercompression_scheme: options
+            .supercompression_scheme
+            .map_or(0, |scheme| scheme.value()),
+        zstd_level: 0,
+    }
+}
+
+/// Encode a tightly packed RGBA8 image to a BC7 KTX2 file, as `compress-bc7` does.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_encode_bc7(
+    rgba: *const u8,
+    width: u32,
+    height: u32,
+    options: *const Ktx2Bc7Options,
+    out: *mut *mut Ktx2Buffer,
+) -> Ktx2Status {
+    run(|| {
+        let options = reference(options, "options")?;
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or_else(|| Failure::new(Ktx2Status::InvalidDimensions, "the image is too large"))?;
+        let rgba = slice(rgba, len, "rgba")?;
+
+        write_out(out, || {
+            let image = image::RgbaImage::from_raw(width, height, rgba.to_vec()).unwrap();
+
+            let texture = encode_bc7(
+                &image,
+                &Bc7Options {
+                    srgb: options.srgb,
+                    generate_mipmaps: options.generate_mipmaps,
+                    supercompression_scheme: supercompression_scheme(
+                        options.supercompression_scheme,
+                    )?,
+                },
+            )?;
+
+            let mut bytes = Vec::new();
+            texture
+                .writer(
+                    &BTreeMap::new(),
+                    WriterOptions {
+                        zstd_level: options.zstd_level,
+                        ..Default::default()
+                    },
+                )
+                .write(&mut bytes)?;
+
+            Ok(Ktx2Buffer::into_raw(bytes))
+        })
+    })
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Ktx2Bc6hOptions {
+    /// As for [`ktx2_writer_set_supercompression_scheme`].
+    pub supercompression_scheme: u32,
+    pub zstd_level: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn ktx2_bc6h_options_default() -> Ktx2Bc6hOptions {
+    Ktx2Bc6hOptions {
+        supercompression_scheme: Bc6hOptions::default()
+            .supercompression_scheme
+            .map_or(0, |scheme| scheme.value()),
+        zstd_level: 0,
+    }
+}
+
+/// Encode an uncompressed RGBA16F or RGBA32F KTX2 file to BC6H, as `ktx2-bc6h-compress` does.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_encode_bc6h(
+    ktx2_data: *const u8,
+    ktx2_len: usize,
+    options: *const Ktx2Bc6hOptions,
+    out: *mut *mut Ktx2Buffer,
+) -> Ktx2Status {
+    run(|| {
+        let options = reference(options, "options")?;
+        let input = slice(ktx2_data, ktx2_len, "ktx2_data")?;
+
+        write_out(out, || {
+            let output = encode_bc6h(
+                input,
+                std::io::Cursor::new(Vec::new()),
+                &BTreeMap::new(),
+                &Bc6hOptions {
+                    supercompression_scheme: supercompression_scheme(
+                        options.supercompression_scheme,
+                    )?,
+                },
+                WriterOptions {
+                    zstd_level: options.zstd_level,
+                    ..Default::default()
+                },
+            )?;
+
+            Ok(Ktx2Buffer::into_raw(output.into_inner()))
+        })
+    })
+}
+
+/// A parsed KTX2 file, for querying its header, levels and key/value data.
+pub struct Ktx2Info {
+    reader: ktx2::Reader<Vec<u8>>,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Ktx2Header {
+    /// 0 if the format is only described by the DFD.
+    pub vk_format: u32,
+    pub type_size: u32,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub pixel_depth: u32,
+    pub layer_count: u32,
+    pub face_count: u32,
+    pub level_count: u32,
+    pub supercompression_scheme: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Ktx2LevelInfo {
+    pub byte_offset: u64,
+    pub byte_length: u64,
+    pub uncompressed_byte_length: u64,
+}
+
+/// Parse a KTX2 file. The data is copied.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_info_new(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut Ktx2Info,
+) -> Ktx2Status {
+    run(|| {
+        let data = slice(data, len, "data")?;
+
+        write_out(out, || {
+            Ok(Box::into_raw(Box::new(Ktx2Info {
+                reader: ktx2::Reader::new(data.to_vec())?,
+            })))
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_info_header(
+    info: *const Ktx2Info,
+    out: *mut Ktx2Header,
+) -> Ktx2Status {
+    run(|| {
+        let header = reference(info, "info")?.reader.header();
+
+        write_out(out, || {
+            Ok(Ktx2Header {
+                vk_format: header.format.map_or(0, |format| format.value()),
+                type_size: header.type_size,
+                pixel_width: header.pixel_width,
+                pixel_height: header.pixel_height,
+                pixel_depth: header.pixel_depth,
+                layer_count: header.layer_count,
+                face_count: header.face_count,
+                level_count: header.level_count,
+                supercompression_scheme: header
+                    .supercompression_scheme
+                    .map_or(0, |scheme| scheme.value()),
+            })
+        })
+    })
+}
+
+/// The position of a level in the file. Level 0 is the largest.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_info_level(
+    info: *const Ktx2Info,
+    level: u32,
+    out: *mut Ktx2LevelInfo,
+) -> Ktx2Status {
+    run(|| {
+        let reader = &reference(info, "info")?.reader;
+
+        write_out(out, || {
+            let index = reader
+                .levels()
+                .nth(level as usize)
+                .ok_or(Error::LevelOutOfRange(level))?;
+
+            Ok(Ktx2LevelInfo {
+                byte_offset: (index.data.as_ptr() as usize - reader.data().as_ptr() as usize)
+                    as u64,
+                byte_length: index.data.len() as u64,
+                uncompressed_byte_length: index.uncompressed_byte_length,
+            })
+        })
+    })
+}
+
+/// Look up a key/value pair. The value points into `info` and is valid until it is freed.
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_info_key_value(
+    info: *const Ktx2Info,
+    key: *const c_char,
+    value_out: *mut *const u8,
+    value_len_out: *mut usize,
+) -> Ktx2Status {
+    run(|| {
+        let reader = &reference(info, "info")?.reader;
+        let key = string(key, "key")?;
+
+        if value_len_out.is_null() {
+            return Err(null_argument("value_len_out"));
+        }
+
+        let value = reader
+            .key_value_data()
+            .find(|&(candidate, _)| candidate == key)
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                Failure::new(
+                    Ktx2Status::NotFound,
+                    format!("no value for the {} key", key),
+                )
+            })?;
+
+        write_out(value_out, || Ok(value.as_ptr()))?;
+        value_len_out.write(value.len());
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ktx2_info_free(info: *mut Ktx2Info) {
+    if !info.is_null() {
+        drop(Box::from_raw(info));
+    }
+}
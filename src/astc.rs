@@ -0,0 +1,965 @@
+use crate::{resize_mip_level, EncodedTexture, Error, TextureKind, WriterBuilder};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+/// A 2D ASTC block size of at most 8x8 pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AstcBlockSize {
+    Block4x4,
+    Block5x4,
+    Block5x5,
+    Block6x5,
+    Block6x6,
+    Block8x5,
+    Block8x6,
+    Block8x8,
+}
+
+impl AstcBlockSize {
+    /// The width and height of a block, in pixels.
+    pub fn dimensions(self) -> [u32; 2] {
+        match self {
+            Self::Block4x4 => [4, 4],
+            Self::Block5x4 => [5, 4],
+            Self::Block5x5 => [5, 5],
+            Self::Block6x5 => [6, 5],
+            Self::Block6x6 => [6, 6],
+            Self::Block8x5 => [8, 5],
+            Self::Block8x6 => [8, 6],
+            Self::Block8x8 => [8, 8],
+        }
+    }
+
+    /// The LDR `vkFormat` for this block size.
+    pub fn ktx2_format(self, srgb: bool) -> ktx2::Format {
+        use ktx2::Format;
+
+        match (self, srgb) {
+            (Self::Block4x4, false) => Format::ASTC_4x4_UNORM_BLOCK,
+            (Self::Block4x4, true) => Format::ASTC_4x4_SRGB_BLOCK,
+            (Self::Block5x4, false) => Format::ASTC_5x4_UNORM_BLOCK,
+            (Self::Block5x4, true) => Format::ASTC_5x4_SRGB_BLOCK,
+            (Self::Block5x5, false) => Format::ASTC_5x5_UNORM_BLOCK,
+            (Self::Block5x5, true) => Format::ASTC_5x5_SRGB_BLOCK,
+            (Self::Block6x5, false) => Format::ASTC_6x5_UNORM_BLOCK,
+            (Self::Block6x5, true) => Format::ASTC_6x5_SRGB_BLOCK,
+            (Self::Block6x6, false) => Format::ASTC_6x6_UNORM_BLOCK,
+            (Self::Block6x6, true) => Format::ASTC_6x6_SRGB_BLOCK,
+            (Self::Block8x5, false) => Format::ASTC_8x5_UNORM_BLOCK,
+            (Self::Block8x5, true) => Format::ASTC_8x5_SRGB_BLOCK,
+            (Self::Block8x6, false) => Format::ASTC_8x6_UNORM_BLOCK,
+            (Self::Block8x6, true) => Format::ASTC_8x6_SRGB_BLOCK,
+            (Self::Block8x8, false) => Format::ASTC_8x8_UNORM_BLOCK,
+            (Self::Block8x8, true) => Format::ASTC_8x8_SRGB_BLOCK,
+        }
+    }
+}
+
+impl std::str::FromStr for AstcBlockSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4x4" => Ok(Self::Block4x4),
+            "5x4" => Ok(Self::Block5x4),
+            "5x5" => Ok(Self::Block5x5),
+            "6x5" => Ok(Self::Block6x5),
+            "6x6" => Ok(Self::Block6x6),
+            "8x5" => Ok(Self::Block8x5),
+            "8x6" => Ok(Self::Block8x6),
+            "8x8" => Ok(Self::Block8x8),
+            _ => Err(format!(
+                "unknown block size {:?}, expected 4x4, 5x4, 5x5, 6x5, 6x6, 8x5, 8x6 or 8x8",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AstcOptions {
+    pub block_size: AstcBlockSize,
+    /// Whether the image is sRGB-encoded color data.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for AstcOptions {
+    fn default() -> Self {
+        Self {
+            block_size: AstcBlockSize::Block4x4,
+            srgb: false,
+            generate_mipmaps: true,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+/// Encode an RGBA image to LDR ASTC. Mipmaps are resized from the full image, in linear light if
+/// it is sRGB, down to 1x1, and levels that aren't a whole number of blocks are padded by
+/// repeating their last row and column.
+///
+/// Every block has a single partition with direct RGB or RGBA endpoints, depending on whether it
+/// has any transparent pixels, and the weight grid and quantization that give the smallest error
+/// are chosen for each block. Blocks of a single color are stored as void-extent blocks.
+///
+/// This doesn't use `intel_tex_2::astc`, whose block encoding is `unimplemented!()` as of 0.2.
+pub fn encode_astc(
+    image: &image::RgbaImage,
+    options: &AstcOptions,
+) -> Result<EncodedTexture, Error> {
+    let [block_width, block_height] = options.block_size.dimensions();
+
+    let header = WriterBuilder::new(
+        options.block_size.ktx2_format(options.srgb),
+        image.width(),
+        image.height(),
+        TextureKind::Texture2D,
+    )?
+    .supercompression_scheme(options.supercompression_scheme)
+    .header();
+
+    let level_count = if options.generate_mipmaps {
+        header.max_level_count()
+    } else {
+        1
+    };
+
+    let encoder = BlockEncoder::new(block_width, block_height, options.srgb);
+
+    let levels = (0..level_count)
+        .into_par_iter()
+        .map(|level| {
+            let [width, height, _] = header.level_dimensions(level);
+
+            let resized = if level == 0 {
+                Cow::Borrowed(image)
+            } else {
+                Cow::Owned(resize_mip_level(image, width, height, options.srgb))
+            };
+
+            let blocks_x = width.div_ceil(block_width);
+            let blocks_y = height.div_ceil(block_height);
+
+            (0..blocks_x * blocks_y)
+                .into_par_iter()
+                .flat_map_iter(|i| {
+                    let (x, y) = (i % blocks_x * block_width, i / blocks_x * block_height);
+
+                    // Pixels past the edge of the level repeat its last row and column.
+                    let pixels = (0..block_width * block_height)
+                        .map(|j| {
+                            let px = (x + j % block_width).min(width - 1);
+                            let py = (y + j / block_width).min(height - 1);
+                            resized.get_pixel(px, py).0
+                        })
+                        .collect::<Vec<_>>();
+
+                    encoder.encode(&pixels)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for (i, level) in levels.iter().enumerate() {
+        header.check_level_size(i, level)?;
+    }
+
+    Ok(EncodedTexture {
+        header,
+        dfd_bytes: Vec::new(),
+        sgd_bytes: Vec::new(),
+        levels: levels.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
+/// A range of the integer sequence encoding, with `base` (1, or 3 for trits or 5 for quints)
+/// times two to the power of `bits` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Range {
+    base: u32,
+    bits: u32,
+}
+
+/// Every range, smallest first. Weights can use the first 12 and color endpoints the last 17.
+const RANGES: [Range; 21] = [
+    range(1, 1),
+    range(3, 0),
+    range(1, 2),
+    range(5, 0),
+    range(3, 1),
+    range(1, 3),
+    range(5, 1),
+    range(3, 2),
+    range(1, 4),
+    range(5, 2),
+    range(3, 3),
+    range(1, 5),
+    range(5, 3),
+    range(3, 4),
+    range(1, 6),
+    range(5, 4),
+    range(3, 5),
+    range(1, 7),
+    range(5, 5),
+    range(3, 6),
+    range(1, 8),
+];
+
+const WEIGHT_RANGES: usize = 12;
+const FIRST_ENDPOINT_RANGE: usize = 4;
+
+const fn range(base: u32, bits: u32) -> Range {
+    Range { base, bits }
+}
+
+impl Range {
+    fn levels(self) -> u32 {
+        self.base << self.bits
+    }
+
+    /// The number of bits that `count` values take.
+    fn encoded_bits(self, count: u32) -> u32 {
+        count * self.bits
+            + match self.base {
+                3 => (count * 8).div_ceil(5),
+                5 => (count * 7).div_ceil(3),
+                _ => 0,
+            }
+    }
+}
+
+/// Color endpoint unquantization for trit and quint ranges, indexed by `bits - 1` since endpoint
+/// ranges always have at least 1 bit: the bit pattern of `B`, with `b` to `f` standing for bits 1
+/// to 5 of the value, and `C`.
+const COLOR_TRITS: [(&str, u32); 6] = [
+    ("", 204),
+    ("b000b0bb0", 93),
+    ("cb000cbcb", 44),
+    ("dcb000dcb", 22),
+    ("edcb000ed", 11),
+    ("fedcb000f", 5),
+];
+const COLOR_QUINTS: [(&str, u32); 5] = [
+    ("", 113),
+    ("b0000bb00", 54),
+    ("cb0000cbc", 26),
+    ("dcb0000dc", 13),
+    ("edcb0000e", 6),
+];
+const WEIGHT_TRITS: [(&str, u32); 3] = [("", 50), ("b000b0b", 23), ("cb000cb", 11)];
+const WEIGHT_QUINTS: [(&str, u32); 2] = [("", 28), ("b0000b0", 13)];
+
+/// Lookup tables for the integer sequence encoding, which are built from the decoding rules.
+struct Tables {
+    /// The 8-bit packing of each combination of five trits, `t0 + 3 * t1 + ...`.
+    trits: [u8; 243],
+    /// The 7-bit packing of each combination of three quints.
+    quints: [u8; 125],
+    /// The unquantized 0-255 value of every value of the endpoint ranges.
+    colors: Vec<Vec<u8>>,
+    /// The value of each endpoint range that unquantizes closest to each 0-255 value.
+    nearest_colors: Vec<[u8; 256]>,
+    /// The unquantized 0-64 value of every value of the weight ranges.
+    weights: Vec<Vec<u8>>,
+    /// The value of each weight range that unquantizes closest to each 0-64 value.
+    nearest_weights: Vec<[u8; 65]>,
+}
+
+fn nearest<const N: usize>(unquantized: &[u8]) -> [u8; N] {
+    std::array::from_fn(|target| {
+        (0..unquantized.len())
+            .min_by_key(|&i| (unquantized[i] as i32 - target as i32).abs())
+            .unwrap() as u8
+    })
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+
+    TABLES.get_or_init(|| {
+        let mut trits = [0; 243];
+        let mut quints = [0; 125];
+
+        // Take the smallest packing of each combination, so that trailing zeros in a partial
+        // group only need zero bits.
+        for packed in (0..256).rev() {
+            let index = decode_trits(packed)
+                .iter()
+                .rev()
+                .fold(0, |index, &trit| index * 3 + trit);
+            trits[index as usize] = packed as u8;
+        }
+
+        for packed in (0..128).rev() {
+            let index = decode_quints(packed)
+                .iter()
+                .rev()
+                .fold(0, |index, &quint| index * 5 + quint);
+            quints[index as usize] = packed as u8;
+        }
+
+        let colors = RANGES[FIRST_ENDPOINT_RANGE..]
+            .iter()
+            .map(|&range| {
+                (0..range.levels())
+                    .map(|value| unquantize_color(range, value))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let weights = RANGES[..WEIGHT_RANGES]
+            .iter()
+            .map(|&range| {
+                (0..range.levels())
+                    .map(|value| unquantize_weight(range, value))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        Tables {
+            trits,
+            quints,
+            nearest_colors: colors.iter().map(|colors| nearest(colors)).collect(),
+            colors,
+            nearest_weights: weights.iter().map(|weights| nearest(weights)).collect(),
+            weights,
+        }
+    })
+}
+
+fn decode_trits(packed: u32) -> [u32; 5] {
+    let bit = |i: u32| (packed >> i) & 1;
+    let bits = |high: u32, low: u32| (packed >> low) & ((1 << (high - low + 1)) - 1);
+
+    let (c, t3, t4) = if bits(4, 2) == 0b111 {
+        ((bits(7, 5) << 2) | bits(1, 0), 2, 2)
+    } else if bits(6, 5) == 0b11 {
+        (bits(4, 0), bit(7), 2)
+    } else {
+        (bits(4, 0), bits(6, 5), bit(7))
+    };
+
+    let c_bit = |i: u32| (c >> i) & 1;
+
+    let (t0, t1, t2) = if c & 0b11 == 0b11 {
+        ((c_bit(3) << 1) | (c_bit(2) & !c_bit(3) & 1), c_bit(4), 2)
+    } else if (c >> 2) & 0b11 == 0b11 {
+        (c & 0b11, 2, 2)
+    } else {
+        (
+            (c_bit(1) << 1) | (c_bit(0) & !c_bit(1) & 1),
+            (c >> 2) & 0b11,
+            c_bit(4),
+        )
+    };
+
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(packed: u32) -> [u32; 3] {
+    let bit = |i: u32| (packed >> i) & 1;
+    let bits = |high: u32, low: u32| (packed >> low) & ((1 << (high - low + 1)) - 1);
+
+    if bits(2, 1) == 0b11 && bits(6, 5) == 0 {
+        let not_0 = !bit(0) & 1;
+        return [
+            4,
+            4,
+            (bit(0) << 2) | ((bit(4) & not_0) << 1) | (bit(3) & not_0),
+        ];
+    }
+
+    let (c, q2) = if bits(2, 1) == 0b11 {
+        ((bits(4, 3) << 3) | ((!bits(6, 5) & 0b11) << 1) | bit(0), 4)
+    } else {
+        (bits(4, 0), bits(6, 5))
+    };
+
+    if c & 0b111 == 0b101 {
+        [(c >> 3) & 0b11, 4, q2]
+    } else {
+        [c & 0b111, (c >> 3) & 0b11, q2]
+    }
+}
+
+/// Repeat the low `from` bits of `value` to fill `to` bits.
+fn replicate(value: u32, from: u32, to: u32) -> u32 {
+    let mut result = 0;
+    let mut filled = 0;
+
+    while filled < to {
+        result = (result << from) | value;
+        filled += from;
+    }
+
+    result >> (filled - to)
+}
+
+/// `B` from its bit pattern, most significant bit first.
+fn pattern_bits(pattern: &str, value: u32) -> u32 {
+    pattern.bytes().fold(0, |b, letter| {
+        let bit = match letter {
+            b'0' => 0,
+            letter => (value >> (letter - b'a')) & 1,
+        };
+        (b << 1) | bit
+    })
+}
+
+fn unquantize_color(range: Range, value: u32) -> u8 {
+    let table = match range.base {
+        3 => &COLOR_TRITS[..],
+        5 => &COLOR_QUINTS[..],
+        _ => return replicate(value, range.bits, 8) as u8,
+    };
+
+    let low = value & ((1 << range.bits) - 1);
+    let (pattern, c) = table[range.bits as usize - 1];
+    let a = if low & 1 == 1 { 0x1ff } else { 0 };
+    let t = ((value >> range.bits) * c + pattern_bits(pattern, low)) ^ a;
+
+    ((a & 0x80) | (t >> 2)) as u8
+}
+
+fn unquantize_weight(range: Range, value: u32) -> u8 {
+    let unquantized = match (range.base, range.bits) {
+        (1, bits) => replicate(value, bits, 6),
+        (3, 0) => [0, 32, 63][value as usize],
+        (5, 0) => [0, 16, 32, 47, 63][value as usize],
+        (base, bits) => {
+            let table = if base == 3 {
+                &WEIGHT_TRITS[..]
+            } else {
+                &WEIGHT_QUINTS[..]
+            };
+
+            let low = value & ((1 << bits) - 1);
+            let (pattern, c) = table[bits as usize - 1];
+            let a = if low & 1 == 1 { 0x7f } else { 0 };
+            let t = ((value >> bits) * c + pattern_bits(pattern, low)) ^ a;
+
+            (a & 0x20) | (t >> 2)
+        }
+    };
+
+    (unquantized + (unquantized > 32) as u32) as u8
+}
+
+/// Bits written least significant first.
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        if self.position < 128 {
+            self.bits |= ((value & ((1 << count) - 1)) as u128) << self.position;
+        }
+
+        self.position += count;
+    }
+
+    /// Write `values` with the integer sequence encoding.
+    fn write_sequence(&mut self, range: Range, values: &[u8]) {
+        let tables = tables();
+        let mut sequence = BitWriter::default();
+        let bits = range.bits;
+
+        match range.base {
+            3 => {
+                for group in values.chunks(5) {
+                    let mut trits = [0; 5];
+                    let mut low = [0; 5];
+
+                    for (i, &value) in group.iter().enumerate() {
+                        trits[i] = value as u32 >> bits;
+                        low[i] = value as u32;
+                    }
+
+                    let index = trits.iter().rev().fold(0, |index, &trit| index * 3 + trit);
+                    let packed = tables.trits[index as usize] as u32;
+
+                    for (i, (shift, count)) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)]
+                        .into_iter()
+                        .enumerate()
+                    {
+                        sequence.write(low[i], bits);
+                        sequence.write(packed >> shift, count);
+                    }
+                }
+            }
+            5 => {
+                for group in values.chunks(3) {
+                    let mut quints = [0; 3];
+                    let mut low = [0; 3];
+
+                    for (i, &value) in group.iter().enumerate() {
+                        quints[i] = value as u32 >> bits;
+                        low[i] = value as u32;
+                    }
+
+                    let index = quints
+                        .iter()
+                        .rev()
+                        .fold(0, |index, &quint| index * 5 + quint);
+                    let packed = tables.quints[index as usize] as u32;
+
+                    for (i, (shift, count)) in [(0, 3), (3, 2), (5, 2)].into_iter().enumerate() {
+                        sequence.write(low[i], bits);
+                        sequence.write(packed >> shift, count);
+                    }
+                }
+            }
+            _ => {
+                for &value in values {
+                    sequence.write(value as u32, bits);
+                }
+            }
+        }
+
+        // Drop the padding of a partial last group.
+        let count = range.encoded_bits(values.len() as u32);
+        self.bits |= (sequence.bits & ((1 << count) - 1)) << self.position;
+        self.position += count;
+    }
+}
+
+/// A weight grid and quantization that fits in a block alongside single-partition endpoints.
+struct Config {
+    block_mode: u32,
+    grid_width: u32,
+    grid_height: u32,
+    weight_range: usize,
+    endpoint_range: usize,
+    /// For each pixel, the four grid weights that are blended into its weight and their factors
+    /// out of 16.
+    infill: Vec<[(u8, u8); 4]>,
+}
+
+/// The block mode field for a single-plane weight grid, if it can be encoded.
+fn block_mode(grid_width: u32, grid_height: u32, weight_range: usize) -> Option<u32> {
+    let (high_precision, r) = if weight_range >= 6 {
+        (1, weight_range as u32 - 4)
+    } else {
+        (0, weight_range as u32 + 2)
+    };
+
+    let (x, y) = (grid_width, grid_height);
+    let precision = ((r >> 1) & 0b11) | ((r & 1) << 4) | (high_precision << 9);
+
+    let layout = match (x, y) {
+        (4..=7, 2..=5) => (y - 2) << 5 | (x - 4) << 7,
+        (8..=11, 2..=5) => 0b0100 | (y - 2) << 5 | (x - 8) << 7,
+        (2..=5, 8..=11) => 0b1000 | (x - 2) << 5 | (y - 8) << 7,
+        (2..=5, 6..=7) => 0b1100 | (x - 2) << 5 | (y - 6) << 7,
+        (2..=3, 2..=5) => 0b1100 | (y - 2) << 5 | (x - 2) << 7 | 1 << 8,
+        // The remaining layouts leave no room for the precision bit.
+        (6..=9, 6..=9) if high_precision == 0 => {
+            return Some((r >> 1) << 2 | (r & 1) << 4 | (x - 6) << 5 | 0b10 << 7 | (y - 6) << 9)
+        }
+        _ => return None,
+    };
+
+    Some(layout | precision)
+}
+
+impl Config {
+    /// Every grid and weight range for `values` endpoint values, leaving out those that are no
+    /// better in any way than another.
+    fn all(block_width: u32, block_height: u32, values: u32) -> Vec<Self> {
+        let mut candidates = Vec::new();
+
+        for grid_width in 2..=block_width {
+            for grid_height in 2..=block_height {
+                for (weight_range, range) in RANGES[..WEIGHT_RANGES].iter().enumerate() {
+                    let Some(block_mode) = block_mode(grid_width, grid_height, weight_range) else {
+                        continue;
+                    };
+
+                    let weight_bits = range.encoded_bits(grid_width * grid_height);
+
+                    if !(24..=96).contains(&weight_bits) {
+                        continue;
+                    }
+
+                    // The endpoints get the largest range that fits in the rest of the block,
+                    // after the block mode, partition count and endpoint mode.
+                    let available = 128 - 17 - weight_bits;
+                    let Some(endpoint_range) = RANGES
+                        .iter()
+                        .rposition(|range| range.encoded_bits(values) <= available)
+                    else {
+                        continue;
+                    };
+
+                    // Anything below 20 levels loses too much color.
+                    if endpoint_range < 9 {
+                        continue;
+                    }
+
+                    candidates.push((
+                        [
+                            grid_width,
+                            grid_height,
+                            range.levels(),
+                            RANGES[endpoint_range].levels(),
+                        ],
+                        block_mode,
+                        weight_range,
+                        endpoint_range,
+                    ));
+                }
+            }
+        }
+
+        let dominated = |a: &[u32; 4]| {
+            candidates
+                .iter()
+                .any(|(b, ..)| b != a && a.iter().zip(b).all(|(a, b)| a <= b))
+        };
+
+        candidates
+            .iter()
+            .filter(|(key, ..)| !dominated(key))
+            .map(
+                |&([grid_width, grid_height, ..], block_mode, weight_range, endpoint_range)| Self {
+                    block_mode,
+                    grid_width,
+                    grid_height,
+                    weight_range,
+                    endpoint_range,
+                    infill: infill(block_width, block_height, grid_width, grid_height),
+                },
+            )
+            .collect()
+    }
+}
+
+/// The bilinear weight infill from the specification.
+fn infill(
+    block_width: u32,
+    block_height: u32,
+    grid_width: u32,
+    grid_height: u32,
+) -> Vec<[(u8, u8); 4]> {
+    let ds = (1024 + block_width / 2) / (block_width - 1);
+    let dt = (1024 + block_height / 2) / (block_height - 1);
+
+    (0..block_width * block_height)
+        .map(|i| {
+            let (s, t) = (i % block_width, i / block_width);
+            let gs = (ds * s * (grid_width - 1) + 32) >> 6;
+            let gt = (dt * t * (grid_height - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, gs & 0xf);
+            let (jt, ft) = (gt >> 4, gt & 0xf);
+            let v0 = js + jt * grid_width;
+
+            let w11 = (fs * ft + 8) >> 4;
+            let factors = [16 + w11 - fs - ft, fs - w11, ft - w11, w11];
+            let indices = [v0, v0 + 1, v0 + grid_width, v0 + grid_width + 1];
+
+            // Indices past the edge of the grid always have a factor of 0.
+            std::array::from_fn(|j| {
+                let index = if factors[j] == 0 { v0 } else { indices[j] };
+                (index as u8, factors[j] as u8)
+            })
+        })
+        .collect()
+}
+
+/// A block that was tried, with its squared error.
+struct Candidate {
+    error: f32,
+    block: u128,
+}
+
+struct BlockEncoder {
+    srgb: bool,
+    rgb: Vec<Config>,
+    rgba: Vec<Config>,
+}
+
+impl BlockEncoder {
+    fn new(block_width: u32, block_height: u32, srgb: bool) -> Self {
+        Self {
+            srgb,
+            rgb: Config::all(block_width, block_height, 6),
+            rgba: Config::all(block_width, block_height, 8),
+        }
+    }
+
+    fn encode(&self, pixels: &[[u8; 4]]) -> [u8; 16] {
+        if pixels.iter().all(|pixel| *pixel == pixels[0]) {
+            return void_extent(pixels[0]).to_le_bytes();
+        }
+
+        let channels = if pixels.iter().any(|pixel| pixel[3] != 255) {
+            4
+        } else {
+            3
+        };
+        let configs = if channels == 4 { &self.rgba } else { &self.rgb };
+        let endpoints = principal_endpoints(pixels, channels);
+
+        configs
+            .iter()
+            .map(|config| self.encode_with(config, pixels, channels, endpoints))
+            .min_by(|a, b| a.error.total_cmp(&b.error))
+            .unwrap()
+            .block
+            .to_le_bytes()
+    }
+
+    fn encode_with(
+        &self,
+        config: &Config,
+        pixels: &[[u8; 4]],
+        channels: usize,
+        endpoints: [[f32; 4]; 2],
+    ) -> Candidate {
+        let tables = tables();
+        let colors = &tables.colors[config.endpoint_range - FIRST_ENDPOINT_RANGE];
+        let nearest_colors = &tables.nearest_colors[config.endpoint_range - FIRST_ENDPOINT_RANGE];
+        let weight_levels = &tables.weights[config.weight_range];
+        let nearest_weights = &tables.nearest_weights[config.weight_range];
+
+        let mut quantized =
+            endpoints.map(|endpoint| endpoint.map(|value| nearest_colors[value.round() as usize]));
+
+        // Endpoints whose second color sums to less than the first are swapped and blue
+        // contracted by the decoder.
+        let sum = |endpoint: &[u8; 4]| -> u32 {
+            endpoint[..3]
+                .iter()
+                .map(|&i| colors[i as usize] as u32)
+                .sum()
+        };
+
+        if sum(&quantized[1]) < sum(&quantized[0]) {
+            quantized.swap(0, 1);
+        }
+
+        let decoded = quantized.map(|endpoint| {
+            let mut decoded = endpoint.map(|i| colors[i as usize]);
+
+            if channels == 3 {
+                decoded[3] = 255;
+            }
+
+            decoded
+        });
+
+        // The ideal weight of each pixel, from 0 to 64, projected onto the endpoints.
+        let start = decoded[0].map(|value| value as f32);
+        let direction: [f32; 4] = std::array::from_fn(|c| decoded[1][c] as f32 - start[c]);
+        let length = direction.iter().map(|d| d * d).sum::<f32>();
+
+        let ideal = pixels
+            .iter()
+            .map(|pixel| {
+                if length == 0.0 {
+                    return 0.0;
+                }
+
+                let dot = (0..4)
+                    .map(|c| (pixel[c] as f32 - start[c]) * direction[c])
+                    .sum::<f32>();
+                (dot / length).clamp(0.0, 1.0) * 64.0
+            })
+            .collect::<Vec<_>>();
+
+        let grid = fit_grid(config, &ideal);
+
+        let grid = grid
+            .iter()
+            .map(|&weight| nearest_weights[weight.round() as usize])
+            .collect::<Vec<_>>();
+
+        let error = pixels
+            .iter()
+            .zip(&config.infill)
+            .map(|(pixel, infill)| {
+                let weight = (infill
+                    .iter()
+                    .map(|&(i, factor)| {
+                        weight_levels[grid[i as usize] as usize] as u32 * factor as u32
+                    })
+                    .sum::<u32>()
+                    + 8)
+                    >> 4;
+
+                (0..4)
+                    .map(|c| {
+                        let value = self.interpolate(decoded[0][c], decoded[1][c], weight);
+                        let difference = value - pixel[c] as f32;
+                        difference * difference
+                    })
+                    .sum::<f32>()
+            })
+            .sum::<f32>();
+
+        let values = if channels == 4 {
+            (0..4)
+                .flat_map(|c| [quantized[0][c], quantized[1][c]])
+                .collect::<Vec<_>>()
+        } else {
+            (0..3)
+                .flat_map(|c| [quantized[0][c], quantized[1][c]])
+                .collect()
+        };
+
+        let endpoint_mode = if channels == 4 { 12 } else { 8 };
+
+        let mut block = BitWriter::default();
+        block.write(config.block_mode, 11);
+        // A single partition.
+        block.write(0, 2);
+        block.write(endpoint_mode, 4);
+        block.write_sequence(RANGES[config.endpoint_range], &values);
+
+        // Weights are stored from the top of the block down.
+        let mut weights = BitWriter::default();
+        weights.write_sequence(RANGES[config.weight_range], &grid);
+
+        Candidate {
+            error,
+            block: block.bits | weights.bits.reverse_bits(),
+        }
+    }
+
+    /// Decode a channel as a GPU would, from 0 to 255.
+    fn interpolate(&self, a: u8, b: u8, weight: u32) -> f32 {
+        let expand = |value: u8| {
+            if self.srgb {
+                (value as u32) << 8 | 0x80
+            } else {
+                value as u32 * 257
+            }
+        };
+
+        let value = (expand(a) * (64 - weight) + expand(b) * weight + 32) >> 6;
+        value as f32 / 257.0
+    }
+}
+
+/// Endpoints spanning the pixels along their principal axis.
+fn principal_endpoints(pixels: &[[u8; 4]], channels: usize) -> [[f32; 4]; 2] {
+    let count = pixels.len() as f32;
+    let mut mean = [0.0f32; 4];
+
+    for pixel in pixels {
+        for c in 0..channels {
+            mean[c] += pixel[c] as f32 / count;
+        }
+    }
+
+    let mut covariance = [[0.0f32; 4]; 4];
+
+    for pixel in pixels {
+        for i in 0..channels {
+            for j in 0..channels {
+                covariance[i][j] += (pixel[i] as f32 - mean[i]) * (pixel[j] as f32 - mean[j]);
+            }
+        }
+    }
+
+    // Power iteration, starting from the diagonal of the bounding box.
+    let mut axis = [0.0f32; 4];
+
+    for (c, axis) in axis.iter_mut().enumerate().take(channels) {
+        let (min, max) = pixels.iter().fold((255u8, 0u8), |(min, max), pixel| {
+            (min.min(pixel[c]), max.max(pixel[c]))
+        });
+        *axis = max as f32 - min as f32;
+    }
+
+    for _ in 0..8 {
+        let next: [f32; 4] =
+            std::array::from_fn(|i| (0..4).map(|j| covariance[i][j] * axis[j]).sum());
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+
+        if length == 0.0 {
+            break;
+        }
+
+        axis = next.map(|v| v / length);
+    }
+
+    let (low, high) = pixels
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(low, high), pixel| {
+            let t = (0..channels)
+                .map(|c| (pixel[c] as f32 - mean[c]) * axis[c])
+                .sum::<f32>();
+            (low.min(t), high.max(t))
+        });
+
+    [low, high].map(|t| {
+        let mut endpoint: [f32; 4] =
+            std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0));
+
+        if channels == 3 {
+            endpoint[3] = 255.0;
+        }
+
+        endpoint
+    })
+}
+
+/// Grid weights whose infill best matches the ideal weight of each pixel, before quantization.
+fn fit_grid(config: &Config, ideal: &[f32]) -> Vec<f32> {
+    let grid_count = (config.grid_width * config.grid_height) as usize;
+    let mut grid = vec![32.0f32; grid_count];
+
+    // Start from the weighted average of the pixels each grid weight covers, then correct by the
+    // remaining error a few times.
+    for iteration in 0..4 {
+        let mut sums = vec![0.0f32; grid_count];
+        let mut totals = vec![0.0f32; grid_count];
+
+        for (infill, &ideal) in config.infill.iter().zip(ideal) {
+            let target = if iteration == 0 {
+                ideal
+            } else {
+                ideal
+                    - infill
+                        .iter()
+                        .map(|&(i, factor)| grid[i as usize] * factor as f32 / 16.0)
+                        .sum::<f32>()
+            };
+
+            for &(i, factor) in infill {
+                sums[i as usize] += target * factor as f32;
+                totals[i as usize] += factor as f32;
+            }
+        }
+
+        for ((weight, sum), total) in grid.iter_mut().zip(sums).zip(totals) {
+            if total > 0.0 {
+                *weight = if iteration == 0 {
+                    sum / total
+                } else {
+                    (*weight + sum / total).clamp(0.0, 64.0)
+                };
+            }
+        }
+    }
+
+    grid
+}
+
+/// A block of a single color, which covers the whole image as far as the decoder is concerned.
+fn void_extent(color: [u8; 4]) -> u128 {
+    // The void-extent block mode, LDR, and all ones for the extent coordinates.
+    let mut block = 0x1fc | 0b11 << 10 | ((1u128 << 52) - 1) << 12;
+
+    for (c, &value) in color.iter().enumerate() {
+        block |= (value as u128 * 257) << (64 + c * 16);
+    }
+
+    block
+}
@@ -0,0 +1,425 @@
+use crate::{BasicDfd, EncodedTexture, Error, WriterHeader};
+use basis_universal::{BasisTextureFormat, ColorSpace, Compressor, CompressorParams};
+use std::borrow::Cow;
+
+// Offsets into `basis_file_header` and `basis_slice_desc` (see `basisu_file_headers.h`). All
+// fields are packed little-endian integers.
+const HEADER_SIG: (usize, usize) = (0, 2);
+const HEADER_TOTAL_SLICES: (usize, usize) = (14, 3);
+const HEADER_FLAGS: (usize, usize) = (21, 2);
+const HEADER_TOTAL_ENDPOINTS: (usize, usize) = (39, 2);
+const HEADER_ENDPOINT_CB_FILE_OFS: (usize, usize) = (41, 4);
+const HEADER_ENDPOINT_CB_FILE_SIZE: (usize, usize) = (45, 3);
+const HEADER_TOTAL_SELECTORS: (usize, usize) = (48, 2);
+const HEADER_SELECTOR_CB_FILE_OFS: (usize, usize) = (50, 4);
+const HEADER_SELECTOR_CB_FILE_SIZE: (usize, usize) = (54, 3);
+const HEADER_TABLES_FILE_OFS: (usize, usize) = (57, 4);
+const HEADER_TABLES_FILE_SIZE: (usize, usize) = (61, 4);
+const HEADER_SLICE_DESC_FILE_OFS: (usize, usize) = (65, 4);
+const HEADER_EXTENDED_FILE_OFS: (usize, usize) = (69, 4);
+const HEADER_EXTENDED_FILE_SIZE: (usize, usize) = (73, 4);
+
+const SLICE_DESC_LENGTH: usize = 23;
+const SLICE_IMAGE_INDEX: (usize, usize) = (0, 3);
+const SLICE_LEVEL_INDEX: (usize, usize) = (3, 1);
+const SLICE_FLAGS: (usize, usize) = (4, 1);
+const SLICE_FILE_OFS: (usize, usize) = (13, 4);
+const SLICE_FILE_SIZE: (usize, usize) = (17, 4);
+
+const BASIS_SIGNATURE: u32 = ((b'B' as u32) << 8) | b's' as u32;
+const BASIS_HEADER_FLAG_HAS_ALPHA_SLICES: u32 = 4;
+const BASIS_SLICE_FLAG_HAS_ALPHA: u32 = 1;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Etc1sOptions {
+    /// The ETC1S quality level, from 1 to 255.
+    pub quality_level: u32,
+    /// Whether the images are sRGB-encoded color data.
+    pub srgb: bool,
+    /// Use linear metrics and disable rate distortion optimizations, like `basisu -normal_map`.
+    pub normal_map: bool,
+    pub generate_mipmaps: bool,
+    /// The total number of encoder threads, including the calling thread.
+    pub threads: u32,
+}
+
+impl Default for Etc1sOptions {
+    fn default() -> Self {
+        Self {
+            quality_level: basis_universal::ETC1S_QUALITY_DEFAULT,
+            srgb: true,
+            normal_map: false,
+            generate_mipmaps: true,
+            threads: rayon::current_num_threads() as u32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UastcOptions {
+    /// The UASTC quality level, from 0 (fastest) to 4 (slowest).
+    pub quality_level: u32,
+    /// Enable rate distortion optimization with this lambda. Higher values give smaller files
+    /// after supercompression at the cost of quality.
+    pub rdo_lambda: Option<f32>,
+    /// Whether the images are sRGB-encoded color data.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    /// The total number of encoder threads, including the calling thread.
+    pub threads: u32,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for UastcOptions {
+    fn default() -> Self {
+        Self {
+            quality_level: basis_universal::UASTC_QUALITY_DEFAULT,
+            rdo_lambda: None,
+            srgb: true,
+            generate_mipmaps: true,
+            threads: rayon::current_num_threads() as u32,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+/// Encode RGBA images to ETC1S and package them for BasisLZ supercompression. `images` must all
+/// have the same dimensions and are ordered by layer, then face.
+pub fn encode_etc1s(
+    images: &[image::RgbaImage],
+    face_count: u32,
+    options: &Etc1sOptions,
+) -> Result<EncodedTexture, Error> {
+    if !(basis_universal::ETC1S_QUALITY_MIN..=basis_universal::ETC1S_QUALITY_MAX)
+        .contains(&options.quality_level)
+    {
+        return Err(Error::Encoding(format!(
+            "the ETC1S quality level must be between {} and {}",
+            basis_universal::ETC1S_QUALITY_MIN,
+            basis_universal::ETC1S_QUALITY_MAX
+        )));
+    }
+
+    let mut params = CompressorParams::new();
+    params.set_basis_format(BasisTextureFormat::ETC1S);
+    params.set_etc1s_quality_level(options.quality_level);
+
+    if options.normal_map {
+        params.tune_for_normal_maps();
+    }
+
+    let basis_file = compress(
+        images,
+        face_count,
+        options.srgb,
+        options.generate_mipmaps,
+        options.threads,
+        params,
+    )?;
+    let basis_file = BasisFile::parse(&basis_file)?;
+
+    let (width, height) = images[0].dimensions();
+    let layer_count = images.len() as u32 / face_count;
+    let level_count = basis_file.level_count();
+    let has_alpha = basis_file.flags() & BASIS_HEADER_FLAG_HAS_ALPHA_SLICES != 0;
+
+    // Level data is the RGB and alpha slices of each image in the level, with the image
+    // descriptors in the global data pointing into it.
+    let mut levels = vec![Vec::new(); level_count as usize];
+    let mut image_descs = Vec::new();
+
+    for level in 0..level_count {
+        let level_data = &mut levels[level as usize];
+
+        for image in 0..images.len() as u32 {
+            let rgb = basis_file.slice(image, level, false)?;
+            let alpha = if has_alpha {
+                Some(basis_file.slice(image, level, true)?)
+            } else {
+                None
+            };
+
+            let rgb_offset = level_data.len();
+            level_data.extend_from_slice(rgb);
+            let alpha_offset = level_data.len();
+            level_data.extend_from_slice(alpha.unwrap_or_default());
+
+            // Image flags. Only video P-frames set any, which we never produce.
+            image_descs.extend_from_slice(&0_u32.to_le_bytes());
+            image_descs.extend_from_slice(&(rgb_offset as u32).to_le_bytes());
+            image_descs.extend_from_slice(&(rgb.len() as u32).to_le_bytes());
+            image_descs.extend_from_slice(
+                &(if has_alpha { alpha_offset } else { 0 } as u32).to_le_bytes(),
+            );
+            image_descs
+                .extend_from_slice(&(alpha.map_or(0, |alpha| alpha.len()) as u32).to_le_bytes());
+        }
+    }
+
+    let endpoints =
+        basis_file.section(HEADER_ENDPOINT_CB_FILE_OFS, HEADER_ENDPOINT_CB_FILE_SIZE)?;
+    let selectors =
+        basis_file.section(HEADER_SELECTOR_CB_FILE_OFS, HEADER_SELECTOR_CB_FILE_SIZE)?;
+    let tables = basis_file.section(HEADER_TABLES_FILE_OFS, HEADER_TABLES_FILE_SIZE)?;
+    let extended = basis_file.section(HEADER_EXTENDED_FILE_OFS, HEADER_EXTENDED_FILE_SIZE)?;
+
+    let mut sgd_bytes = Vec::new();
+    sgd_bytes.extend_from_slice(&(basis_file.header(HEADER_TOTAL_ENDPOINTS) as u16).to_le_bytes());
+    sgd_bytes.extend_from_slice(&(basis_file.header(HEADER_TOTAL_SELECTORS) as u16).to_le_bytes());
+    sgd_bytes.extend_from_slice(&(endpoints.len() as u32).to_le_bytes());
+    sgd_bytes.extend_from_slice(&(selectors.len() as u32).to_le_bytes());
+    sgd_bytes.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+    sgd_bytes.extend_from_slice(&(extended.len() as u32).to_le_bytes());
+    sgd_bytes.extend_from_slice(&image_descs);
+    sgd_bytes.extend_from_slice(endpoints);
+    sgd_bytes.extend_from_slice(selectors);
+    sgd_bytes.extend_from_slice(tables);
+    sgd_bytes.extend_from_slice(extended);
+
+    Ok(EncodedTexture {
+        header: WriterHeader {
+            format: None,
+            type_size: 1,
+            pixel_width: width,
+            pixel_height: height,
+            pixel_depth: 0,
+            layer_count: if layer_count > 1 { layer_count } else { 0 },
+            face_count,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::BasisLZ),
+        },
+        dfd_bytes: BasicDfd::etc1s(options.srgb, has_alpha).to_bytes(),
+        sgd_bytes,
+        levels: levels.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
+/// Encode RGBA images to UASTC. `images` must all have the same dimensions and are ordered by
+/// layer, then face. The levels are left uncompressed for the [`Writer`](crate::Writer) to supercompress.
+pub fn encode_uastc(
+    images: &[image::RgbaImage],
+    face_count: u32,
+    options: &UastcOptions,
+) -> Result<EncodedTexture, Error> {
+    if !(basis_universal::UASTC_QUALITY_MIN..=basis_universal::UASTC_QUALITY_MAX)
+        .contains(&options.quality_level)
+    {
+        return Err(Error::Encoding(format!(
+            "the UASTC quality level must be between {} and {}",
+            basis_universal::UASTC_QUALITY_MIN,
+            basis_universal::UASTC_QUALITY_MAX
+        )));
+    }
+
+    if let Some(lambda) = options.rdo_lambda {
+        if !(lambda > 0.0 && lambda.is_finite()) {
+            return Err(Error::Encoding(format!(
+                "the UASTC RDO lambda must be positive, got {}",
+                lambda
+            )));
+        }
+    }
+
+    let mut params = CompressorParams::new();
+    params.set_basis_format(BasisTextureFormat::UASTC4x4);
+    params.set_uastc_quality_level(options.quality_level);
+    params.set_rdo_uastc(options.rdo_lambda);
+
+    let basis_file = compress(
+        images,
+        face_count,
+        options.srgb,
+        options.generate_mipmaps,
+        options.threads,
+        params,
+    )?;
+    let basis_file = BasisFile::parse(&basis_file)?;
+
+    let (width, height) = images[0].dimensions();
+    let layer_count = images.len() as u32 / face_count;
+    let level_count = basis_file.level_count();
+    // UASTC stores alpha in the same blocks as color, but the slices are still flagged.
+    let has_alpha = basis_file.flags() & BASIS_HEADER_FLAG_HAS_ALPHA_SLICES != 0;
+
+    let levels = (0..level_count)
+        .map(|level| {
+            let mut level_data = Vec::new();
+
+            for image in 0..images.len() as u32 {
+                level_data.extend_from_slice(basis_file.slice(image, level, has_alpha)?);
+            }
+
+            Ok(Cow::Owned(level_data))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut dfd = BasicDfd::uastc(options.srgb, has_alpha);
+
+    if options.supercompression_scheme.is_some() {
+        dfd.header.bytes_planes = [0; 8];
+    }
+
+    Ok(EncodedTexture {
+        header: WriterHeader {
+            format: None,
+            type_size: 1,
+            pixel_width: width,
+            pixel_height: height,
+            pixel_depth: 0,
+            layer_count: if layer_count > 1 { layer_count } else { 0 },
+            face_count,
+            supercompression_scheme: options.supercompression_scheme,
+        },
+        dfd_bytes: dfd.to_bytes(),
+        sgd_bytes: Vec::new(),
+        levels,
+    })
+}
+
+fn compress(
+    images: &[image::RgbaImage],
+    face_count: u32,
+    srgb: bool,
+    generate_mipmaps: bool,
+    threads: u32,
+    mut params: CompressorParams,
+) -> Result<Vec<u8>, Error> {
+    let first = images
+        .first()
+        .ok_or_else(|| Error::Encoding("no images to encode".to_string()))?;
+
+    if face_count == 0
+        || !(images.len() as u32).is_multiple_of(face_count)
+        || images
+            .iter()
+            .any(|image| image.dimensions() != first.dimensions())
+    {
+        return Err(Error::Encoding(format!(
+            "expected {} faces per layer, all with the same dimensions",
+            face_count
+        )));
+    }
+
+    let color_space = if srgb {
+        ColorSpace::Srgb
+    } else {
+        ColorSpace::Linear
+    };
+
+    params.set_color_space(color_space);
+    params.set_mip_color_space(color_space);
+    params.set_generate_mipmaps(generate_mipmaps);
+
+    for (i, image) in images.iter().enumerate() {
+        params
+            .source_image_mut(i as u32)
+            .init(image, image.width(), image.height(), 4);
+    }
+
+    let mut compressor = Compressor::new(threads.max(1));
+
+    // Safety: the parameters are validated above and all source images were initialized from
+    // correctly-sized RGBA buffers.
+    unsafe {
+        if !compressor.init(&params) {
+            return Err(Error::Encoding(
+                "failed to initialize the Basis Universal encoder".to_string(),
+            ));
+        }
+
+        compressor.process().map_err(|error| {
+            Error::Encoding(format!("Basis Universal encoding failed: {:?}", error))
+        })?;
+    }
+
+    Ok(compressor.basis_file().to_vec())
+}
+
+/// A minimal reader for the `.basis` files produced by the encoder.
+pub(crate) struct BasisFile<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BasisFile<'a> {
+    pub(crate) fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let file = Self { bytes };
+
+        if bytes.len() < HEADER_EXTENDED_FILE_SIZE.0 + HEADER_EXTENDED_FILE_SIZE.1
+            || file.header(HEADER_SIG) != BASIS_SIGNATURE
+        {
+            return Err(Error::Encoding(
+                "the encoder produced an invalid .basis file".to_string(),
+            ));
+        }
+
+        Ok(file)
+    }
+
+    pub(crate) fn header(&self, field: (usize, usize)) -> u32 {
+        read_packed(&self.bytes[field.0..field.0 + field.1])
+    }
+
+    pub(crate) fn flags(&self) -> u32 {
+        self.header(HEADER_FLAGS)
+    }
+
+    fn section(&self, offset: (usize, usize), size: (usize, usize)) -> Result<&'a [u8], Error> {
+        let offset = self.header(offset) as usize;
+        let size = self.header(size) as usize;
+
+        self.bytes
+            .get(offset..offset + size)
+            .ok_or_else(|| Error::Encoding("a .basis file section is out of bounds".to_string()))
+    }
+
+    fn slice_descs(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let start = self.header(HEADER_SLICE_DESC_FILE_OFS) as usize;
+        let count = self.header(HEADER_TOTAL_SLICES) as usize;
+
+        self.bytes
+            .get(start..start + count * SLICE_DESC_LENGTH)
+            .unwrap_or_default()
+            .chunks_exact(SLICE_DESC_LENGTH)
+    }
+
+    pub(crate) fn level_count(&self) -> u32 {
+        self.slice_descs()
+            .map(|desc| read_field(desc, SLICE_LEVEL_INDEX) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The compressed data for one mip level of one source image.
+    pub(crate) fn slice(&self, image: u32, level: u32, alpha: bool) -> Result<&'a [u8], Error> {
+        let desc = self
+            .slice_descs()
+            .find(|desc| {
+                read_field(desc, SLICE_IMAGE_INDEX) == image
+                    && read_field(desc, SLICE_LEVEL_INDEX) == level
+                    && (read_field(desc, SLICE_FLAGS) & BASIS_SLICE_FLAG_HAS_ALPHA != 0) == alpha
+            })
+            .ok_or_else(|| {
+                Error::Encoding(format!(
+                    "the .basis file is missing level {} of image {}",
+                    level, image
+                ))
+            })?;
+
+        let offset = read_field(desc, SLICE_FILE_OFS) as usize;
+        let size = read_field(desc, SLICE_FILE_SIZE) as usize;
+
+        self.bytes
+            .get(offset..offset + size)
+            .ok_or_else(|| Error::Encoding("a .basis slice is out of bounds".to_string()))
+    }
+}
+
+fn read_field(bytes: &[u8], field: (usize, usize)) -> u32 {
+    read_packed(&bytes[field.0..field.0 + field.1])
+}
+
+fn read_packed(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | byte as u32)
+}
@@ -0,0 +1,345 @@
+use crate::{
+    decompress_level, resize_mip_level, EncodedTexture, Error, TextureKind, WriterBuilder,
+    WriterHeader, WriterOptions,
+};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{Seek, Write};
+
+/// A block-compressed format that [`encode_bc`] can produce from 8-bit images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BcFormat {
+    /// Opaque color. Alpha is ignored.
+    Bc1,
+    /// Color with interpolated alpha.
+    Bc3,
+    /// A single channel, taken from red.
+    Bc4,
+    /// Two channels, taken from red and green, such as normal maps.
+    Bc5,
+    /// Color with or without alpha, at a higher quality than BC1 or BC3.
+    Bc7,
+}
+
+impl BcFormat {
+    /// The `vkFormat` for this format. BC4 and BC5 have no sRGB variants, so `None` is returned
+    /// for them when `srgb` is set.
+    pub fn ktx2_format(self, srgb: bool) -> Option<ktx2::Format> {
+        use ktx2::Format;
+
+        Some(match (self, srgb) {
+            (Self::Bc1, false) => Format::BC1_RGB_UNORM_BLOCK,
+            (Self::Bc1, true) => Format::BC1_RGB_SRGB_BLOCK,
+            (Self::Bc3, false) => Format::BC3_UNORM_BLOCK,
+            (Self::Bc3, true) => Format::BC3_SRGB_BLOCK,
+            (Self::Bc4, false) => Format::BC4_UNORM_BLOCK,
+            (Self::Bc5, false) => Format::BC5_UNORM_BLOCK,
+            (Self::Bc7, false) => Format::BC7_UNORM_BLOCK,
+            (Self::Bc7, true) => Format::BC7_SRGB_BLOCK,
+            (Self::Bc4 | Self::Bc5, true) => return None,
+        })
+    }
+}
+
+impl std::str::FromStr for BcFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "bc1" => Ok(Self::Bc1),
+            "bc3" => Ok(Self::Bc3),
+            "bc4" => Ok(Self::Bc4),
+            "bc5" => Ok(Self::Bc5),
+            "bc7" => Ok(Self::Bc7),
+            _ => Err(format!(
+                "unknown format {:?}, expected bc1, bc3, bc4, bc5 or bc7",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BcOptions {
+    pub format: BcFormat,
+    /// Whether the image is sRGB-encoded color data. Not allowed for BC4 and BC5.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for BcOptions {
+    fn default() -> Self {
+        Self {
+            format: BcFormat::Bc7,
+            srgb: false,
+            generate_mipmaps: true,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bc7Options {
+    /// Whether the image is sRGB-encoded color data.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for Bc7Options {
+    fn default() -> Self {
+        Self {
+            srgb: false,
+            generate_mipmaps: true,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bc6hOptions {
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for Bc6hOptions {
+    fn default() -> Self {
+        Self {
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+/// Encode an RGBA image to BC7, using the slow alpha or opaque settings for each level depending
+/// on whether any of its pixels are transparent. Mipmaps are resized from the full image, in
+/// linear light if it is sRGB, down to 4x4.
+pub fn encode_bc7(image: &image::RgbaImage, options: &Bc7Options) -> Result<EncodedTexture, Error> {
+    encode_bc(
+        image,
+        &BcOptions {
+            format: BcFormat::Bc7,
+            srgb: options.srgb,
+            generate_mipmaps: options.generate_mipmaps,
+            supercompression_scheme: options.supercompression_scheme,
+        },
+    )
+}
+
+/// Encode an RGBA image to BC1, BC3, BC4, BC5 or BC7. BC7 uses the slow alpha or opaque settings
+/// for each level depending on whether any of its pixels are transparent. Mipmaps are resized
+/// from the full image, in linear light if it is sRGB, down to 4x4, and levels that aren't a whole
+/// number of blocks, including images smaller than 4x4, are padded by repeating their last row
+/// and column.
+pub fn encode_bc(image: &image::RgbaImage, options: &BcOptions) -> Result<EncodedTexture, Error> {
+    let format = options
+        .format
+        .ktx2_format(options.srgb)
+        .ok_or_else(|| Error::Encoding("BC4 and BC5 can't be sRGB".to_string()))?;
+
+    let header = WriterBuilder::new(
+        format,
+        image.width(),
+        image.height(),
+        TextureKind::Texture2D,
+    )?
+    .supercompression_scheme(options.supercompression_scheme)
+    .header();
+
+    // Mipmaps stop at the first level smaller than a block.
+    let level_count = if options.generate_mipmaps {
+        1 + (1..header.max_level_count())
+            .take_while(|&level| {
+                let [width, height, _] = header.level_dimensions(level);
+                width >= 4 && height >= 4
+            })
+            .count() as u32
+    } else {
+        1
+    };
+
+    let levels = (0..level_count)
+        .into_par_iter()
+        .map(|level| {
+            let [width, height, _] = header.level_dimensions(level);
+            let resized = pad_to_blocks(resize_mip_level(image, width, height, options.srgb));
+
+            let surface = intel_tex_2::RgbaSurface {
+                data: &resized,
+                width: resized.width(),
+                height: resized.height(),
+                stride: resized.width() * 4,
+            };
+
+            match options.format {
+                BcFormat::Bc1 => intel_tex_2::bc1::compress_blocks(&surface),
+                BcFormat::Bc3 => intel_tex_2::bc3::compress_blocks(&surface),
+                BcFormat::Bc4 => intel_tex_2::bc4::compress_blocks(&surface),
+                BcFormat::Bc5 => intel_tex_2::bc5::compress_blocks(&surface),
+                BcFormat::Bc7 => {
+                    let settings = if resized.pixels().any(|pixel| pixel[3] != 255) {
+                        intel_tex_2::bc7::alpha_slow_settings()
+                    } else {
+                        intel_tex_2::bc7::opaque_slow_settings()
+                    };
+
+                    intel_tex_2::bc7::compress_blocks(&settings, &surface)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (i, level) in levels.iter().enumerate() {
+        header.check_level_size(i, level)?;
+    }
+
+    Ok(EncodedTexture {
+        header,
+        dfd_bytes: Vec::new(),
+        sgd_bytes: Vec::new(),
+        levels: levels.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
+/// Extend an image to a whole number of 4x4 blocks by repeating its last row and column.
+fn pad_to_blocks(image: image::RgbaImage) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let (padded_width, padded_height) = (width.div_ceil(4) * 4, height.div_ceil(4) * 4);
+
+    if (padded_width, padded_height) == (width, height) {
+        return image;
+    }
+
+    image::RgbaImage::from_fn(padded_width, padded_height, |x, y| {
+        *image.get_pixel(x.min(width - 1), y.min(height - 1))
+    })
+}
+
+/// Encode an uncompressed RGBA16F or RGBA32F KTX2 file to BC6H, writing each level to `writer`
+/// as soon as it has been encoded, smallest first, so that only one level is held in memory at a
+/// time. Levels that aren't a whole number of blocks, including those smaller than 4x4, are padded
+/// by repeating their last row and column.
+pub fn encode_bc6h<W: Write + Seek>(
+    input: &[u8],
+    writer: W,
+    key_value_pairs: &BTreeMap<String, Vec<u8>>,
+    options: &Bc6hOptions,
+    writer_options: WriterOptions,
+) -> Result<W, Error> {
+    let ktx2 = ktx2::Reader::new(input)?;
+
+    let header = ktx2.header();
+    let input_header = WriterHeader::from(header);
+
+    let convert_to_half = match header.format {
+        Some(ktx2::Format::R16G16B16A16_SFLOAT) => false,
+        Some(ktx2::Format::R32G32B32A32_SFLOAT) => true,
+        _ => return Err(Error::UnsupportedFormat(header.format)),
+    };
+
+    let kind = TextureKind::from_counts(header.pixel_depth, header.layer_count, header.face_count)
+        .ok_or_else(|| {
+            Error::InvalidDimensions("inconsistent depth, layer and face counts".to_string())
+        })?;
+
+    let builder = WriterBuilder::new(
+        ktx2::Format::BC6H_UFLOAT_BLOCK,
+        header.pixel_width,
+        header.pixel_height,
+        kind,
+    )?
+    .supercompression_scheme(options.supercompression_scheme)
+    .key_value_pairs(key_value_pairs.clone())
+    .options(writer_options);
+
+    // A level count of 0 asks readers to generate mipmaps, which can't be done for BC6H.
+    let num_levels = header.level_count.clamp(1, builder.max_level_count());
+
+    let mut writer = builder.streaming_writer(writer, num_levels as usize)?;
+
+    let levels = ktx2.levels().take(num_levels as usize).collect::<Vec<_>>();
+
+    // Levels are written smallest first so that only one level is held in memory at a time.
+    for (i, level) in levels.iter().enumerate().rev() {
+        let mut level_bytes = decompress_level(
+            header.supercompression_scheme,
+            level.data,
+            level.uncompressed_byte_length,
+        )?;
+
+        // The level is split into images below, so it must be exactly the size the header implies.
+        let expected = input_header.level_size(i as u32).ok_or_else(|| {
+            Error::InvalidDimensions(format!("the size of level {} overflows", i))
+        })?;
+
+        if level_bytes.len() as u64 != expected {
+            return Err(Error::LevelSizeMismatch {
+                level: i,
+                expected,
+                actual: level_bytes.len() as u64,
+            });
+        }
+
+        if convert_to_half {
+            level_bytes = Cow::Owned(
+                level_bytes
+                    .chunks(4)
+                    .flat_map(|bytes| {
+                        half::f16::from_f32(f32::from_le_bytes(<[u8; 4]>::try_from(bytes).unwrap()))
+                            .to_le_bytes()
+                    })
+                    .collect(),
+            );
+        }
+
+        let [width, height, depth] = builder.header().level_dimensions(i as u32);
+        let image_count =
+            header.layer_count.max(1) as usize * header.face_count as usize * depth as usize;
+
+        let mut compressed = Vec::new();
+
+        for chunk in level_bytes.chunks(level_bytes.len() / image_count) {
+            let (padded_width, padded_height) = (width.div_ceil(4) * 4, height.div_ceil(4) * 4);
+            let padded = pad_rgba16f_to_blocks(chunk, width, height);
+
+            let compressed_chunk = intel_tex_2::bc6h::compress_blocks(
+                &intel_tex_2::bc6h::very_slow_settings(),
+                &intel_tex_2::RgbaSurface {
+                    width: padded_width,
+                    height: padded_height,
+                    stride: padded_width * 8,
+                    data: &padded,
+                },
+            );
+
+            compressed.extend_from_slice(&compressed_chunk);
+        }
+
+        writer.write_level(i, &compressed)?;
+    }
+
+    writer.finish()
+}
+
+/// Extend an RGBA16F image to a whole number of 4x4 blocks by repeating its last row and column.
+fn pad_rgba16f_to_blocks(data: &[u8], width: u32, height: u32) -> Cow<'_, [u8]> {
+    let (width, height) = (width as usize, height as usize);
+    let (padded_width, padded_height) = (width.div_ceil(4) * 4, height.div_ceil(4) * 4);
+
+    if (padded_width, padded_height) == (width, height) {
+        return Cow::Borrowed(data);
+    }
+
+    let mut padded = Vec::with_capacity(padded_width * padded_height * 8);
+
+    for y in 0..padded_height {
+        let row = &data[y.min(height - 1) * width * 8..][..width * 8];
+        padded.extend_from_slice(row);
+
+        for _ in width..padded_width {
+            padded.extend_from_slice(&row[(width - 1) * 8..]);
+        }
+    }
+
+    Cow::Owned(padded)
+}
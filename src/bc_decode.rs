@@ -0,0 +1,1091 @@
+use crate::Error;
+
+/// Pixels decoded from a block-compressed image, in rows from the top, cropped to the image's
+/// dimensions. Channels that a format doesn't store are 0, or 1 for alpha.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodedPixels {
+    Rgba8(Vec<u8>),
+    RgbaF32(Vec<f32>),
+}
+
+impl DecodedPixels {
+    /// The pixels as RGBA8, clamping float values to 0-1.
+    pub fn into_rgba8(self) -> Vec<u8> {
+        match self {
+            Self::Rgba8(pixels) => pixels,
+            Self::RgbaF32(pixels) => pixels
+                .into_iter()
+                .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect(),
+        }
+    }
+
+    /// The pixels as RGBA f32, with 8-bit values scaled to 0-1.
+    pub fn into_rgba_f32(self) -> Vec<f32> {
+        match self {
+            Self::Rgba8(pixels) => pixels
+                .into_iter()
+                .map(|value| value as f32 / 255.0)
+                .collect(),
+            Self::RgbaF32(pixels) => pixels,
+        }
+    }
+}
+
+/// Decode a BC1-BC7 image of `width` by `height` pixels from its blocks, which are stored in
+/// rows from the top left.
+///
+/// Formats normalized to 0-1 decode to [`DecodedPixels::Rgba8`], and the signed BC4 and BC5
+/// formats and BC6H to [`DecodedPixels::RgbaF32`]. Decoding follows the D3D11 specification, and
+/// is bit-exact for BC6H and BC7. Reserved BC6H and BC7 modes decode to black, with alpha 1 for
+/// BC6H and 0 for BC7.
+pub fn decode_bc(
+    format: ktx2::Format,
+    data: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<DecodedPixels, Error> {
+    use ktx2::Format;
+
+    match format {
+        Format::BC1_RGB_UNORM_BLOCK
+        | Format::BC1_RGB_SRGB_BLOCK
+        | Format::BC1_RGBA_UNORM_BLOCK
+        | Format::BC1_RGBA_SRGB_BLOCK => {
+            decode_blocks(data, width, height, |block| bc1(block, false)).map(DecodedPixels::Rgba8)
+        }
+        Format::BC2_UNORM_BLOCK | Format::BC2_SRGB_BLOCK => {
+            decode_blocks(data, width, height, bc2).map(DecodedPixels::Rgba8)
+        }
+        Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK => {
+            decode_blocks(data, width, height, bc3).map(DecodedPixels::Rgba8)
+        }
+        Format::BC4_UNORM_BLOCK => decode_blocks(data, width, height, |block: &[u8; 8]| {
+            bc4_unorm(block).map(|red| [red, 0, 0, 255])
+        })
+        .map(DecodedPixels::Rgba8),
+        Format::BC4_SNORM_BLOCK => decode_blocks(data, width, height, |block: &[u8; 8]| {
+            bc4_snorm(block).map(|red| [red, 0.0, 0.0, 1.0])
+        })
+        .map(DecodedPixels::RgbaF32),
+        Format::BC5_UNORM_BLOCK => decode_blocks(data, width, height, |block: &[u8; 16]| {
+            let (red, green) = split_bc5(block);
+            let (red, green) = (bc4_unorm(red), bc4_unorm(green));
+            std::array::from_fn(|i| [red[i], green[i], 0, 255])
+        })
+        .map(DecodedPixels::Rgba8),
+        Format::BC5_SNORM_BLOCK => decode_blocks(data, width, height, |block: &[u8; 16]| {
+            let (red, green) = split_bc5(block);
+            let (red, green) = (bc4_snorm(red), bc4_snorm(green));
+            std::array::from_fn(|i| [red[i], green[i], 0.0, 1.0])
+        })
+        .map(DecodedPixels::RgbaF32),
+        Format::BC6H_UFLOAT_BLOCK => decode_blocks(data, width, height, |block| bc6h(block, false))
+            .map(DecodedPixels::RgbaF32),
+        Format::BC6H_SFLOAT_BLOCK => decode_blocks(data, width, height, |block| bc6h(block, true))
+            .map(DecodedPixels::RgbaF32),
+        Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK => {
+            decode_blocks(data, width, height, bc7).map(DecodedPixels::Rgba8)
+        }
+        _ => Err(Error::UnsupportedFormat(Some(format))),
+    }
+}
+
+/// Decode every 4x4 block of an image with `decode_block`, which returns the block's pixels in
+/// rows from the top.
+fn decode_blocks<const N: usize, T: Copy + Default>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    decode_block: impl Fn(&[u8; N]) -> [[T; 4]; 16],
+) -> Result<Vec<T>, Error> {
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = width.div_ceil(4);
+    let expected = blocks_wide * height.div_ceil(4) * N;
+
+    if data.len() < expected {
+        return Err(Error::InvalidDimensions(format!(
+            "a {}x{} image needs {} bytes of blocks but only {} were given",
+            width,
+            height,
+            expected,
+            data.len()
+        )));
+    }
+
+    let mut pixels = vec![T::default(); width * height * 4];
+
+    for (i, block) in data[..expected].chunks_exact(N).enumerate() {
+        let (block_x, block_y) = (i % blocks_wide * 4, i / blocks_wide * 4);
+        let decoded = decode_block(block.try_into().unwrap());
+
+        for (j, pixel) in decoded.iter().enumerate() {
+            let (x, y) = (block_x + j % 4, block_y + j / 4);
+
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+fn split_bc5(block: &[u8; 16]) -> (&[u8; 8], &[u8; 8]) {
+    let (red, green) = block.split_at(8);
+    (red.try_into().unwrap(), green.try_into().unwrap())
+}
+
+fn rgb565(color: u16) -> [u8; 3] {
+    let (red, green, blue) = (color >> 11, (color >> 5) & 63, color & 31);
+
+    [
+        (red << 3 | red >> 2) as u8,
+        (green << 2 | green >> 4) as u8,
+        (blue << 3 | blue >> 2) as u8,
+    ]
+}
+
+/// BC1 color, which is also the color half of BC2 and BC3 blocks. Those always use four colors,
+/// while BC1 uses three colors and transparent black when the first endpoint isn't greater.
+fn bc1(block: &[u8; 8], four_colors: bool) -> [[u8; 4]; 16] {
+    let color_0 = u16::from_le_bytes([block[0], block[1]]);
+    let color_1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(color_0), rgb565(color_1));
+
+    let mix = |weight_a: u32, weight_b: u32| {
+        let total = weight_a + weight_b;
+        std::array::from_fn::<u8, 3, _>(|c| {
+            ((a[c] as u32 * weight_a + b[c] as u32 * weight_b) / total) as u8
+        })
+    };
+
+    let with_alpha = |[red, green, blue]: [u8; 3]| [red, green, blue, 255];
+
+    let palette = if four_colors || color_0 > color_1 {
+        [
+            with_alpha(a),
+            with_alpha(b),
+            with_alpha(mix(2, 1)),
+            with_alpha(mix(1, 2)),
+        ]
+    } else {
+        [with_alpha(a), with_alpha(b), with_alpha(mix(1, 1)), [0; 4]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    std::array::from_fn(|i| palette[(indices >> (i * 2)) as usize & 3])
+}
+
+fn bc2(block: &[u8; 16]) -> [[u8; 4]; 16] {
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    let mut pixels = bc1(block[8..].try_into().unwrap(), true);
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        pixel[3] = ((alpha >> (i * 4)) & 15) as u8 * 17;
+    }
+
+    pixels
+}
+
+fn bc3(block: &[u8; 16]) -> [[u8; 4]; 16] {
+    let alpha = bc4_unorm(block[..8].try_into().unwrap());
+    let mut pixels = bc1(block[8..].try_into().unwrap(), true);
+
+    for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+        pixel[3] = alpha;
+    }
+
+    pixels
+}
+
+/// The 3-bit indices of a BC4 block.
+fn bc4_indices(block: &[u8; 8]) -> [usize; 16] {
+    let bits = u64::from_le_bytes(*block) >> 16;
+    std::array::from_fn(|i| ((bits >> (i * 3)) & 7) as usize)
+}
+
+fn bc4_unorm(block: &[u8; 8]) -> [u8; 16] {
+    let (a, b) = (block[0] as u32, block[1] as u32);
+
+    let palette: [u8; 8] = if a > b {
+        std::array::from_fn(|i| match i {
+            0 => a as u8,
+            1 => b as u8,
+            _ => ((a * (8 - i as u32) + b * (i as u32 - 1)) / 7) as u8,
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => a as u8,
+            1 => b as u8,
+            6 => 0,
+            7 => 255,
+            _ => ((a * (6 - i as u32) + b * (i as u32 - 1)) / 5) as u8,
+        })
+    };
+
+    bc4_indices(block).map(|index| palette[index])
+}
+
+fn bc4_snorm(block: &[u8; 8]) -> [f32; 16] {
+    let (a, b) = (block[0] as i8, block[1] as i8);
+    // -128 and -127 both mean -1.
+    let (a_f, b_f) = (a.max(-127) as f32 / 127.0, b.max(-127) as f32 / 127.0);
+
+    let palette: [f32; 8] = if a > b {
+        std::array::from_fn(|i| match i {
+            0 => a_f,
+            1 => b_f,
+            _ => (a_f * (8 - i) as f32 + b_f * (i - 1) as f32) / 7.0,
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => a_f,
+            1 => b_f,
+            6 => -1.0,
+            7 => 1.0,
+            _ => (a_f * (6 - i) as f32 + b_f * (i - 1) as f32) / 5.0,
+        })
+    };
+
+    bc4_indices(block).map(|index| palette[index])
+}
+
+/// Reads the bits of a 128-bit block from the least significant bit up.
+struct BitReader {
+    bits: u128,
+}
+
+impl BitReader {
+    fn new(block: &[u8; 16]) -> Self {
+        Self {
+            bits: u128::from_le_bytes(*block),
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits & ((1 << count) - 1)) as u32;
+        self.bits >>= count;
+        value
+    }
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+/// Which subset each pixel belongs to for the 64 two-subset partitions, as a bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Which subset each pixel belongs to for the 64 three-subset partitions, as two bits per pixel.
+const PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// The pixel with the implied high index bit of the second subset in two-subset partitions.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The anchor pixels of the second and third subsets in three-subset partitions.
+const ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15],
+    [3, 8],
+    [15, 8],
+    [15, 3],
+    [8, 15],
+    [3, 15],
+    [15, 3],
+    [15, 8],
+    [8, 15],
+    [8, 15],
+    [6, 15],
+    [6, 15],
+    [6, 15],
+    [5, 15],
+    [3, 15],
+    [3, 8],
+    [3, 15],
+    [3, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [3, 8],
+    [6, 15],
+    [10, 8],
+    [5, 3],
+    [8, 15],
+    [8, 6],
+    [6, 10],
+    [8, 15],
+    [5, 15],
+    [15, 10],
+    [15, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [5, 10],
+    [6, 10],
+    [10, 8],
+    [8, 9],
+    [15, 10],
+    [15, 6],
+    [3, 15],
+    [15, 8],
+    [5, 15],
+    [15, 3],
+    [15, 6],
+    [15, 6],
+    [15, 8],
+    [3, 15],
+    [15, 3],
+    [5, 15],
+    [5, 15],
+    [5, 15],
+    [8, 15],
+    [5, 15],
+    [10, 15],
+    [5, 15],
+    [10, 15],
+    [8, 15],
+    [13, 15],
+    [15, 3],
+    [12, 15],
+    [3, 15],
+    [3, 8],
+];
+
+/// The subset of each pixel, and whether the pixel is the anchor of its subset, whose index has
+/// an implied high bit of 0.
+fn partition(subsets: u32, partition: usize) -> [(usize, bool); 16] {
+    std::array::from_fn(|i| match subsets {
+        1 => (0, i == 0),
+        2 => {
+            let subset = (PARTITIONS_2[partition] >> i) as usize & 1;
+            (subset, i == 0 || i == ANCHORS_2[partition] as usize)
+        }
+        _ => {
+            let subset = (PARTITIONS_3[partition] >> (i * 2)) as usize & 3;
+            let [anchor_1, anchor_2] = ANCHORS_3[partition];
+            (
+                subset,
+                i == 0 || i == anchor_1 as usize || i == anchor_2 as usize,
+            )
+        }
+    })
+}
+
+fn read_indices(
+    bits: &mut BitReader,
+    partition: &[(usize, bool); 16],
+    index_bits: u32,
+) -> [usize; 16] {
+    std::array::from_fn(|i| bits.read(index_bits - partition[i].1 as u32) as usize)
+}
+
+struct Bc7Mode {
+    subsets: u32,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const fn bc7_mode(
+    [subsets, partition_bits, rotation_bits, index_selection_bits, color_bits, alpha_bits, endpoint_p_bits, shared_p_bits, index_bits, secondary_index_bits]: [u32; 10],
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_p_bits: endpoint_p_bits == 1,
+        shared_p_bits: shared_p_bits == 1,
+        index_bits,
+        secondary_index_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode([3, 4, 0, 0, 4, 0, 1, 0, 3, 0]),
+    bc7_mode([2, 6, 0, 0, 6, 0, 0, 1, 3, 0]),
+    bc7_mode([3, 6, 0, 0, 5, 0, 0, 0, 2, 0]),
+    bc7_mode([2, 6, 0, 0, 7, 0, 1, 0, 2, 0]),
+    bc7_mode([1, 0, 2, 1, 5, 6, 0, 0, 2, 3]),
+    bc7_mode([1, 0, 2, 0, 7, 8, 0, 0, 2, 2]),
+    bc7_mode([1, 0, 0, 0, 7, 7, 1, 0, 4, 0]),
+    bc7_mode([2, 6, 0, 0, 5, 5, 1, 0, 2, 0]),
+];
+
+fn bc7(block: &[u8; 16]) -> [[u8; 4]; 16] {
+    // The mode is the number of zero bits before the first set bit. Blocks without one are
+    // reserved and decode to transparent black.
+    let Some(mode) = BC7_MODES.get(block[0].trailing_zeros() as usize) else {
+        return [[0; 4]; 16];
+    };
+
+    let mut bits = BitReader::new(block);
+    bits.read(block[0].trailing_zeros() + 1);
+
+    let partition_index = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let subsets = mode.subsets as usize;
+    // Two endpoints per subset, each RGBA.
+    let mut endpoints = [[0u32; 4]; 6];
+
+    for channel in 0..4 {
+        let channel_bits = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+
+        for endpoint in &mut endpoints[..subsets * 2] {
+            endpoint[channel] = bits.read(channel_bits);
+        }
+    }
+
+    let mut precision = [
+        mode.color_bits,
+        mode.color_bits,
+        mode.color_bits,
+        mode.alpha_bits,
+    ];
+
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let mut p_bits = [0; 6];
+
+        if mode.endpoint_p_bits {
+            p_bits[..subsets * 2].fill_with(|| bits.read(1));
+        } else {
+            for subset in 0..subsets {
+                p_bits[subset * 2..subset * 2 + 2].fill(bits.read(1));
+            }
+        }
+
+        for (endpoint, p_bit) in endpoints[..subsets * 2].iter_mut().zip(p_bits) {
+            for (channel, value) in endpoint.iter_mut().enumerate() {
+                if precision[channel] > 0 {
+                    *value = *value << 1 | p_bit;
+                }
+            }
+        }
+
+        for channel_precision in precision.iter_mut().filter(|precision| **precision > 0) {
+            *channel_precision += 1;
+        }
+    }
+
+    // Expand to 8 bits by replicating the high bits into the low bits.
+    for endpoint in &mut endpoints[..subsets * 2] {
+        for (value, &precision) in endpoint.iter_mut().zip(&precision) {
+            *value = if precision == 0 {
+                255
+            } else {
+                *value << (8 - precision) | *value >> (2 * precision - 8)
+            };
+        }
+    }
+
+    let partition = partition(mode.subsets, partition_index);
+    let indices = read_indices(&mut bits, &partition, mode.index_bits);
+
+    let (color_indices, color_bits, alpha_indices, alpha_bits) = if mode.secondary_index_bits > 0 {
+        let secondary = read_indices(&mut bits, &partition, mode.secondary_index_bits);
+
+        if index_selection == 0 {
+            (
+                indices,
+                mode.index_bits,
+                secondary,
+                mode.secondary_index_bits,
+            )
+        } else {
+            (
+                secondary,
+                mode.secondary_index_bits,
+                indices,
+                mode.index_bits,
+            )
+        }
+    } else {
+        (indices, mode.index_bits, indices, mode.index_bits)
+    };
+
+    let interpolate = |a: u32, b: u32, weight: u32| ((64 - weight) * a + weight * b + 32) >> 6;
+
+    std::array::from_fn(|i| {
+        let subset = partition[i].0;
+        let (a, b) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let color_weight = weights(color_bits)[color_indices[i]];
+        let alpha_weight = weights(alpha_bits)[alpha_indices[i]];
+
+        let mut pixel: [u8; 4] = std::array::from_fn(|channel| {
+            let weight = if channel < 3 {
+                color_weight
+            } else {
+                alpha_weight
+            };
+            interpolate(a[channel], b[channel], weight) as u8
+        });
+
+        if rotation > 0 {
+            pixel.swap(3, rotation as usize - 1);
+        }
+
+        pixel
+    })
+}
+
+/// A run of bits in a BC6H mode's header: the endpoint (w, x, y, z), the channel, and the first
+/// and last bit of the value, read from `first` towards `last`.
+#[derive(Clone, Copy)]
+struct Bits {
+    endpoint: u8,
+    channel: u8,
+    first: u8,
+    last: u8,
+}
+
+// Endpoints and channels, named as in the D3D11 specification.
+const RW: (u8, u8) = (0, 0);
+const GW: (u8, u8) = (0, 1);
+const BW: (u8, u8) = (0, 2);
+const RX: (u8, u8) = (1, 0);
+const GX: (u8, u8) = (1, 1);
+const BX: (u8, u8) = (1, 2);
+const RY: (u8, u8) = (2, 0);
+const GY: (u8, u8) = (2, 1);
+const BY: (u8, u8) = (2, 2);
+const RZ: (u8, u8) = (3, 0);
+const GZ: (u8, u8) = (3, 1);
+const BZ: (u8, u8) = (3, 2);
+
+const fn b((endpoint, channel): (u8, u8), first: u8, last: u8) -> Bits {
+    Bits {
+        endpoint,
+        channel,
+        first,
+        last,
+    }
+}
+
+struct Bc6hMode {
+    /// The 2 or 5 bit mode value.
+    value: u32,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    header: &'static [Bits],
+}
+
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode {
+        value: 0x00,
+        transformed: true,
+        endpoint_bits: 10,
+        delta_bits: [5, 5, 5],
+        header: &[
+            b(GY, 4, 4),
+            b(BY, 4, 4),
+            b(BZ, 4, 4),
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 4),
+            b(GZ, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 4),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 4),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 4),
+            b(BZ, 2, 2),
+            b(RZ, 0, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x01,
+        transformed: true,
+        endpoint_bits: 7,
+        delta_bits: [6, 6, 6],
+        header: &[
+            b(GY, 5, 5),
+            b(GZ, 4, 5),
+            b(RW, 0, 6),
+            b(BZ, 0, 1),
+            b(BY, 4, 4),
+            b(GW, 0, 6),
+            b(BY, 5, 5),
+            b(BZ, 2, 2),
+            b(GY, 4, 4),
+            b(BW, 0, 6),
+            b(BZ, 3, 3),
+            b(BZ, 5, 4),
+            b(RX, 0, 5),
+            b(GY, 0, 3),
+            b(GX, 0, 5),
+            b(GZ, 0, 3),
+            b(BX, 0, 5),
+            b(BY, 0, 3),
+            b(RY, 0, 5),
+            b(RZ, 0, 5),
+        ],
+    },
+    Bc6hMode {
+        value: 0x02,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [5, 4, 4],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 4),
+            b(RW, 10, 10),
+            b(GY, 0, 3),
+            b(GX, 0, 3),
+            b(GW, 10, 10),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 3),
+            b(BW, 10, 10),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 4),
+            b(BZ, 2, 2),
+            b(RZ, 0, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x06,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 5, 4],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 3),
+            b(RW, 10, 10),
+            b(GZ, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 4),
+            b(GW, 10, 10),
+            b(GZ, 0, 3),
+            b(BX, 0, 3),
+            b(BW, 10, 10),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 3),
+            b(BZ, 0, 0),
+            b(BZ, 2, 2),
+            b(RZ, 0, 3),
+            b(GY, 4, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x0a,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 4, 5],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 3),
+            b(RW, 10, 10),
+            b(BY, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 3),
+            b(GW, 10, 10),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 4),
+            b(BW, 10, 10),
+            b(BY, 0, 3),
+            b(RY, 0, 3),
+            b(BZ, 1, 2),
+            b(RZ, 0, 3),
+            b(BZ, 4, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x0e,
+        transformed: true,
+        endpoint_bits: 9,
+        delta_bits: [5, 5, 5],
+        header: &[
+            b(RW, 0, 8),
+            b(BY, 4, 4),
+            b(GW, 0, 8),
+            b(GY, 4, 4),
+            b(BW, 0, 8),
+            b(BZ, 4, 4),
+            b(RX, 0, 4),
+            b(GZ, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 4),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 4),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 4),
+            b(BZ, 2, 2),
+            b(RZ, 0, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x12,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [6, 5, 5],
+        header: &[
+            b(RW, 0, 7),
+            b(GZ, 4, 4),
+            b(BY, 4, 4),
+            b(GW, 0, 7),
+            b(BZ, 2, 2),
+            b(GY, 4, 4),
+            b(BW, 0, 7),
+            b(BZ, 3, 4),
+            b(RX, 0, 5),
+            b(GY, 0, 3),
+            b(GX, 0, 4),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 4),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 5),
+            b(RZ, 0, 5),
+        ],
+    },
+    Bc6hMode {
+        value: 0x16,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 6, 5],
+        header: &[
+            b(RW, 0, 7),
+            b(BZ, 0, 0),
+            b(BY, 4, 4),
+            b(GW, 0, 7),
+            b(GY, 5, 4),
+            b(BW, 0, 7),
+            b(GZ, 5, 5),
+            b(BZ, 4, 4),
+            b(RX, 0, 4),
+            b(GZ, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 5),
+            b(GZ, 0, 3),
+            b(BX, 0, 4),
+            b(BZ, 1, 1),
+            b(BY, 0, 3),
+            b(RY, 0, 4),
+            b(BZ, 2, 2),
+            b(RZ, 0, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x1a,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 5, 6],
+        header: &[
+            b(RW, 0, 7),
+            b(BZ, 1, 1),
+            b(BY, 4, 4),
+            b(GW, 0, 7),
+            b(BY, 5, 5),
+            b(GY, 4, 4),
+            b(BW, 0, 7),
+            b(BZ, 5, 4),
+            b(RX, 0, 4),
+            b(GZ, 4, 4),
+            b(GY, 0, 3),
+            b(GX, 0, 4),
+            b(BZ, 0, 0),
+            b(GZ, 0, 3),
+            b(BX, 0, 5),
+            b(BY, 0, 3),
+            b(RY, 0, 4),
+            b(BZ, 2, 2),
+            b(RZ, 0, 4),
+            b(BZ, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        value: 0x1e,
+        transformed: false,
+        endpoint_bits: 6,
+        delta_bits: [6, 6, 6],
+        header: &[
+            b(RW, 0, 5),
+            b(GZ, 4, 4),
+            b(BZ, 0, 1),
+            b(BY, 4, 4),
+            b(GW, 0, 5),
+            b(GY, 5, 5),
+            b(BY, 5, 5),
+            b(BZ, 2, 2),
+            b(GY, 4, 4),
+            b(BW, 0, 5),
+            b(GZ, 5, 5),
+            b(BZ, 3, 3),
+            b(BZ, 5, 4),
+            b(RX, 0, 5),
+            b(GY, 0, 3),
+            b(GX, 0, 5),
+            b(GZ, 0, 3),
+            b(BX, 0, 5),
+            b(BY, 0, 3),
+            b(RY, 0, 5),
+            b(RZ, 0, 5),
+        ],
+    },
+    Bc6hMode {
+        value: 0x03,
+        transformed: false,
+        endpoint_bits: 10,
+        delta_bits: [10, 10, 10],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 9),
+            b(GX, 0, 9),
+            b(BX, 0, 9),
+        ],
+    },
+    Bc6hMode {
+        value: 0x07,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [9, 9, 9],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 8),
+            b(RW, 10, 10),
+            b(GX, 0, 8),
+            b(GW, 10, 10),
+            b(BX, 0, 8),
+            b(BW, 10, 10),
+        ],
+    },
+    Bc6hMode {
+        value: 0x0b,
+        transformed: true,
+        endpoint_bits: 12,
+        delta_bits: [8, 8, 8],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 7),
+            b(RW, 11, 10),
+            b(GX, 0, 7),
+            b(GW, 11, 10),
+            b(BX, 0, 7),
+            b(BW, 11, 10),
+        ],
+    },
+    Bc6hMode {
+        value: 0x0f,
+        transformed: true,
+        endpoint_bits: 16,
+        delta_bits: [4, 4, 4],
+        header: &[
+            b(RW, 0, 9),
+            b(GW, 0, 9),
+            b(BW, 0, 9),
+            b(RX, 0, 3),
+            b(RW, 15, 10),
+            b(GX, 0, 3),
+            b(GW, 15, 10),
+            b(BX, 0, 3),
+            b(BW, 15, 10),
+        ],
+    },
+];
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+/// Scale a quantized endpoint to 16 bits (15 bits and a sign for signed formats).
+fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 || value == 0 {
+            value
+        } else if value == (1 << bits) - 1 {
+            0xffff
+        } else {
+            ((value << 16) + 0x8000) >> bits
+        }
+    } else if bits >= 16 || value == 0 {
+        value
+    } else {
+        let magnitude = value.abs();
+
+        let unquantized = if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7fff
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+
+        unquantized * value.signum()
+    }
+}
+
+/// Convert an interpolated value to the bits of a half float.
+fn bc6h_finish_unquantize(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        0x8000 | ((-value * 31) >> 5) as u16
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+fn bc6h(block: &[u8; 16], signed: bool) -> [[f32; 4]; 16] {
+    let mut bits = BitReader::new(block);
+
+    let mut value = bits.read(2);
+
+    if value > 1 {
+        value |= bits.read(3) << 2;
+    }
+
+    // Reserved modes decode to black.
+    let Some(mode) = BC6H_MODES.iter().find(|mode| mode.value == value) else {
+        return [[0.0, 0.0, 0.0, 1.0]; 16];
+    };
+
+    let mut endpoints = [[0i32; 3]; 4];
+
+    for field in mode.header {
+        let mut bit = field.first;
+
+        loop {
+            endpoints[field.endpoint as usize][field.channel as usize] |=
+                (bits.read(1) << bit) as i32;
+
+            if bit == field.last {
+                break;
+            }
+
+            if field.first < field.last {
+                bit += 1;
+            } else {
+                bit -= 1;
+            }
+        }
+    }
+
+    let subsets = if mode.header.len() > 9 { 2 } else { 1 };
+    let partition_index = if subsets == 2 { bits.read(5) } else { 0 } as usize;
+    let endpoint_count = subsets * 2;
+
+    let mask = (1 << mode.endpoint_bits) - 1;
+
+    if signed {
+        for channel in &mut endpoints[0] {
+            *channel = sign_extend(*channel, mode.endpoint_bits);
+        }
+    }
+
+    if signed || mode.transformed {
+        for endpoint in &mut endpoints[1..endpoint_count] {
+            for (channel, &delta_bits) in endpoint.iter_mut().zip(&mode.delta_bits) {
+                *channel = sign_extend(*channel, delta_bits);
+            }
+        }
+    }
+
+    if mode.transformed {
+        let base = endpoints[0];
+
+        for endpoint in &mut endpoints[1..endpoint_count] {
+            for (channel, base) in endpoint.iter_mut().zip(base) {
+                *channel = (*channel + base) & mask;
+
+                if signed {
+                    *channel = sign_extend(*channel, mode.endpoint_bits);
+                }
+            }
+        }
+    }
+
+    for endpoint in &mut endpoints[..endpoint_count] {
+        for channel in endpoint.iter_mut() {
+            *channel = bc6h_unquantize(*channel, mode.endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if subsets == 2 { 3 } else { 4 };
+    let partition = partition(subsets as u32, partition_index);
+    let indices = read_indices(&mut bits, &partition, index_bits);
+
+    std::array::from_fn(|i| {
+        let subset = partition[i].0;
+        let (a, b) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let weight = weights(index_bits)[indices[i]] as i32;
+
+        let channel = |c: usize| {
+            let value = ((64 - weight) * a[c] + weight * b[c] + 32) >> 6;
+            half::f16::from_bits(bc6h_finish_unquantize(value, signed)).to_f32()
+        };
+
+        [channel(0), channel(1), channel(2), 1.0]
+    })
+}
@@ -0,0 +1,168 @@
+use crate::{BasicDfd, Error, StreamingWriter, Writer, WriterHeader, WriterOptions};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{Seek, Write};
+
+/// The shape of a texture, which determines the depth, layer and face counts in the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureKind {
+    Texture2D,
+    Array2D { layer_count: u32 },
+    Cube,
+    CubeArray { layer_count: u32 },
+    Texture3D { depth: u32 },
+}
+
+impl TextureKind {
+    /// The kind described by a header's counts, or `None` if they are inconsistent.
+    pub fn from_counts(pixel_depth: u32, layer_count: u32, face_count: u32) -> Option<Self> {
+        match (pixel_depth, layer_count, face_count) {
+            (0, 0, 1) => Some(Self::Texture2D),
+            (0, layer_count, 1) => Some(Self::Array2D { layer_count }),
+            (0, 0, 6) => Some(Self::Cube),
+            (0, layer_count, 6) => Some(Self::CubeArray { layer_count }),
+            (depth, 0, 1) => Some(Self::Texture3D { depth }),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a [`Writer`] from a format, dimensions and [`TextureKind`], deriving the rest of the
+/// header and checking the size of each level as it is added.
+pub struct WriterBuilder<'a> {
+    header: WriterHeader,
+    key_value_pairs: BTreeMap<String, Vec<u8>>,
+    levels: Vec<Cow<'a, [u8]>>,
+    options: WriterOptions,
+}
+
+impl<'a> WriterBuilder<'a> {
+    pub fn new(
+        format: ktx2::Format,
+        width: u32,
+        height: u32,
+        kind: TextureKind,
+    ) -> Result<Self, Error> {
+        let dfd = BasicDfd::from_format(format).ok_or(Error::UnsupportedFormat(Some(format)))?;
+
+        let (pixel_depth, layer_count, face_count) = match kind {
+            TextureKind::Texture2D => (0, 0, 1),
+            TextureKind::Array2D { layer_count } => (0, layer_count, 1),
+            TextureKind::Cube => (0, 0, 6),
+            TextureKind::CubeArray { layer_count } => (0, layer_count, 6),
+            TextureKind::Texture3D { depth } => (depth, 0, 1),
+        };
+
+        let invalid = |message: &str| Err(Error::InvalidDimensions(message.to_string()));
+
+        if width == 0 || height == 0 {
+            return invalid("textures must be at least 1x1");
+        }
+
+        if matches!(
+            kind,
+            TextureKind::Array2D { layer_count: 0 }
+                | TextureKind::CubeArray { layer_count: 0 }
+                | TextureKind::Texture3D { depth: 0 }
+        ) {
+            return invalid("arrays need at least one layer and 3D textures at least one slice");
+        }
+
+        if face_count == 6 && width != height {
+            return invalid("cubemap faces must be square");
+        }
+
+        Ok(Self {
+            header: WriterHeader {
+                format: Some(format),
+                type_size: dfd.type_size(),
+                pixel_width: width,
+                pixel_height: height,
+                pixel_depth,
+                layer_count,
+                face_count,
+                supercompression_scheme: None,
+            },
+            key_value_pairs: BTreeMap::new(),
+            levels: Vec::new(),
+            options: WriterOptions::default(),
+        })
+    }
+
+    pub fn supercompression_scheme(mut self, scheme: Option<ktx2::SupercompressionScheme>) -> Self {
+        self.header.supercompression_scheme = scheme;
+        self
+    }
+
+    pub fn key_value_pairs(mut self, key_value_pairs: BTreeMap<String, Vec<u8>>) -> Self {
+        self.key_value_pairs = key_value_pairs;
+        self
+    }
+
+    pub fn options(mut self, options: WriterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn header(&self) -> WriterHeader {
+        self.header
+    }
+
+    /// The size in bytes that a level must be, covering all layers, faces and depth slices.
+    pub fn level_size(&self, level: u32) -> u64 {
+        // The format was checked in `new`, so only an overflow could make this fail.
+        self.header.level_size(level).unwrap_or(u64::MAX)
+    }
+
+    /// The number of levels in a full mip chain.
+    pub fn max_level_count(&self) -> u32 {
+        self.header.max_level_count()
+    }
+
+    /// Add the next level, largest first, checking that it is the expected size.
+    pub fn push_level(&mut self, level: impl Into<Cow<'a, [u8]>>) -> Result<(), Error> {
+        let level = level.into();
+        let max_level_count = self.max_level_count();
+
+        if self.levels.len() >= max_level_count as usize {
+            return Err(Error::InconsistentLevelCount {
+                level_count: self.levels.len() + 1,
+                max_level_count,
+            });
+        }
+
+        self.header.check_level_size(self.levels.len(), &level)?;
+        self.levels.push(level);
+
+        Ok(())
+    }
+
+    pub fn writer(&self) -> Writer<'_> {
+        Writer {
+            header: self.header,
+            dfd_bytes: &[],
+            key_value_pairs: &self.key_value_pairs,
+            sgd_bytes: &[],
+            uncompressed_levels_descending: &self.levels,
+            options: self.options,
+        }
+    }
+
+    /// A streaming writer with the same header, for writing `level_count` levels one at a time
+    /// instead of pushing them to the builder.
+    pub fn streaming_writer<W: Write + Seek>(
+        &self,
+        writer: W,
+        level_count: usize,
+    ) -> Result<StreamingWriter<'_, W>, Error> {
+        StreamingWriter::new(
+            writer,
+            self.header,
+            level_count,
+            &[],
+            &self.key_value_pairs,
+            &[],
+            self.options,
+        )
+    }
+}
@@ -0,0 +1,69 @@
+use ktx2_tools::{encode_astc, open_image, AstcBlockSize, AstcOptions, WriterOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    /// 4x4, 5x4, 5x5, 6x5, 6x6, 8x5, 8x6 or 8x8. Larger blocks give smaller files at a lower
+    /// quality.
+    #[structopt(long, default_value = "4x4")]
+    block_size: AstcBlockSize,
+    #[structopt(long)]
+    no_zstd: bool,
+    #[structopt(long)]
+    srgb: bool,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
+    #[structopt(long)]
+    no_mips: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let image = open_image(&opts.input)?;
+
+    let image = image.into_rgba8();
+
+    println!(
+        "Width: {}\nHeight: {}\nBlock size: {:?}",
+        image.width(),
+        image.height(),
+        opts.block_size.dimensions()
+    );
+
+    let texture = encode_astc(
+        &image,
+        &AstcOptions {
+            block_size: opts.block_size,
+            srgb: opts.srgb,
+            generate_mipmaps: !opts.no_mips,
+            supercompression_scheme: if opts.no_zstd {
+                None
+            } else {
+                Some(ktx2::SupercompressionScheme::Zstandard)
+            },
+        },
+    )?;
+
+    texture
+        .writer(
+            &Default::default(),
+            WriterOptions {
+                zstd_level: opts.zstd_level,
+                ..Default::default()
+            },
+        )
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
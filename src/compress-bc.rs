@@ -0,0 +1,75 @@
+use ktx2_tools::{encode_bc, open_image, BcFormat, BcOptions, WriterOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    /// bc1 for opaque color, bc3 for color with alpha, bc4 for single-channel masks, bc5 for
+    /// normal maps or bc7 for high quality color.
+    #[structopt(long, default_value = "bc7")]
+    format: BcFormat,
+    #[structopt(long)]
+    no_zstd: bool,
+    #[structopt(long)]
+    srgb: bool,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
+    #[structopt(long)]
+    no_mips: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let image = open_image(&opts.input)?;
+
+    let image = image.into_rgba8();
+
+    let has_alpha = image.pixels().any(|pixel| pixel.0[3] != 255);
+
+    println!(
+        "Width: {}\nHeight: {}\nHas alpha: {}",
+        image.width(),
+        image.height(),
+        has_alpha
+    );
+
+    if has_alpha && opts.format == BcFormat::Bc1 {
+        eprintln!("warning: BC1 doesn't keep alpha");
+    }
+
+    let texture = encode_bc(
+        &image,
+        &BcOptions {
+            format: opts.format,
+            srgb: opts.srgb,
+            generate_mipmaps: !opts.no_mips,
+            supercompression_scheme: if opts.no_zstd {
+                None
+            } else {
+                Some(ktx2::SupercompressionScheme::Zstandard)
+            },
+        },
+    )?;
+
+    texture
+        .writer(
+            &Default::default(),
+            WriterOptions {
+                zstd_level: opts.zstd_level,
+                ..Default::default()
+            },
+        )
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
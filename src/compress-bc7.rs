@@ -1,6 +1,4 @@
-use ktx2_tools::{Writer, WriterHeader};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::borrow::Cow;
+use ktx2_tools::{encode_bc7, open_image, Bc7Options, WriterOptions};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -12,12 +10,21 @@ struct Opts {
     no_zstd: bool,
     #[structopt(long)]
     srgb: bool,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
 }
 
 fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::from_args();
 
-    let image = image::open(&opts.input).unwrap();
+    let image = open_image(&opts.input)?;
 
     let image = image.into_rgba8();
 
@@ -30,74 +37,28 @@ fn main() {
         has_alpha
     );
 
-    let settings = if has_alpha {
-        intel_tex_2::bc7::alpha_slow_settings()
-    } else {
-        intel_tex_2::bc7::opaque_slow_settings()
-    };
-
-    let mut width = image.width().max(4);
-    let mut height = image.height().max(4);
-
-    let mut sizes = Vec::new();
-
-    while width >= 4 && height >= 4 {
-        sizes.push((width, height));
-
-        width >>= 1;
-        height >>= 1;
-    }
-
-    let format = if opts.srgb {
-        ktx2::Format::BC7_SRGB_BLOCK
-    } else {
-        ktx2::Format::BC7_UNORM_BLOCK
-    };
-
-    let writer = Writer {
-        header: WriterHeader {
-            format: Some(format),
-            type_size: 1,
-            pixel_width: image.width().max(4),
-            pixel_height: image.height().max(4),
-            pixel_depth: 0,
-            layer_count: 1,
-            face_count: 1,
+    let texture = encode_bc7(
+        &image,
+        &Bc7Options {
+            srgb: opts.srgb,
             supercompression_scheme: if opts.no_zstd {
                 None
             } else {
                 Some(ktx2::SupercompressionScheme::Zstandard)
             },
+            ..Default::default()
         },
-        dfd_bytes: &4_u32.to_le_bytes(),
-        key_value_pairs: &Default::default(),
-        sgd_bytes: &[],
-        uncompressed_levels_descending: &sizes
-            .into_par_iter()
-            .map(|(width, height)| {
-                let resized = image::imageops::resize(
-                    &image,
-                    width,
-                    height,
-                    image::imageops::FilterType::Triangle,
-                );
-
-                let compressed = intel_tex_2::bc7::compress_blocks(
-                    &settings,
-                    &intel_tex_2::RgbaSurface {
-                        data: &resized,
-                        width: intel_tex_2::divide_up_by_multiple(width, 4) * 4,
-                        height, //: intel_tex_2::divide_up_by_multiple(height, 4) * 4,
-                        stride: width * 4,
-                    },
-                );
-
-                Cow::Owned(compressed)
-            })
-            .collect::<Vec<_>>(),
-    };
+    )?;
+
+    texture
+        .writer(
+            &Default::default(),
+            WriterOptions {
+                zstd_level: opts.zstd_level,
+                ..Default::default()
+            },
+        )
+        .write(&mut std::fs::File::create(&opts.output)?)?;
 
-    writer
-        .write(&mut std::fs::File::create(&opts.output).unwrap())
-        .unwrap();
+    Ok(())
 }
@@ -0,0 +1,75 @@
+use ktx2_tools::{encode_etc, open_image, EtcFormat, EtcOptions, WriterOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    /// etc2-rgb for opaque color, etc2-rgba for color with alpha, eac-r11 for single-channel
+    /// masks or eac-rg11 for normal maps.
+    #[structopt(long, default_value = "etc2-rgba")]
+    format: EtcFormat,
+    #[structopt(long)]
+    no_zstd: bool,
+    #[structopt(long)]
+    srgb: bool,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
+    #[structopt(long)]
+    no_mips: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let image = open_image(&opts.input)?;
+
+    let image = image.into_rgba8();
+
+    let has_alpha = image.pixels().any(|pixel| pixel.0[3] != 255);
+
+    println!(
+        "Width: {}\nHeight: {}\nHas alpha: {}",
+        image.width(),
+        image.height(),
+        has_alpha
+    );
+
+    if has_alpha && opts.format == EtcFormat::Etc2Rgb {
+        eprintln!("warning: ETC2 RGB doesn't keep alpha");
+    }
+
+    let texture = encode_etc(
+        &image,
+        &EtcOptions {
+            format: opts.format,
+            srgb: opts.srgb,
+            generate_mipmaps: !opts.no_mips,
+            supercompression_scheme: if opts.no_zstd {
+                None
+            } else {
+                Some(ktx2::SupercompressionScheme::Zstandard)
+            },
+        },
+    )?;
+
+    texture
+        .writer(
+            &Default::default(),
+            WriterOptions {
+                zstd_level: opts.zstd_level,
+                ..Default::default()
+            },
+        )
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
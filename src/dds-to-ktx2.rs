@@ -1,68 +1,62 @@
+use ktx2_tools::{TextureKind, WriterBuilder};
+
 fn main() {
-    let filename = std::env::args().nth(1).unwrap();
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
 
-    let dds = ddsfile::Dds::read(std::fs::File::open(&filename).unwrap()).unwrap();
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let filename = std::env::args()
+        .nth(1)
+        .ok_or("usage: dds-to-ktx2 <input>")?;
 
-    dbg!(&dds);
+    let dds = ddsfile::Dds::read(std::fs::File::open(&filename)?)?;
 
     let num_array_layers = dds.get_num_array_layers();
 
-    assert_eq!(num_array_layers, 1);
+    if num_array_layers != 1 {
+        return Err(format!("expected 1 array layer, got {}", num_array_layers).into());
+    }
 
     let num_mipmap_levels = dds.get_num_mipmap_levels();
 
-    let format = dds.header10.as_ref().unwrap().dxgi_format;
+    let format = dds
+        .header10
+        .as_ref()
+        .ok_or("only DDS files with a DX10 header are supported")?
+        .dxgi_format;
 
-    let header = ktx2_tools::WriterHeader {
-        format: Some(match format {
-            ddsfile::DxgiFormat::R9G9B9E5_SharedExp => ktx2::Format::E5B9G9R9_UFLOAT_PACK32,
-            ddsfile::DxgiFormat::BC1_UNorm => ktx2::Format::BC1_RGB_SRGB_BLOCK,
-            _ => panic!("{:?}", format),
-        }),
-        type_size: 4,
-        pixel_width: dds.header.width,
-        pixel_height: dds.header.height,
-        pixel_depth: dds.header.depth.unwrap_or(0),
-        layer_count: 0,
-        face_count: 1,
-        supercompression_scheme: None, //Some(ktx2::SupercompressionScheme::Zstandard)
+    let format = match format {
+        ddsfile::DxgiFormat::R9G9B9E5_SharedExp => ktx2::Format::E5B9G9R9_UFLOAT_PACK32,
+        ddsfile::DxgiFormat::BC1_UNorm => ktx2::Format::BC1_RGB_SRGB_BLOCK,
+        _ => return Err(format!("unsupported DXGI format: {:?}", format).into()),
     };
 
-    let width = dds.header.width as usize;
-    let height = dds.header.height as usize;
-    let mut offset = 0;
+    let kind = match dds.header.depth {
+        Some(depth) if depth > 1 => TextureKind::Texture3D { depth },
+        _ => TextureKind::Texture2D,
+    };
 
-    let mut levels = Vec::new();
+    let mut builder = WriterBuilder::new(format, dds.header.width, dds.header.height, kind)?;
 
-    for i in 0..num_mipmap_levels {
-        let level_width = (width >> i).max(4);
-        let level_height = (height >> i).max(4);
-        let data: &[u8] = &dds.data[offset..offset + level_width * level_height / 2];
-        offset += data.len();
+    let mut offset = 0;
 
-        levels.push(std::borrow::Cow::Borrowed(data));
+    for i in 0..num_mipmap_levels {
+        let size = builder.level_size(i) as usize;
+        let data = dds
+            .data
+            .get(offset..offset + size)
+            .ok_or("the DDS file is too short for its mip levels")?;
+        offset += size;
+
+        builder.push_level(data)?;
     }
 
-    dbg!(offset, dds.data.len());
-
-    let dfd = [
-        // DFD
-        0x0c, 0x00, 0x00, 0x00, // UInt32 dfdTotalSize = 0x3C (60)
-        0x01, 0x01, 0x01, 0x01, // vendorId = 0 (17 bits), descriptorType = 0
-        0x00, 0x00, 0x0, 0x00, // versionNumber = 2, descriptorBlockSize = 0 (120)
-    ];
-
-    dbg!(&dfd.len());
-
-    let writer = ktx2_tools::Writer {
-        header: header,
-        dfd_bytes: &dfd,
-        key_value_pairs: &Default::default(),
-        sgd_bytes: &[],
-        uncompressed_levels_descending: &levels,
-    };
+    builder
+        .writer()
+        .write(&mut std::fs::File::create("out.ktx2")?)?;
 
-    writer
-        .write(&mut std::fs::File::create("out.ktx2").unwrap())
-        .unwrap()
+    Ok(())
 }
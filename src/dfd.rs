@@ -0,0 +1,684 @@
+use crate::Error;
+use ktx2::{
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, DfdBlockHeaderBasic,
+    DfdHeader, Format, SampleInformation, TransferFunction,
+};
+use std::num::NonZeroU8;
+
+const CHANNEL_RED: u8 = 0;
+const CHANNEL_GREEN: u8 = 1;
+const CHANNEL_BLUE: u8 = 2;
+const CHANNEL_STENCIL: u8 = 13;
+const CHANNEL_DEPTH: u8 = 14;
+const CHANNEL_ALPHA: u8 = 15;
+
+// Channel ids for the block-compressed color models. These overlap with the RGBSDA ids above.
+const CHANNEL_BC1A_COLOR: u8 = 0;
+const CHANNEL_BC1A_ALPHA: u8 = 1;
+const CHANNEL_BC_COLOR: u8 = 0;
+const CHANNEL_ETC2_COLOR: u8 = 2;
+const CHANNEL_ASTC_DATA: u8 = 0;
+const CHANNEL_ETC1S_RGB: u8 = 0;
+const CHANNEL_ETC1S_AAA: u8 = 15;
+const CHANNEL_UASTC_RGB: u8 = 0;
+const CHANNEL_UASTC_RGBA: u8 = 3;
+
+const FLOAT_ONE: u32 = 0x3F80_0000;
+const FLOAT_MINUS_ONE: u32 = 0xBF80_0000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Numeric {
+    Unorm,
+    Snorm,
+    Uint,
+    Sint,
+    Srgb,
+    Ufloat,
+    Sfloat,
+}
+
+impl Numeric {
+    fn qualifiers(self) -> ChannelTypeQualifiers {
+        match self {
+            Self::Snorm | Self::Sint => ChannelTypeQualifiers::SIGNED,
+            Self::Ufloat => ChannelTypeQualifiers::FLOAT,
+            Self::Sfloat => ChannelTypeQualifiers::SIGNED | ChannelTypeQualifiers::FLOAT,
+            Self::Unorm | Self::Uint | Self::Srgb => ChannelTypeQualifiers::empty(),
+        }
+    }
+
+    fn bounds(self, bit_length: u8) -> (u32, u32) {
+        let max = |bits: u8| {
+            if bits >= 32 {
+                u32::MAX
+            } else {
+                (1 << bits) - 1
+            }
+        };
+
+        match self {
+            Self::Unorm | Self::Srgb => (0, max(bit_length)),
+            Self::Snorm => {
+                let upper = max(bit_length.min(32) - 1);
+                ((upper as i32).wrapping_neg() as u32, upper)
+            }
+            Self::Uint => (0, 1),
+            Self::Sint => (-1_i32 as u32, 1),
+            Self::Ufloat => (0, FLOAT_ONE),
+            Self::Sfloat => (FLOAT_MINUS_ONE, FLOAT_ONE),
+        }
+    }
+
+    fn transfer_function(self) -> TransferFunction {
+        match self {
+            Self::Srgb => TransferFunction::SRGB,
+            _ => TransferFunction::Linear,
+        }
+    }
+}
+
+/// A Khronos Basic Data Format Descriptor block, as stored in the DFD section of a KTX2 file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicDfd {
+    pub header: DfdBlockHeaderBasic,
+    pub samples: Vec<SampleInformation>,
+}
+
+impl BasicDfd {
+    /// Describe a `vkFormat`. Returns `None` for formats that can't be described by a single
+    /// Basic DFD block without extensions (64-bit channels, combined depth/stencil and so on).
+    pub fn from_format(format: Format) -> Option<Self> {
+        let r = CHANNEL_RED;
+        let g = CHANNEL_GREEN;
+        let b = CHANNEL_BLUE;
+        let a = CHANNEL_ALPHA;
+
+        let dfd = match format {
+            Format::R4G4_UNORM_PACK8 => packed(&[(g, 0, 4), (r, 4, 4)], 1, Numeric::Unorm),
+            Format::R4G4B4A4_UNORM_PACK16 => packed(
+                &[(a, 0, 4), (b, 4, 4), (g, 8, 4), (r, 12, 4)],
+                2,
+                Numeric::Unorm,
+            ),
+            Format::B4G4R4A4_UNORM_PACK16 => packed(
+                &[(a, 0, 4), (r, 4, 4), (g, 8, 4), (b, 12, 4)],
+                2,
+                Numeric::Unorm,
+            ),
+            Format::R5G6B5_UNORM_PACK16 => {
+                packed(&[(b, 0, 5), (g, 5, 6), (r, 11, 5)], 2, Numeric::Unorm)
+            }
+            Format::B5G6R5_UNORM_PACK16 => {
+                packed(&[(r, 0, 5), (g, 5, 6), (b, 11, 5)], 2, Numeric::Unorm)
+            }
+            Format::R5G5B5A1_UNORM_PACK16 => packed(
+                &[(a, 0, 1), (b, 1, 5), (g, 6, 5), (r, 11, 5)],
+                2,
+                Numeric::Unorm,
+            ),
+            Format::B5G5R5A1_UNORM_PACK16 => packed(
+                &[(a, 0, 1), (r, 1, 5), (g, 6, 5), (b, 11, 5)],
+                2,
+                Numeric::Unorm,
+            ),
+            Format::A1R5G5B5_UNORM_PACK16 => packed(
+                &[(b, 0, 5), (g, 5, 5), (r, 10, 5), (a, 15, 1)],
+                2,
+                Numeric::Unorm,
+            ),
+
+            Format::R8_UNORM => unpacked(&[r], 8, Numeric::Unorm),
+            Format::R8_SNORM => unpacked(&[r], 8, Numeric::Snorm),
+            Format::R8_UINT => unpacked(&[r], 8, Numeric::Uint),
+            Format::R8_SINT => unpacked(&[r], 8, Numeric::Sint),
+            Format::R8_SRGB => unpacked(&[r], 8, Numeric::Srgb),
+            Format::R8G8_UNORM => unpacked(&[r, g], 8, Numeric::Unorm),
+            Format::R8G8_SNORM => unpacked(&[r, g], 8, Numeric::Snorm),
+            Format::R8G8_UINT => unpacked(&[r, g], 8, Numeric::Uint),
+            Format::R8G8_SINT => unpacked(&[r, g], 8, Numeric::Sint),
+            Format::R8G8_SRGB => unpacked(&[r, g], 8, Numeric::Srgb),
+            Format::R8G8B8_UNORM => unpacked(&[r, g, b], 8, Numeric::Unorm),
+            Format::R8G8B8_SNORM => unpacked(&[r, g, b], 8, Numeric::Snorm),
+            Format::R8G8B8_UINT => unpacked(&[r, g, b], 8, Numeric::Uint),
+            Format::R8G8B8_SINT => unpacked(&[r, g, b], 8, Numeric::Sint),
+            Format::R8G8B8_SRGB => unpacked(&[r, g, b], 8, Numeric::Srgb),
+            Format::B8G8R8_UNORM => unpacked(&[b, g, r], 8, Numeric::Unorm),
+            Format::B8G8R8_SNORM => unpacked(&[b, g, r], 8, Numeric::Snorm),
+            Format::B8G8R8_UINT => unpacked(&[b, g, r], 8, Numeric::Uint),
+            Format::B8G8R8_SINT => unpacked(&[b, g, r], 8, Numeric::Sint),
+            Format::B8G8R8_SRGB => unpacked(&[b, g, r], 8, Numeric::Srgb),
+            Format::R8G8B8A8_UNORM => unpacked(&[r, g, b, a], 8, Numeric::Unorm),
+            Format::R8G8B8A8_SNORM => unpacked(&[r, g, b, a], 8, Numeric::Snorm),
+            Format::R8G8B8A8_UINT => unpacked(&[r, g, b, a], 8, Numeric::Uint),
+            Format::R8G8B8A8_SINT => unpacked(&[r, g, b, a], 8, Numeric::Sint),
+            Format::R8G8B8A8_SRGB => unpacked(&[r, g, b, a], 8, Numeric::Srgb),
+            Format::B8G8R8A8_UNORM => unpacked(&[b, g, r, a], 8, Numeric::Unorm),
+            Format::B8G8R8A8_SNORM => unpacked(&[b, g, r, a], 8, Numeric::Snorm),
+            Format::B8G8R8A8_UINT => unpacked(&[b, g, r, a], 8, Numeric::Uint),
+            Format::B8G8R8A8_SINT => unpacked(&[b, g, r, a], 8, Numeric::Sint),
+            Format::B8G8R8A8_SRGB => unpacked(&[b, g, r, a], 8, Numeric::Srgb),
+
+            Format::A2R10G10B10_UNORM_PACK32 => a2_rgb10(b, r, Numeric::Unorm),
+            Format::A2R10G10B10_SNORM_PACK32 => a2_rgb10(b, r, Numeric::Snorm),
+            Format::A2R10G10B10_UINT_PACK32 => a2_rgb10(b, r, Numeric::Uint),
+            Format::A2R10G10B10_SINT_PACK32 => a2_rgb10(b, r, Numeric::Sint),
+            Format::A2B10G10R10_UNORM_PACK32 => a2_rgb10(r, b, Numeric::Unorm),
+            Format::A2B10G10R10_SNORM_PACK32 => a2_rgb10(r, b, Numeric::Snorm),
+            Format::A2B10G10R10_UINT_PACK32 => a2_rgb10(r, b, Numeric::Uint),
+            Format::A2B10G10R10_SINT_PACK32 => a2_rgb10(r, b, Numeric::Sint),
+
+            Format::R16_UNORM => unpacked(&[r], 16, Numeric::Unorm),
+            Format::R16_SNORM => unpacked(&[r], 16, Numeric::Snorm),
+            Format::R16_UINT => unpacked(&[r], 16, Numeric::Uint),
+            Format::R16_SINT => unpacked(&[r], 16, Numeric::Sint),
+            Format::R16_SFLOAT => unpacked(&[r], 16, Numeric::Sfloat),
+            Format::R16G16_UNORM => unpacked(&[r, g], 16, Numeric::Unorm),
+            Format::R16G16_SNORM => unpacked(&[r, g], 16, Numeric::Snorm),
+            Format::R16G16_UINT => unpacked(&[r, g], 16, Numeric::Uint),
+            Format::R16G16_SINT => unpacked(&[r, g], 16, Numeric::Sint),
+            Format::R16G16_SFLOAT => unpacked(&[r, g], 16, Numeric::Sfloat),
+            Format::R16G16B16_UNORM => unpacked(&[r, g, b], 16, Numeric::Unorm),
+            Format::R16G16B16_SNORM => unpacked(&[r, g, b], 16, Numeric::Snorm),
+            Format::R16G16B16_UINT => unpacked(&[r, g, b], 16, Numeric::Uint),
+            Format::R16G16B16_SINT => unpacked(&[r, g, b], 16, Numeric::Sint),
+            Format::R16G16B16_SFLOAT => unpacked(&[r, g, b], 16, Numeric::Sfloat),
+            Format::R16G16B16A16_UNORM => unpacked(&[r, g, b, a], 16, Numeric::Unorm),
+            Format::R16G16B16A16_SNORM => unpacked(&[r, g, b, a], 16, Numeric::Snorm),
+            Format::R16G16B16A16_UINT => unpacked(&[r, g, b, a], 16, Numeric::Uint),
+            Format::R16G16B16A16_SINT => unpacked(&[r, g, b, a], 16, Numeric::Sint),
+            Format::R16G16B16A16_SFLOAT => unpacked(&[r, g, b, a], 16, Numeric::Sfloat),
+
+            Format::R32_UINT => unpacked(&[r], 32, Numeric::Uint),
+            Format::R32_SINT => unpacked(&[r], 32, Numeric::Sint),
+            Format::R32_SFLOAT => unpacked(&[r], 32, Numeric::Sfloat),
+            Format::R32G32_UINT => unpacked(&[r, g], 32, Numeric::Uint),
+            Format::R32G32_SINT => unpacked(&[r, g], 32, Numeric::Sint),
+            Format::R32G32_SFLOAT => unpacked(&[r, g], 32, Numeric::Sfloat),
+            Format::R32G32B32_UINT => unpacked(&[r, g, b], 32, Numeric::Uint),
+            Format::R32G32B32_SINT => unpacked(&[r, g, b], 32, Numeric::Sint),
+            Format::R32G32B32_SFLOAT => unpacked(&[r, g, b], 32, Numeric::Sfloat),
+            Format::R32G32B32A32_UINT => unpacked(&[r, g, b, a], 32, Numeric::Uint),
+            Format::R32G32B32A32_SINT => unpacked(&[r, g, b, a], 32, Numeric::Sint),
+            Format::R32G32B32A32_SFLOAT => unpacked(&[r, g, b, a], 32, Numeric::Sfloat),
+
+            Format::B10G11R11_UFLOAT_PACK32 => {
+                packed(&[(r, 0, 11), (g, 11, 11), (b, 22, 10)], 4, Numeric::Ufloat)
+            }
+            Format::E5B9G9R9_UFLOAT_PACK32 => e5b9g9r9(),
+
+            Format::D16_UNORM => unpacked(&[CHANNEL_DEPTH], 16, Numeric::Unorm),
+            Format::D32_SFLOAT => unpacked(&[CHANNEL_DEPTH], 32, Numeric::Sfloat),
+            Format::S8_UINT => unpacked(&[CHANNEL_STENCIL], 8, Numeric::Uint),
+
+            Format::BC1_RGB_UNORM_BLOCK => bc1(false, Numeric::Unorm),
+            Format::BC1_RGB_SRGB_BLOCK => bc1(false, Numeric::Srgb),
+            Format::BC1_RGBA_UNORM_BLOCK => bc1(true, Numeric::Unorm),
+            Format::BC1_RGBA_SRGB_BLOCK => bc1(true, Numeric::Srgb),
+            Format::BC2_UNORM_BLOCK => color_and_alpha(ColorModel::BC2, Numeric::Unorm),
+            Format::BC2_SRGB_BLOCK => color_and_alpha(ColorModel::BC2, Numeric::Srgb),
+            Format::BC3_UNORM_BLOCK => color_and_alpha(ColorModel::BC3, Numeric::Unorm),
+            Format::BC3_SRGB_BLOCK => color_and_alpha(ColorModel::BC3, Numeric::Srgb),
+            Format::BC4_UNORM_BLOCK => {
+                block(ColorModel::BC4, [4, 4], 8, &[(r, 0, 64)], Numeric::Unorm)
+            }
+            Format::BC4_SNORM_BLOCK => {
+                block(ColorModel::BC4, [4, 4], 8, &[(r, 0, 64)], Numeric::Snorm)
+            }
+            Format::BC5_UNORM_BLOCK => block(
+                ColorModel::BC5,
+                [4, 4],
+                16,
+                &[(r, 0, 64), (g, 64, 64)],
+                Numeric::Unorm,
+            ),
+            Format::BC5_SNORM_BLOCK => block(
+                ColorModel::BC5,
+                [4, 4],
+                16,
+                &[(r, 0, 64), (g, 64, 64)],
+                Numeric::Snorm,
+            ),
+            Format::BC6H_UFLOAT_BLOCK => block(
+                ColorModel::BC6H,
+                [4, 4],
+                16,
+                &[(CHANNEL_BC_COLOR, 0, 128)],
+                Numeric::Ufloat,
+            ),
+            Format::BC6H_SFLOAT_BLOCK => block(
+                ColorModel::BC6H,
+                [4, 4],
+                16,
+                &[(CHANNEL_BC_COLOR, 0, 128)],
+                Numeric::Sfloat,
+            ),
+            Format::BC7_UNORM_BLOCK => block(
+                ColorModel::BC7,
+                [4, 4],
+                16,
+                &[(CHANNEL_BC_COLOR, 0, 128)],
+                Numeric::Unorm,
+            ),
+            Format::BC7_SRGB_BLOCK => block(
+                ColorModel::BC7,
+                [4, 4],
+                16,
+                &[(CHANNEL_BC_COLOR, 0, 128)],
+                Numeric::Srgb,
+            ),
+
+            Format::ETC2_R8G8B8_UNORM_BLOCK => etc2(&[(CHANNEL_ETC2_COLOR, 0, 64)], Numeric::Unorm),
+            Format::ETC2_R8G8B8_SRGB_BLOCK => etc2(&[(CHANNEL_ETC2_COLOR, 0, 64)], Numeric::Srgb),
+            Format::ETC2_R8G8B8A1_UNORM_BLOCK => {
+                etc2(&[(CHANNEL_ETC2_COLOR, 0, 64), (a, 0, 64)], Numeric::Unorm)
+            }
+            Format::ETC2_R8G8B8A1_SRGB_BLOCK => {
+                etc2(&[(CHANNEL_ETC2_COLOR, 0, 64), (a, 0, 64)], Numeric::Srgb)
+            }
+            Format::ETC2_R8G8B8A8_UNORM_BLOCK => {
+                etc2(&[(a, 0, 64), (CHANNEL_ETC2_COLOR, 64, 64)], Numeric::Unorm)
+            }
+            Format::ETC2_R8G8B8A8_SRGB_BLOCK => {
+                etc2(&[(a, 0, 64), (CHANNEL_ETC2_COLOR, 64, 64)], Numeric::Srgb)
+            }
+            Format::EAC_R11_UNORM_BLOCK => etc2(&[(r, 0, 64)], Numeric::Unorm),
+            Format::EAC_R11_SNORM_BLOCK => etc2(&[(r, 0, 64)], Numeric::Snorm),
+            Format::EAC_R11G11_UNORM_BLOCK => etc2(&[(r, 0, 64), (g, 64, 64)], Numeric::Unorm),
+            Format::EAC_R11G11_SNORM_BLOCK => etc2(&[(r, 0, 64), (g, 64, 64)], Numeric::Snorm),
+
+            _ => {
+                let (dimensions, numeric) = astc_block(format)?;
+                block(
+                    ColorModel::ASTC,
+                    dimensions,
+                    16,
+                    &[(CHANNEL_ASTC_DATA, 0, 128)],
+                    numeric,
+                )
+            }
+        };
+
+        Some(dfd)
+    }
+
+    /// The descriptor for BasisLZ-supercompressed ETC1S data. ETC1S has no `vkFormat`.
+    pub fn etc1s(srgb: bool, has_alpha: bool) -> Self {
+        let mut fields = vec![(CHANNEL_ETC1S_RGB, 0, 64)];
+
+        if has_alpha {
+            fields.push((CHANNEL_ETC1S_AAA, 64, 64));
+        }
+
+        let mut dfd = block(ColorModel::ETC1S, [4, 4], 0, &fields, Numeric::Unorm);
+        dfd.header.transfer_function = Some(srgb_or_linear(srgb));
+        dfd
+    }
+
+    /// The descriptor for UASTC data. Like ETC1S, UASTC has no `vkFormat`. `bytesPlane0` must be
+    /// zeroed if the levels are supercompressed.
+    pub fn uastc(srgb: bool, has_alpha: bool) -> Self {
+        let channel = if has_alpha {
+            CHANNEL_UASTC_RGBA
+        } else {
+            CHANNEL_UASTC_RGB
+        };
+
+        let mut dfd = block(
+            ColorModel::UASTC,
+            [4, 4],
+            16,
+            &[(channel, 0, 128)],
+            Numeric::Unorm,
+        );
+        dfd.header.transfer_function = Some(srgb_or_linear(srgb));
+        dfd
+    }
+
+    /// Whether UASTC data has alpha, or `None` if this doesn't describe UASTC.
+    #[cfg(feature = "basis")]
+    pub(crate) fn uastc_has_alpha(&self) -> Option<bool> {
+        (self.header.color_model == Some(ColorModel::UASTC)).then(|| {
+            self.samples
+                .iter()
+                .any(|sample| sample.channel_type == CHANNEL_UASTC_RGBA)
+        })
+    }
+
+    /// The dimensions of a texel block, in pixels.
+    pub fn block_dimensions(&self) -> [u32; 4] {
+        self.header
+            .texel_block_dimensions
+            .map(|dimension| dimension.get() as u32)
+    }
+
+    /// The size of a single texel block, in bytes.
+    pub fn bytes_per_block(&self) -> u32 {
+        self.header.bytes_planes[0] as u32
+    }
+
+    /// The `typeSize` for data in this format: 1 for block-compressed formats, the size of a texel
+    /// for packed formats, and the size of a single component otherwise.
+    pub fn type_size(&self) -> u32 {
+        if self.block_dimensions() != [1; 4] {
+            return 1;
+        }
+
+        let is_unpacked = self.samples.iter().all(|sample| {
+            sample.bit_offset.is_multiple_of(8)
+                && (sample.bit_length.get() as u16).is_multiple_of(8)
+        });
+
+        if is_unpacked {
+            self.samples
+                .iter()
+                .map(|sample| (sample.bit_length.get() as u32 / 8).next_power_of_two())
+                .max()
+                .unwrap_or(1)
+        } else {
+            self.bytes_per_block()
+        }
+    }
+
+    /// Parse the contents of a Basic descriptor block, following its `DfdHeader`. Samples are
+    /// read up to the first one with an invalid `bitLength`.
+    ///
+    /// Texel block dimensions are stored minus 1, so a `texelBlockDimension` byte of 255 means
+    /// 256 texels, which doesn't fit in [`DfdBlockHeaderBasic`]. Those are rejected here, as
+    /// `ktx2` panics on them.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let header: &[u8; DfdBlockHeaderBasic::LENGTH] = bytes
+            .get(..DfdBlockHeaderBasic::LENGTH)
+            .ok_or(ktx2::ParseError::UnexpectedEnd)?
+            .try_into()
+            .unwrap();
+
+        if let Some(i) = header[4..8]
+            .iter()
+            .position(|&dimension| dimension == u8::MAX)
+        {
+            return Err(Error::InvalidDimensions(format!(
+                "texelBlockDimension{} is 255, for blocks 256 texels across",
+                i
+            )));
+        }
+
+        Ok(Self {
+            header: DfdBlockHeaderBasic::from_bytes(header)?,
+            samples: bytes[DfdBlockHeaderBasic::LENGTH..]
+                .chunks_exact(SampleInformation::LENGTH)
+                .map_while(|sample| SampleInformation::from_bytes(sample.try_into().unwrap()).ok())
+                .collect(),
+        })
+    }
+
+    /// Serialize the descriptor, including the leading `dfdTotalSize` field.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let descriptor_block_size = DfdHeader::LENGTH
+            + DfdBlockHeaderBasic::LENGTH
+            + self.samples.len() * SampleInformation::LENGTH;
+
+        let mut bytes = Vec::with_capacity(4 + descriptor_block_size);
+
+        bytes.extend_from_slice(&(4 + descriptor_block_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&DfdHeader::BASIC.as_bytes(descriptor_block_size as u16));
+        bytes.extend_from_slice(&self.header.as_bytes());
+
+        for sample in &self.samples {
+            bytes.extend_from_slice(&sample.as_bytes());
+        }
+
+        bytes
+    }
+}
+
+fn srgb_or_linear(srgb: bool) -> TransferFunction {
+    if srgb {
+        TransferFunction::SRGB
+    } else {
+        TransferFunction::Linear
+    }
+}
+
+fn nonzero(value: u8) -> NonZeroU8 {
+    NonZeroU8::new(value).unwrap()
+}
+
+fn descriptor(
+    color_model: ColorModel,
+    dimensions: [u8; 2],
+    bytes_per_block: u8,
+    numeric: Numeric,
+    samples: Vec<SampleInformation>,
+) -> BasicDfd {
+    BasicDfd {
+        header: DfdBlockHeaderBasic {
+            color_model: Some(color_model),
+            color_primaries: Some(ColorPrimaries::BT709),
+            transfer_function: Some(numeric.transfer_function()),
+            flags: DataFormatFlags::STRAIGHT_ALPHA,
+            texel_block_dimensions: [
+                nonzero(dimensions[0]),
+                nonzero(dimensions[1]),
+                nonzero(1),
+                nonzero(1),
+            ],
+            bytes_planes: [bytes_per_block, 0, 0, 0, 0, 0, 0, 0],
+        },
+        samples,
+    }
+}
+
+fn sample(
+    channel: u8,
+    bit_offset: u16,
+    bit_length: u8,
+    numeric: Numeric,
+    bounds: (u32, u32),
+) -> SampleInformation {
+    let mut qualifiers = numeric.qualifiers();
+
+    // Alpha is never sRGB-encoded, so it is flagged as linear in otherwise sRGB formats.
+    if numeric == Numeric::Srgb && channel == CHANNEL_ALPHA {
+        qualifiers |= ChannelTypeQualifiers::LINEAR;
+    }
+
+    SampleInformation {
+        bit_offset,
+        bit_length: nonzero(bit_length),
+        channel_type: channel,
+        channel_type_qualifiers: qualifiers,
+        sample_positions: [0; 4],
+        lower: bounds.0,
+        upper: bounds.1,
+    }
+}
+
+fn unpacked(channels: &[u8], bits: u8, numeric: Numeric) -> BasicDfd {
+    let samples = channels
+        .iter()
+        .enumerate()
+        .map(|(i, &channel)| {
+            sample(
+                channel,
+                i as u16 * bits as u16,
+                bits,
+                numeric,
+                numeric.bounds(bits),
+            )
+        })
+        .collect();
+
+    descriptor(
+        ColorModel::RGBSDA,
+        [1, 1],
+        channels.len() as u8 * bits / 8,
+        numeric,
+        samples,
+    )
+}
+
+fn packed(fields: &[(u8, u16, u8)], bytes: u8, numeric: Numeric) -> BasicDfd {
+    let samples = fields
+        .iter()
+        .map(|&(channel, bit_offset, bit_length)| {
+            sample(
+                channel,
+                bit_offset,
+                bit_length,
+                numeric,
+                numeric.bounds(bit_length),
+            )
+        })
+        .collect();
+
+    descriptor(ColorModel::RGBSDA, [1, 1], bytes, numeric, samples)
+}
+
+fn a2_rgb10(low: u8, high: u8, numeric: Numeric) -> BasicDfd {
+    packed(
+        &[
+            (low, 0, 10),
+            (CHANNEL_GREEN, 10, 10),
+            (high, 20, 10),
+            (CHANNEL_ALPHA, 30, 2),
+        ],
+        4,
+        numeric,
+    )
+}
+
+fn e5b9g9r9() -> BasicDfd {
+    // Each channel is described by a mantissa sample followed by a sample for the shared exponent,
+    // matching the example in the Khronos Data Format specification.
+    let samples = [CHANNEL_RED, CHANNEL_GREEN, CHANNEL_BLUE]
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &channel)| {
+            let mut mantissa = sample(channel, i as u16 * 9, 9, Numeric::Ufloat, (0, 8448));
+            mantissa.channel_type_qualifiers = ChannelTypeQualifiers::empty();
+
+            let mut exponent = sample(channel, 27, 5, Numeric::Ufloat, (15, 31));
+            exponent.channel_type_qualifiers = ChannelTypeQualifiers::EXPONENT;
+
+            [mantissa, exponent]
+        })
+        .collect();
+
+    descriptor(ColorModel::RGBSDA, [1, 1], 4, Numeric::Ufloat, samples)
+}
+
+fn block(
+    color_model: ColorModel,
+    dimensions: [u8; 2],
+    bytes: u8,
+    fields: &[(u8, u16, u8)],
+    numeric: Numeric,
+) -> BasicDfd {
+    // Block-compressed samples cover whole 32-bit words, so the normalized bounds are the full
+    // range of a word rather than of the sample's bit length.
+    let bounds = match numeric {
+        Numeric::Unorm | Numeric::Srgb | Numeric::Uint => (0, u32::MAX),
+        Numeric::Snorm | Numeric::Sint => (i32::MIN as u32, i32::MAX as u32),
+        Numeric::Ufloat | Numeric::Sfloat => numeric.bounds(32),
+    };
+
+    let samples = fields
+        .iter()
+        .map(|&(channel, bit_offset, bit_length)| {
+            sample(channel, bit_offset, bit_length, numeric, bounds)
+        })
+        .collect();
+
+    descriptor(color_model, dimensions, bytes, numeric, samples)
+}
+
+fn bc1(has_alpha: bool, numeric: Numeric) -> BasicDfd {
+    let mut dfd = block(
+        ColorModel::BC1A,
+        [4, 4],
+        8,
+        &[(CHANNEL_BC1A_COLOR, 0, 64)],
+        numeric,
+    );
+
+    if has_alpha {
+        dfd.samples
+            .push(sample(CHANNEL_BC1A_ALPHA, 0, 64, numeric, (0, u32::MAX)));
+        // BC1A alpha uses its own channel id rather than the RGBSDA alpha id, so mark it manually.
+        if numeric == Numeric::Srgb {
+            dfd.samples[1].channel_type_qualifiers |= ChannelTypeQualifiers::LINEAR;
+        }
+    }
+
+    dfd
+}
+
+fn color_and_alpha(color_model: ColorModel, numeric: Numeric) -> BasicDfd {
+    block(
+        color_model,
+        [4, 4],
+        16,
+        &[(CHANNEL_ALPHA, 0, 64), (CHANNEL_BC_COLOR, 64, 64)],
+        numeric,
+    )
+}
+
+fn etc2(fields: &[(u8, u16, u8)], numeric: Numeric) -> BasicDfd {
+    let bytes = fields
+        .iter()
+        .map(|&(_, bit_offset, bit_length)| (bit_offset + bit_length as u16) / 8)
+        .max()
+        .unwrap_or(8) as u8;
+
+    block(ColorModel::ETC2, [4, 4], bytes, fields, numeric)
+}
+
+fn astc_block(format: Format) -> Option<([u8; 2], Numeric)> {
+    let (dimensions, numeric) = match format {
+        Format::ASTC_4x4_UNORM_BLOCK => ([4, 4], Numeric::Unorm),
+        Format::ASTC_4x4_SRGB_BLOCK => ([4, 4], Numeric::Srgb),
+        Format::ASTC_4x4_SFLOAT_BLOCK => ([4, 4], Numeric::Sfloat),
+        Format::ASTC_5x4_UNORM_BLOCK => ([5, 4], Numeric::Unorm),
+        Format::ASTC_5x4_SRGB_BLOCK => ([5, 4], Numeric::Srgb),
+        Format::ASTC_5x4_SFLOAT_BLOCK => ([5, 4], Numeric::Sfloat),
+        Format::ASTC_5x5_UNORM_BLOCK => ([5, 5], Numeric::Unorm),
+        Format::ASTC_5x5_SRGB_BLOCK => ([5, 5], Numeric::Srgb),
+        Format::ASTC_5x5_SFLOAT_BLOCK => ([5, 5], Numeric::Sfloat),
+        Format::ASTC_6x5_UNORM_BLOCK => ([6, 5], Numeric::Unorm),
+        Format::ASTC_6x5_SRGB_BLOCK => ([6, 5], Numeric::Srgb),
+        Format::ASTC_6x5_SFLOAT_BLOCK => ([6, 5], Numeric::Sfloat),
+        Format::ASTC_6x6_UNORM_BLOCK => ([6, 6], Numeric::Unorm),
+        Format::ASTC_6x6_SRGB_BLOCK => ([6, 6], Numeric::Srgb),
+        Format::ASTC_6x6_SFLOAT_BLOCK => ([6, 6], Numeric::Sfloat),
+        Format::ASTC_8x5_UNORM_BLOCK => ([8, 5], Numeric::Unorm),
+        Format::ASTC_8x5_SRGB_BLOCK => ([8, 5], Numeric::Srgb),
+        Format::ASTC_8x5_SFLOAT_BLOCK => ([8, 5], Numeric::Sfloat),
+        Format::ASTC_8x6_UNORM_BLOCK => ([8, 6], Numeric::Unorm),
+        Format::ASTC_8x6_SRGB_BLOCK => ([8, 6], Numeric::Srgb),
+        Format::ASTC_8x6_SFLOAT_BLOCK => ([8, 6], Numeric::Sfloat),
+        Format::ASTC_8x8_UNORM_BLOCK => ([8, 8], Numeric::Unorm),
+        Format::ASTC_8x8_SRGB_BLOCK => ([8, 8], Numeric::Srgb),
+        Format::ASTC_8x8_SFLOAT_BLOCK => ([8, 8], Numeric::Sfloat),
+        Format::ASTC_10x5_UNORM_BLOCK => ([10, 5], Numeric::Unorm),
+        Format::ASTC_10x5_SRGB_BLOCK => ([10, 5], Numeric::Srgb),
+        Format::ASTC_10x5_SFLOAT_BLOCK => ([10, 5], Numeric::Sfloat),
+        Format::ASTC_10x6_UNORM_BLOCK => ([10, 6], Numeric::Unorm),
+        Format::ASTC_10x6_SRGB_BLOCK => ([10, 6], Numeric::Srgb),
+        Format::ASTC_10x6_SFLOAT_BLOCK => ([10, 6], Numeric::Sfloat),
+        Format::ASTC_10x8_UNORM_BLOCK => ([10, 8], Numeric::Unorm),
+        Format::ASTC_10x8_SRGB_BLOCK => ([10, 8], Numeric::Srgb),
+        Format::ASTC_10x8_SFLOAT_BLOCK => ([10, 8], Numeric::Sfloat),
+        Format::ASTC_10x10_UNORM_BLOCK => ([10, 10], Numeric::Unorm),
+        Format::ASTC_10x10_SRGB_BLOCK => ([10, 10], Numeric::Srgb),
+        Format::ASTC_10x10_SFLOAT_BLOCK => ([10, 10], Numeric::Sfloat),
+        Format::ASTC_12x10_UNORM_BLOCK => ([12, 10], Numeric::Unorm),
+        Format::ASTC_12x10_SRGB_BLOCK => ([12, 10], Numeric::Srgb),
+        Format::ASTC_12x10_SFLOAT_BLOCK => ([12, 10], Numeric::Sfloat),
+        Format::ASTC_12x12_UNORM_BLOCK => ([12, 12], Numeric::Unorm),
+        Format::ASTC_12x12_SRGB_BLOCK => ([12, 12], Numeric::Srgb),
+        Format::ASTC_12x12_SFLOAT_BLOCK => ([12, 12], Numeric::Sfloat),
+        _ => return None,
+    };
+
+    Some((dimensions, numeric))
+}
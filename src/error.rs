@@ -0,0 +1,138 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The supercompression scheme isn't supported for this operation.
+    UnsupportedSupercompression(ktx2::SupercompressionScheme),
+    /// The format isn't supported for this operation. `None` means the format is unknown or
+    /// described only by the DFD.
+    UnsupportedFormat(Option<ktx2::Format>),
+    /// The dimensions or counts don't describe a valid texture.
+    InvalidDimensions(String),
+    /// The number of levels doesn't fit the dimensions in the header.
+    InconsistentLevelCount {
+        level_count: usize,
+        max_level_count: u32,
+    },
+    /// A level isn't the size implied by the header's format and dimensions.
+    LevelSizeMismatch {
+        level: usize,
+        expected: u64,
+        actual: u64,
+    },
+    /// A level was written more than once, or is beyond the level count.
+    UnexpectedLevel(usize),
+    /// A level was never written.
+    MissingLevel(usize),
+    /// Holding a supercompressed level until the smaller levels are written would take more
+    /// memory than the [`StreamingWriter`](crate::StreamingWriter) allows.
+    PendingLimitExceeded {
+        level: usize,
+        pending: u64,
+        limit: u64,
+    },
+    /// The level is beyond the texture's level count.
+    LevelOutOfRange(u32),
+    /// The level, layer, face or z-slice is beyond the texture's counts, or the level's size
+    /// doesn't divide evenly into subresources.
+    SubresourceOutOfRange {
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    },
+    /// Supercompressing or decompressing level data failed.
+    Compression(std::io::Error),
+    /// An image encoder rejected its input or failed.
+    Encoding(String),
+    /// An image couldn't be decoded.
+    Decoding(String),
+    /// The value of a standard key/value data key is malformed.
+    InvalidMetadata(String),
+    /// The input isn't a valid KTX2 file.
+    Parse(ktx2::ParseError),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedSupercompression(scheme) => {
+                write!(f, "unsupported supercompression scheme: {:?}", scheme)
+            }
+            Self::UnsupportedFormat(Some(format)) => write!(f, "unsupported format: {:?}", format),
+            Self::UnsupportedFormat(None) => write!(f, "unsupported format: unknown"),
+            Self::InvalidDimensions(message) => write!(f, "invalid dimensions: {}", message),
+            Self::InconsistentLevelCount {
+                level_count,
+                max_level_count,
+            } => write!(
+                f,
+                "got {} levels but the dimensions allow at most {}",
+                level_count, max_level_count
+            ),
+            Self::LevelSizeMismatch {
+                level,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "level {} is {} bytes but {} bytes were expected",
+                level, actual, expected
+            ),
+            Self::UnexpectedLevel(level) => {
+                write!(f, "level {} is out of range or was already written", level)
+            }
+            Self::MissingLevel(level) => write!(f, "level {} was never written", level),
+            Self::PendingLimitExceeded {
+                level,
+                pending,
+                limit,
+            } => write!(
+                f,
+                "holding level {} until the smaller levels are written would take {} bytes, over \
+                 the limit of {}; write levels smallest first",
+                level, pending, limit
+            ),
+            Self::LevelOutOfRange(level) => write!(f, "level {} is out of range", level),
+            Self::SubresourceOutOfRange {
+                level,
+                layer,
+                face,
+                z_slice,
+            } => write!(
+                f,
+                "no subresource at level {}, layer {}, face {}, z-slice {}",
+                level, layer, face, z_slice
+            ),
+            Self::Compression(error) => write!(f, "compression failed: {}", error),
+            Self::Encoding(message) => write!(f, "encoding failed: {}", message),
+            Self::Decoding(message) => write!(f, "decoding failed: {}", message),
+            Self::InvalidMetadata(key) => write!(f, "invalid value for the {} key", key),
+            Self::Parse(error) => write!(f, "invalid KTX2 file: {}", error),
+            Self::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Compression(error) | Self::Io(error) => Some(error),
+            Self::Parse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ktx2::ParseError> for Error {
+    fn from(error: ktx2::ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
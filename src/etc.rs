@@ -0,0 +1,419 @@
+use crate::{resize_mip_level, EncodedTexture, Error, TextureKind, WriterBuilder};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::borrow::Cow;
+
+/// An ETC2 or EAC format that [`encode_etc`] can produce from 8-bit images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EtcFormat {
+    /// Opaque color. Alpha is ignored.
+    Etc2Rgb,
+    /// Color with an EAC alpha block.
+    Etc2Rgba,
+    /// A single 11-bit channel, taken from red.
+    EacR11,
+    /// Two 11-bit channels, taken from red and green, such as normal maps.
+    EacRg11,
+}
+
+impl EtcFormat {
+    /// The `vkFormat` for this format. EAC has no sRGB variants, so `None` is returned for it
+    /// when `srgb` is set.
+    pub fn ktx2_format(self, srgb: bool) -> Option<ktx2::Format> {
+        use ktx2::Format;
+
+        Some(match (self, srgb) {
+            (Self::Etc2Rgb, false) => Format::ETC2_R8G8B8_UNORM_BLOCK,
+            (Self::Etc2Rgb, true) => Format::ETC2_R8G8B8_SRGB_BLOCK,
+            (Self::Etc2Rgba, false) => Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+            (Self::Etc2Rgba, true) => Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+            (Self::EacR11, false) => Format::EAC_R11_UNORM_BLOCK,
+            (Self::EacRg11, false) => Format::EAC_R11G11_UNORM_BLOCK,
+            (Self::EacR11 | Self::EacRg11, true) => return None,
+        })
+    }
+}
+
+impl std::str::FromStr for EtcFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "etc2-rgb" => Ok(Self::Etc2Rgb),
+            "etc2-rgba" => Ok(Self::Etc2Rgba),
+            "eac-r11" => Ok(Self::EacR11),
+            "eac-rg11" => Ok(Self::EacRg11),
+            _ => Err(format!(
+                "unknown format {:?}, expected etc2-rgb, etc2-rgba, eac-r11 or eac-rg11",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EtcOptions {
+    pub format: EtcFormat,
+    /// Whether the image is sRGB-encoded color data. Not allowed for EAC.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for EtcOptions {
+    fn default() -> Self {
+        Self {
+            format: EtcFormat::Etc2Rgba,
+            srgb: false,
+            generate_mipmaps: true,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+/// Encode an RGBA image to ETC2 or EAC. Mipmaps are resized from the full image, in linear light
+/// if it is sRGB, down to 1x1, and levels that aren't a whole number of blocks are padded by
+/// repeating their last row and column.
+///
+/// Color is encoded as ETC1 blocks, which every ETC2 decoder accepts, so the T, H and planar
+/// modes are never used. Alpha and the EAC channels are encoded by searching every table and
+/// multiplier around the block's range.
+pub fn encode_etc(image: &image::RgbaImage, options: &EtcOptions) -> Result<EncodedTexture, Error> {
+    let format = options
+        .format
+        .ktx2_format(options.srgb)
+        .ok_or_else(|| Error::Encoding("EAC can't be sRGB".to_string()))?;
+
+    let header = WriterBuilder::new(
+        format,
+        image.width(),
+        image.height(),
+        TextureKind::Texture2D,
+    )?
+    .supercompression_scheme(options.supercompression_scheme)
+    .header();
+
+    let level_count = if options.generate_mipmaps {
+        header.max_level_count()
+    } else {
+        1
+    };
+
+    let levels = (0..level_count)
+        .into_par_iter()
+        .map(|level| {
+            let [width, height, _] = header.level_dimensions(level);
+
+            let resized = if level == 0 {
+                Cow::Borrowed(image)
+            } else {
+                Cow::Owned(resize_mip_level(image, width, height, options.srgb))
+            };
+
+            let padded = pad_to_blocks(&resized);
+            let blocks_x = padded.width() / 4;
+            let blocks_y = padded.height() / 4;
+
+            (0..blocks_x * blocks_y)
+                .into_par_iter()
+                .flat_map_iter(|i| {
+                    let (x, y) = (i % blocks_x * 4, i / blocks_x * 4);
+
+                    // ETC and EAC order pixels by column.
+                    let pixels: [[u8; 4]; 16] = std::array::from_fn(|j| {
+                        padded.get_pixel(x + j as u32 / 4, y + j as u32 % 4).0
+                    });
+                    let channel = |c: usize| pixels.map(|pixel| pixel[c]);
+
+                    match options.format {
+                        EtcFormat::Etc2Rgb => encode_etc1(&pixels).to_vec(),
+                        EtcFormat::Etc2Rgba => {
+                            [encode_eac(channel(3), false), encode_etc1(&pixels)].concat()
+                        }
+                        EtcFormat::EacR11 => encode_eac(channel(0), true).to_vec(),
+                        EtcFormat::EacRg11 => {
+                            [encode_eac(channel(0), true), encode_eac(channel(1), true)].concat()
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for (i, level) in levels.iter().enumerate() {
+        header.check_level_size(i, level)?;
+    }
+
+    Ok(EncodedTexture {
+        header,
+        dfd_bytes: Vec::new(),
+        sgd_bytes: Vec::new(),
+        levels: levels.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
+/// Extend an image to a whole number of 4x4 blocks by repeating its last row and column.
+fn pad_to_blocks(image: &image::RgbaImage) -> Cow<'_, image::RgbaImage> {
+    let (width, height) = image.dimensions();
+    let (padded_width, padded_height) = (width.div_ceil(4) * 4, height.div_ceil(4) * 4);
+
+    if (padded_width, padded_height) == (width, height) {
+        return Cow::Borrowed(image);
+    }
+
+    Cow::Owned(image::RgbaImage::from_fn(
+        padded_width,
+        padded_height,
+        |x, y| *image.get_pixel(x.min(width - 1), y.min(height - 1)),
+    ))
+}
+
+/// The ETC1 intensity modifiers for selectors 0 and 1. Selectors 2 and 3 negate them.
+const ETC1_TABLES: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// A subblock's stored base color, table, selectors and squared error.
+#[derive(Clone, Copy)]
+struct Subblock {
+    stored: [i32; 3],
+    table: usize,
+    selectors: [u8; 8],
+    error: i32,
+}
+
+/// An ETC1 block for 16 pixels in column order. Both flips and both the individual and
+/// differential modes are tried, keeping the smallest squared error.
+fn encode_etc1(pixels: &[[u8; 4]; 16]) -> [u8; 8] {
+    let individual = |color: [i32; 3]| {
+        let stored = color.map(|c| ((c * 15 + 127) / 255).clamp(0, 15));
+        (stored, stored.map(|c| c * 17))
+    };
+    let differential = |color: [i32; 3]| {
+        let stored = color.map(|c| ((c * 31 + 127) / 255).clamp(0, 31));
+        (stored, stored.map(|c| (c << 3) | (c >> 2)))
+    };
+
+    let mut best = (i32::MAX, 0u64);
+
+    for flip in [false, true] {
+        // Without a flip the subblocks are the left and right halves, and with one the top and
+        // bottom halves.
+        let indices = |second: bool| -> [usize; 8] {
+            let mut indices = (0..16).filter(|&i| {
+                let half = if flip { i % 4 >= 2 } else { i >= 8 };
+                half == second
+            });
+            std::array::from_fn(|_| indices.next().unwrap())
+        };
+        let halves = [indices(false), indices(true)];
+        let subblock_pixels = halves.map(|half| half.map(|i| pixels[i]));
+
+        for diff in [false, true] {
+            let first = if diff {
+                fit_subblock(&subblock_pixels[0], differential)
+            } else {
+                fit_subblock(&subblock_pixels[0], individual)
+            };
+
+            // The second differential color is stored as a 3-bit signed offset from the first.
+            let second = if diff {
+                fit_subblock(&subblock_pixels[1], |color| {
+                    let (stored, _) = differential(color);
+                    let stored: [i32; 3] = std::array::from_fn(|c| {
+                        stored[c].clamp(first.stored[c] - 4, first.stored[c] + 3)
+                    });
+                    (stored, stored.map(|c| (c << 3) | (c >> 2)))
+                })
+            } else {
+                fit_subblock(&subblock_pixels[1], individual)
+            };
+
+            let error = first.error + second.error;
+
+            if error >= best.0 {
+                continue;
+            }
+
+            let colors = if diff {
+                (0..3).fold(0, |colors, c| {
+                    let delta = (second.stored[c] - first.stored[c]) & 0b111;
+                    colors << 8 | (first.stored[c] << 3 | delta) as u64
+                })
+            } else {
+                (0..3).fold(0, |colors, c| {
+                    colors << 8 | (first.stored[c] << 4 | second.stored[c]) as u64
+                })
+            };
+
+            let mut bits = colors << 40
+                | (first.table as u64) << 37
+                | (second.table as u64) << 34
+                | (diff as u64) << 33
+                | (flip as u64) << 32;
+
+            for (subblock, half) in [first, second].iter().zip(&halves) {
+                for (&selector, &i) in subblock.selectors.iter().zip(half) {
+                    bits |= ((selector as u64 >> 1) << (16 + i)) | ((selector as u64 & 1) << i);
+                }
+            }
+
+            best = (error, bits);
+        }
+    }
+
+    best.1.to_be_bytes()
+}
+
+/// The best table, selectors and base color for a subblock. `quantize` gives the stored and
+/// decoded base color closest to a color. Since the modifiers move every channel together, each
+/// table is refined by moving the base against the average modifier its selectors chose.
+fn fit_subblock(
+    pixels: &[[u8; 4]; 8],
+    quantize: impl Fn([i32; 3]) -> ([i32; 3], [i32; 3]),
+) -> Subblock {
+    let average: [i32; 3] =
+        std::array::from_fn(|c| (pixels.iter().map(|p| p[c] as i32).sum::<i32>() + 4) / 8);
+
+    let mut best: Option<Subblock> = None;
+
+    for (table, &[a, b]) in ETC1_TABLES.iter().enumerate() {
+        let modifiers = [a, b, -a, -b];
+        let mut base = average;
+
+        for _ in 0..2 {
+            let (stored, decoded) = quantize(base);
+
+            let mut error = 0;
+            let mut selectors = [0u8; 8];
+            let mut modifier_sum = 0;
+
+            for (pixel, selector) in pixels.iter().zip(&mut selectors) {
+                let (i, pixel_error) = modifiers
+                    .iter()
+                    .map(|&modifier| {
+                        (0..3)
+                            .map(|c| {
+                                let value = (decoded[c] + modifier).clamp(0, 255);
+                                (value - pixel[c] as i32).pow(2)
+                            })
+                            .sum::<i32>()
+                    })
+                    .enumerate()
+                    .min_by_key(|&(_, error)| error)
+                    .unwrap();
+
+                *selector = i as u8;
+                error += pixel_error;
+                modifier_sum += modifiers[i];
+            }
+
+            if best.is_none_or(|best| error < best.error) {
+                best = Some(Subblock {
+                    stored,
+                    table,
+                    selectors,
+                    error,
+                });
+            }
+
+            base = average.map(|c| c - modifier_sum / 8);
+        }
+    }
+
+    best.unwrap()
+}
+
+/// The modifier tables shared by EAC alpha and 11-bit blocks.
+const EAC_TABLES: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// An EAC alpha or 11-bit block for 16 values in column order. Every table and multiplier is
+/// tried with the bases around the one that centers the table on the block's range, keeping the
+/// smallest squared error.
+fn encode_eac(values: [u8; 16], eleven_bit: bool) -> [u8; 8] {
+    // Alpha decodes to `base + modifier * multiplier`. 11-bit values decode to
+    // `base * 8 + 4 + modifier * multiplier * 8`, or a step of 1 with a multiplier of 0, which
+    // alpha doesn't allow.
+    let (base_scale, base_offset, max_value) = if eleven_bit {
+        (8, 4, 2047)
+    } else {
+        (1, 0, 255)
+    };
+    let multipliers = if eleven_bit { 0..=15 } else { 1..=15 };
+    let step = |multiplier: i32| match (eleven_bit, multiplier) {
+        (true, 0) => 1,
+        (true, multiplier) => multiplier * 8,
+        (false, multiplier) => multiplier,
+    };
+
+    // 8-bit values and decoded values are compared as fractions of 255 * `max_value`.
+    let values = values.map(|value| value as i64 * max_value as i64);
+    let center = (values.iter().min().unwrap() + values.iter().max().unwrap()) / 2;
+
+    let mut best = (i64::MAX, 0u64);
+
+    for (table, modifiers) in EAC_TABLES.iter().enumerate() {
+        for multiplier in multipliers.clone() {
+            let step = step(multiplier);
+            let table_center = (modifiers[3] + modifiers[7]) * step / 2;
+            let estimate = ((center / 255) as i32 - base_offset - table_center) / base_scale;
+
+            for base in (estimate - 1).clamp(0, 255)..=(estimate + 1).clamp(0, 255) {
+                let palette = modifiers.map(|modifier| {
+                    (base * base_scale + base_offset + modifier * step).clamp(0, max_value) as i64
+                        * 255
+                });
+
+                let mut error = 0;
+                let mut selectors = 0;
+
+                for &value in &values {
+                    let (selector, difference) = palette
+                        .iter()
+                        .map(|&decoded| (decoded - value).abs())
+                        .enumerate()
+                        .min_by_key(|&(_, difference)| difference)
+                        .unwrap();
+
+                    error += difference * difference;
+                    selectors = selectors << 3 | selector as u64;
+                }
+
+                if error < best.0 {
+                    best = (
+                        error,
+                        (base as u64) << 56
+                            | (multiplier as u64) << 52
+                            | (table as u64) << 48
+                            | selectors,
+                    );
+                }
+            }
+        }
+    }
+
+    best.1.to_be_bytes()
+}
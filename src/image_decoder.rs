@@ -0,0 +1,249 @@
+use crate::validate::KTX2_IDENTIFIER;
+use crate::{decode_bc, DecodedPixels, Error, SubresourceReader};
+use image::ColorType;
+use std::path::Path;
+
+/// An [`image::ImageDecoder`] for a single subresource of a KTX2 file.
+///
+/// Uncompressed R8, RGBA8, BGRA8, RGBA16F, RGBA32F and RGB9E5 formats are supported, as are
+/// BC1-BC7, which are decoded in software. With the `basis` feature, UASTC is transcoded with
+/// Basis Universal. R8 and BC4 decode to luminance, UASTC and BC formats normalized to 0-1 to
+/// RGBA8, and everything else to RGB or RGBA f32. sRGB data is returned as-is.
+pub struct Ktx2Decoder {
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    pixels: Vec<u8>,
+}
+
+impl Ktx2Decoder {
+    /// Decode the largest level of the first layer and face.
+    pub fn new(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+        Self::with_subresource(data, 0, 0, 0, 0)
+    }
+
+    /// Decode a single z-slice of a face of an array layer.
+    pub fn with_subresource(
+        data: impl AsRef<[u8]>,
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Result<Self, Error> {
+        let mut reader = SubresourceReader::new(data)?;
+        let header = reader.header();
+        #[cfg(feature = "basis")]
+        let uastc_has_alpha = uastc_has_alpha(reader.reader());
+        let bytes = reader.subresource(level, layer, face, z_slice)?;
+        let [width, height, _] = header.level_dimensions(level);
+
+        let (color_type, pixels) = match header.format {
+            Some(format) => decode_pixels(format, bytes, width, height)?,
+            #[cfg(feature = "basis")]
+            None if uastc_has_alpha.is_some() => (
+                ColorType::Rgba8,
+                transcode_uastc(bytes, width, height, uastc_has_alpha.unwrap())?,
+            ),
+            None => return Err(Error::UnsupportedFormat(None)),
+        };
+
+        Ok(Self {
+            width,
+            height,
+            color_type,
+            pixels,
+        })
+    }
+
+    /// Decode `width` by `height` pixels of texel data in `format` that isn't in a KTX2 file,
+    /// such as the top level of a DDS file.
+    pub fn from_texels(
+        format: ktx2::Format,
+        bytes: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Self, Error> {
+        let (color_type, pixels) = decode_pixels(format, bytes, width, height)?;
+
+        Ok(Self {
+            width,
+            height,
+            color_type,
+            pixels,
+        })
+    }
+}
+
+fn decode_pixels(
+    format: ktx2::Format,
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<(ColorType, Vec<u8>), Error> {
+    use ktx2::Format;
+
+    let pixel_count = width as usize * height as usize;
+
+    let expect_size = |bytes_per_pixel: usize| {
+        let expected = pixel_count * bytes_per_pixel;
+
+        if bytes.len() < expected {
+            return Err(Error::InvalidDimensions(format!(
+                "a {}x{} image needs {} bytes but only {} were given",
+                width,
+                height,
+                expected,
+                bytes.len()
+            )));
+        }
+
+        Ok(&bytes[..expected])
+    };
+
+    let f32_bytes = |values: &mut dyn Iterator<Item = f32>| {
+        values.flat_map(f32::to_ne_bytes).collect::<Vec<_>>()
+    };
+
+    Ok(match format {
+        Format::R8_UNORM | Format::R8_SRGB => (ColorType::L8, expect_size(1)?.to_vec()),
+        Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB => {
+            (ColorType::Rgba8, expect_size(4)?.to_vec())
+        }
+        Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB => (
+            ColorType::Rgba8,
+            expect_size(4)?
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                .collect(),
+        ),
+        Format::R16G16B16A16_SFLOAT => (
+            ColorType::Rgba32F,
+            f32_bytes(
+                &mut expect_size(8)?
+                    .chunks_exact(2)
+                    .map(|bytes| half::f16::from_le_bytes(bytes.try_into().unwrap()).to_f32()),
+            ),
+        ),
+        Format::R32G32B32A32_SFLOAT => (
+            ColorType::Rgba32F,
+            f32_bytes(
+                &mut expect_size(16)?
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())),
+            ),
+        ),
+        Format::E5B9G9R9_UFLOAT_PACK32 => (
+            ColorType::Rgb32F,
+            f32_bytes(
+                &mut expect_size(4)?
+                    .chunks_exact(4)
+                    .flat_map(|bytes| rgb9e5(u32::from_le_bytes(bytes.try_into().unwrap()))),
+            ),
+        ),
+        _ => match decode_bc(format, bytes, width, height)? {
+            DecodedPixels::Rgba8(pixels) if format == Format::BC4_UNORM_BLOCK => (
+                ColorType::L8,
+                pixels.chunks_exact(4).map(|pixel| pixel[0]).collect(),
+            ),
+            DecodedPixels::Rgba8(pixels) => (ColorType::Rgba8, pixels),
+            DecodedPixels::RgbaF32(pixels) => {
+                (ColorType::Rgba32F, f32_bytes(&mut pixels.into_iter()))
+            }
+        },
+    })
+}
+
+#[cfg(feature = "basis")]
+fn uastc_has_alpha<Data: AsRef<[u8]>>(reader: &ktx2::Reader<Data>) -> Option<bool> {
+    let block = reader.dfd_blocks().next()?;
+
+    if block.header.vendor_id != ktx2::DfdHeader::BASIC.vendor_id
+        || block.header.descriptor_type != ktx2::DfdHeader::BASIC.descriptor_type
+    {
+        return None;
+    }
+
+    crate::BasicDfd::from_bytes(block.data)
+        .ok()?
+        .uastc_has_alpha()
+}
+
+/// Transcode UASTC to BC7 and decode that, as the low-level transcoder's RGBA32 output can't be
+/// used for images more than one block wide.
+#[cfg(feature = "basis")]
+fn transcode_uastc(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+) -> Result<Vec<u8>, Error> {
+    let blocks = basis_universal::LowLevelUastcTranscoder::new()
+        .transcode_slice(
+            bytes,
+            basis_universal::SliceParametersUastc {
+                num_blocks_x: width.div_ceil(4),
+                num_blocks_y: height.div_ceil(4),
+                has_alpha,
+                original_width: width,
+                original_height: height,
+            },
+            basis_universal::DecodeFlags::HIGH_QUALITY,
+            basis_universal::transcoding::TranscoderBlockFormat::BC7,
+        )
+        .map_err(|error| Error::Decoding(format!("failed to transcode UASTC: {:?}", error)))?;
+
+    Ok(decode_bc(ktx2::Format::BC7_UNORM_BLOCK, &blocks, width, height)?.into_rgba8())
+}
+
+fn rgb9e5(value: u32) -> [f32; 3] {
+    let exponent = (value >> 27) as i32;
+    // 2^(exponent - bias - mantissa bits)
+    let scale = 2f32.powi(exponent - 15 - 9);
+
+    [value, value >> 9, value >> 18].map(|mantissa| (mantissa & 0x1ff) as f32 * scale)
+}
+
+impl<'a> image::ImageDecoder<'a> for Ktx2Decoder {
+    type Reader = std::io::Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    fn into_reader(self) -> image::ImageResult<Self::Reader> {
+        Ok(std::io::Cursor::new(self.pixels))
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> image::ImageResult<()> {
+        buf.copy_from_slice(&self.pixels);
+        Ok(())
+    }
+}
+
+/// Open an image with [`image`], or with [`Ktx2Decoder`] if it is a KTX2 file, in which case the
+/// largest level of the first layer and face is decoded.
+pub fn open_image(path: impl AsRef<Path>) -> Result<image::DynamicImage, Error> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+
+    if bytes.starts_with(&KTX2_IDENTIFIER) {
+        return image::DynamicImage::from_decoder(Ktx2Decoder::new(bytes)?)
+            .map_err(|error| Error::Decoding(error.to_string()));
+    }
+
+    let mut reader = image::io::Reader::new(std::io::Cursor::new(bytes)).with_guessed_format()?;
+
+    if reader.format().is_none() {
+        if let Ok(format) = image::ImageFormat::from_path(path) {
+            reader.set_format(format);
+        }
+    }
+
+    reader
+        .decode()
+        .map_err(|error| Error::Decoding(error.to_string()))
+}
@@ -0,0 +1,64 @@
+use crate::{Error, WriterHeader};
+use serde_json::{json, Map, Value};
+
+/// Describe a KTX2 file as JSON: the header, each level, the data format descriptor blocks and the
+/// key/value data. Field names follow the KTX2 specification. This is what `ktx2-info --json` prints.
+pub fn info_json(bytes: &[u8]) -> Result<Value, Error> {
+    let ktx2 = ktx2::Reader::new(bytes)?;
+    let header = ktx2.header();
+    let writer_header = WriterHeader::from(header);
+
+    let levels = ktx2
+        .levels()
+        .enumerate()
+        .map(|(i, level)| {
+            let [width, height, depth] = writer_header.level_dimensions(i as u32);
+
+            json!({
+                "pixelWidth": width,
+                "pixelHeight": height,
+                "pixelDepth": depth,
+                "byteLength": level.data.len(),
+                "uncompressedByteLength": level.uncompressed_byte_length,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let key_value_data = ktx2
+        .key_value_data()
+        .map(|(key, value)| {
+            (
+                key.to_string(),
+                Value::from(String::from_utf8_lossy(value).trim_end_matches('\0')),
+            )
+        })
+        .collect::<Map<_, _>>();
+
+    let data_format_descriptors = ktx2
+        .dfd_blocks()
+        .map(|dfd| {
+            json!({
+                "vendorId": dfd.header.vendor_id,
+                "descriptorType": dfd.header.descriptor_type,
+                "versionNumber": dfd.header.version_number,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(json!({
+        "vkFormat": header.format.map(|format| format!("{:?}", format)),
+        "typeSize": header.type_size,
+        "pixelWidth": header.pixel_width,
+        "pixelHeight": header.pixel_height,
+        "pixelDepth": header.pixel_depth,
+        "layerCount": header.layer_count,
+        "faceCount": header.face_count,
+        "levelCount": header.level_count,
+        "supercompressionScheme": header
+            .supercompression_scheme
+            .map(|scheme| format!("{:?}", scheme)),
+        "levels": levels,
+        "dataFormatDescriptors": data_format_descriptors,
+        "keyValueData": key_value_data,
+    }))
+}
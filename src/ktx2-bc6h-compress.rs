@@ -1,5 +1,4 @@
-use ktx2_tools::{Writer, WriterHeader};
-use std::borrow::Cow;
+use ktx2_tools::{encode_bc6h, Bc6hOptions, WriterOptions};
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -12,6 +11,8 @@ struct Opts {
     key_value_pairs: KeyValuePairs,
     #[structopt(long)]
     sphere_harmonics_file: Option<PathBuf>,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
 }
 
 #[derive(Debug)]
@@ -41,25 +42,14 @@ impl std::str::FromStr for KeyValuePairs {
 }
 
 fn main() {
-    let opts = Opts::from_args();
-
-    let bytes = std::fs::read(&opts.input).unwrap();
-    let ktx2 = ktx2::Reader::new(&bytes).unwrap();
-
-    let header = ktx2.header();
-
-    let num_levels = header
-        .level_count
-        .min((header.pixel_width.min(header.pixel_height) as f32).log2() as u32 - 1);
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
 
-    let convert_to_half = match header.format {
-        Some(ktx2::Format::R16G16B16A16_SFLOAT) => false,
-        Some(ktx2::Format::R32G32B32A32_SFLOAT) => true,
-        _ => {
-            eprintln!("Unsupported frormat: {:?}", header.format);
-            return;
-        }
-    };
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
 
     let mut key_value_pairs: BTreeMap<String, Vec<u8>> = opts
         .key_value_pairs
@@ -69,78 +59,19 @@ fn main() {
         .collect();
 
     if let Some(filename) = &opts.sphere_harmonics_file {
-        key_value_pairs.insert(
-            "sphere_harmonics".to_string(),
-            std::fs::read(filename).unwrap(),
-        );
+        key_value_pairs.insert("sphere_harmonics".to_string(), std::fs::read(filename)?);
     }
 
-    let writer = Writer {
-        header: WriterHeader {
-            format: Some(ktx2::Format::BC6H_UFLOAT_BLOCK),
-            type_size: header.type_size,
-            pixel_width: header.pixel_width,
-            pixel_height: header.pixel_height,
-            pixel_depth: header.pixel_depth,
-            layer_count: header.layer_count,
-            face_count: header.face_count,
-            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+    encode_bc6h(
+        &std::fs::read(&opts.input)?,
+        std::io::BufWriter::new(std::fs::File::create(&opts.output)?),
+        &key_value_pairs,
+        &Bc6hOptions::default(),
+        WriterOptions {
+            zstd_level: opts.zstd_level,
+            ..Default::default()
         },
-        dfd_bytes: &bytes[header.index.dfd_byte_offset as usize
-            ..(header.index.dfd_byte_offset + header.index.dfd_byte_length) as usize],
-        key_value_pairs: &key_value_pairs,
-        sgd_bytes: &[],
-        uncompressed_levels_descending: &ktx2
-            .levels()
-            .take(num_levels as usize)
-            .enumerate()
-            .map(|(i, level)| {
-                let mut level_bytes = match header.supercompression_scheme {
-                    Some(ktx2::SupercompressionScheme::Zstandard) => std::borrow::Cow::Owned(
-                        zstd::bulk::decompress(level.data, level.uncompressed_byte_length as usize)
-                            .unwrap(),
-                    ),
-                    Some(other) => todo!("{:?}", other),
-                    None => std::borrow::Cow::Borrowed(level.data),
-                };
-
-                if convert_to_half {
-                    level_bytes = level_bytes
-                        .chunks(4)
-                        .flat_map(|bytes| {
-                            half::f16::from_f32(f32::from_le_bytes(
-                                <[u8; 4]>::try_from(bytes).unwrap(),
-                            ))
-                            .to_le_bytes()
-                        })
-                        .collect();
-                }
-
-                let width = header.pixel_width >> i;
-                let height = header.pixel_height >> i;
-
-                let mut compressed = Vec::new();
-
-                for chunk in level_bytes.chunks(level_bytes.len() / 6) {
-                    let compressed_chunk = intel_tex_2::bc6h::compress_blocks(
-                        &intel_tex_2::bc6h::very_slow_settings(),
-                        &intel_tex_2::RgbaSurface {
-                            width,
-                            height,
-                            stride: width * 8,
-                            data: chunk,
-                        },
-                    );
-
-                    compressed.extend_from_slice(&compressed_chunk);
-                }
-
-                Cow::Owned(compressed)
-            })
-            .collect::<Vec<_>>(),
-    };
+    )?;
 
-    writer
-        .write(&mut std::fs::File::create(&opts.output).unwrap())
-        .unwrap();
+    Ok(())
 }
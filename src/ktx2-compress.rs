@@ -1,55 +1,61 @@
-use ktx2_tools::{Writer, WriterHeader};
-use std::borrow::Cow;
+use ktx2_tools::{Texture, WriterOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    /// The Zstandard compression level. 0 selects zstd's default level.
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
+    /// Enable zstd long-distance matching.
+    #[structopt(long)]
+    zstd_long: bool,
+    /// Override the zstd window size, as a power of two.
+    #[structopt(long)]
+    zstd_window_log: Option<u32>,
+    /// Supercompress with ZLIB instead of Zstandard.
+    #[structopt(long)]
+    zlib: bool,
+    /// The ZLIB compression level, from 0 to 9.
+    #[structopt(long)]
+    zlib_level: Option<u32>,
+    /// The number of levels to compress in parallel. Defaults to the number of CPUs.
+    #[structopt(long)]
+    threads: Option<usize>,
+}
 
 fn main() {
-    let filename = std::env::args().nth(1).unwrap();
-    let output = std::env::args().nth(2).unwrap();
-
-    let bytes = std::fs::read(filename).unwrap();
-
-    let ktx2 = ktx2::Reader::new(&bytes).unwrap();
-
-    let header = ktx2.header();
-
-    println!("{:#?}", header);
-
-    let is_zstd_compressed = match header.supercompression_scheme {
-        Some(ktx2::SupercompressionScheme::Zstandard) => true,
-        None => false,
-        Some(other) => todo!("{:?}", other),
-    };
-
-    let writer = Writer {
-        header: WriterHeader {
-            format: header.format,
-            type_size: header.type_size,
-            pixel_width: header.pixel_width,
-            pixel_height: header.pixel_height,
-            pixel_depth: header.pixel_depth,
-            layer_count: header.layer_count,
-            face_count: header.face_count,
-            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
-        },
-        dfd_bytes: &bytes[header.index.dfd_byte_offset as usize
-            ..(header.index.dfd_byte_offset + header.index.dfd_byte_length) as usize],
-        key_value_pairs: &Default::default(),
-        sgd_bytes: &[],
-        uncompressed_levels_descending: &ktx2
-            .levels()
-            .map(|level| {
-                if is_zstd_compressed {
-                    Cow::Owned(
-                        zstd::bulk::decompress(level.data, level.uncompressed_byte_length as usize)
-                            .unwrap(),
-                    )
-                } else {
-                    Cow::Borrowed(level.data)
-                }
-            })
-            .collect::<Vec<_>>(),
-    };
-
-    writer
-        .write(&mut std::fs::File::create(output).unwrap())
-        .unwrap();
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let mut texture = Texture::from_bytes(&std::fs::read(&opts.input)?)?;
+
+    println!("{:#?}", texture.header);
+
+    texture.set_supercompression_scheme(Some(if opts.zlib {
+        ktx2::SupercompressionScheme::ZLIB
+    } else {
+        ktx2::SupercompressionScheme::Zstandard
+    }))?;
+
+    texture
+        .to_writer(WriterOptions {
+            zstd_level: opts.zstd_level,
+            zstd_long_distance_matching: opts.zstd_long,
+            zstd_window_log: opts.zstd_window_log,
+            zlib_level: opts.zlib_level,
+            threads: opts.threads,
+            preserve_ktx_writer_history: true,
+        })
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
 }
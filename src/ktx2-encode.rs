@@ -0,0 +1,411 @@
+use ktx2_tools::{
+    encode_astc, encode_bc, encode_bc6h, encode_etc, encode_etc1s, encode_uastc,
+    encode_uncompressed, open_image, AstcBlockSize, AstcOptions, BasicDfd, Bc6hOptions, BcFormat,
+    BcOptions, Etc1sOptions, EtcFormat, EtcOptions, Ktx2Decoder, Metadata, UastcOptions,
+    UncompressedFormat, UncompressedOptions, WriterOptions,
+};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// An image, or a KTX2 or DDS file whose largest level is encoded.
+    input: PathBuf,
+    output: PathBuf,
+    /// rgba8, rgba16f, rgb9e5, bc1, bc3, bc4, bc5, bc6h, bc7, astc (4x4 blocks), astc-<size>
+    /// such as astc-6x6, etc2-rgb, etc2-rgba, eac-r11, eac-rg11, uastc or etc1s.
+    #[structopt(long)]
+    format: OutputFormat,
+    /// fast, default or high. This sets the UASTC and ETC1S quality levels and the zstd level.
+    /// The other encoders always use their best settings.
+    #[structopt(long, default_value = "default")]
+    quality: Quality,
+    /// Generate a full mip chain.
+    #[structopt(long)]
+    mips: bool,
+    /// Treat the input as sRGB color. This is the default for formats with sRGB variants, unless
+    /// the input is a linear KTX2 or DDS file or a float image. Float formats are always linear,
+    /// so sRGB input is converted to linear light for them.
+    #[structopt(long, conflicts_with = "linear")]
+    srgb: bool,
+    /// Treat the input as linear data, such as normal maps.
+    #[structopt(long)]
+    linear: bool,
+    /// none, zstd or zlib. ETC1S is always supercompressed with BasisLZ.
+    #[structopt(long, default_value = "zstd")]
+    supercompression: Supercompression,
+    /// Override the zstd level chosen by --quality. 0 selects zstd's default level.
+    #[structopt(long)]
+    zstd_level: Option<i32>,
+    /// The ZLIB compression level, from 0 to 9.
+    #[structopt(long)]
+    zlib_level: Option<u32>,
+    /// Key/value data to add, as key=value, e.g. KTXorientation=rd. Values of standard keys are
+    /// checked.
+    #[structopt(long = "metadata", number_of_values = 1)]
+    metadata: Vec<KeyValue>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Uncompressed(UncompressedFormat),
+    Bc(BcFormat),
+    Bc6h,
+    Astc(AstcBlockSize),
+    Etc(EtcFormat),
+    Uastc,
+    Etc1s,
+}
+
+impl OutputFormat {
+    fn has_srgb(self) -> bool {
+        match self {
+            Self::Uncompressed(format) => format.ktx2_format(true).is_some(),
+            Self::Bc(format) => format.ktx2_format(true).is_some(),
+            Self::Etc(format) => format.ktx2_format(true).is_some(),
+            Self::Bc6h => false,
+            Self::Astc(_) | Self::Uastc | Self::Etc1s => true,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(
+            self,
+            Self::Uncompressed(UncompressedFormat::Rgba16Float | UncompressedFormat::Rgb9e5)
+                | Self::Bc6h
+        )
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.to_ascii_lowercase();
+
+        match lowercase.as_str() {
+            "bc6h" => return Ok(Self::Bc6h),
+            "astc" => return Ok(Self::Astc(AstcBlockSize::Block4x4)),
+            "uastc" => return Ok(Self::Uastc),
+            "etc1s" => return Ok(Self::Etc1s),
+            _ => {}
+        }
+
+        if let Some(block_size) = lowercase.strip_prefix("astc-") {
+            return block_size.parse().map(Self::Astc);
+        }
+
+        lowercase
+            .parse()
+            .map(Self::Uncompressed)
+            .or_else(|_| lowercase.parse().map(Self::Bc))
+            .or_else(|_| lowercase.parse().map(Self::Etc))
+            .map_err(|_: String| {
+                format!(
+                    "unknown format {:?}, expected rgba8, rgba16f, rgb9e5, bc1, bc3, bc4, bc5, \
+                     bc6h, bc7, astc, astc-<size>, etc2-rgb, etc2-rgba, eac-r11, eac-rg11, uastc \
+                     or etc1s",
+                    s
+                )
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Quality {
+    Fast,
+    Default,
+    High,
+}
+
+impl Quality {
+    fn uastc_level(self) -> u32 {
+        match self {
+            Self::Fast => 0,
+            Self::Default => 2,
+            Self::High => 4,
+        }
+    }
+
+    fn etc1s_level(self) -> u32 {
+        match self {
+            Self::Fast => 64,
+            Self::Default => 128,
+            Self::High => 255,
+        }
+    }
+
+    fn zstd_level(self) -> i32 {
+        match self {
+            Self::Fast => 1,
+            Self::Default => 0,
+            Self::High => 19,
+        }
+    }
+}
+
+impl std::str::FromStr for Quality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fast" => Ok(Self::Fast),
+            "default" => Ok(Self::Default),
+            "high" => Ok(Self::High),
+            _ => Err(format!(
+                "unknown quality {:?}, expected fast, default or high",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Supercompression(Option<ktx2::SupercompressionScheme>);
+
+impl std::str::FromStr for Supercompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self(None)),
+            "zstd" => Ok(Self(Some(ktx2::SupercompressionScheme::Zstandard))),
+            "zlib" => Ok(Self(Some(ktx2::SupercompressionScheme::ZLIB))),
+            _ => Err(format!(
+                "unknown supercompression {:?}, expected none, zstd or zlib",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KeyValue(String, String);
+
+impl std::str::FromStr for KeyValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once('=')
+            .map(|(key, value)| Self(key.to_string(), value.to_string()))
+            .ok_or_else(|| format!("Could not find '=' in '{}'", s))
+    }
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let mut metadata = Metadata::default();
+
+    for KeyValue(key, value) in &opts.metadata {
+        metadata.insert(key, &[value.as_bytes(), b"\0"].concat())?;
+    }
+
+    let key_value_pairs = metadata.to_key_value_pairs();
+
+    let (image, input_srgb) = load(&opts.input)?;
+
+    let srgb = if opts.srgb {
+        true
+    } else if opts.linear {
+        false
+    } else {
+        (opts.format.has_srgb() || opts.format.is_float()) && input_srgb.unwrap_or(true)
+    };
+
+    println!(
+        "Width: {}\nHeight: {}\nFormat: {:?}\nsRGB: {}",
+        image.width(),
+        image.height(),
+        opts.format,
+        srgb
+    );
+
+    let supercompression_scheme = opts.supercompression.0;
+    let generate_mipmaps = opts.mips;
+
+    let writer_options = WriterOptions {
+        zstd_level: opts.zstd_level.unwrap_or(opts.quality.zstd_level()),
+        zlib_level: opts.zlib_level,
+        ..Default::default()
+    };
+
+    let texture = match opts.format {
+        OutputFormat::Uncompressed(format) => encode_uncompressed(
+            &image,
+            &UncompressedOptions {
+                format,
+                srgb,
+                generate_mipmaps,
+                supercompression_scheme,
+            },
+        )?,
+        OutputFormat::Bc(format) => encode_bc(
+            &image.into_rgba8(),
+            &BcOptions {
+                format,
+                srgb,
+                generate_mipmaps,
+                supercompression_scheme,
+            },
+        )?,
+        OutputFormat::Bc6h => {
+            // The BC6H encoder reads RGBA16F KTX2 files, so encode one in memory first.
+            let mut rgba16f = Vec::new();
+
+            encode_uncompressed(
+                &image,
+                &UncompressedOptions {
+                    format: UncompressedFormat::Rgba16Float,
+                    srgb,
+                    generate_mipmaps,
+                    supercompression_scheme: None,
+                },
+            )?
+            .writer(&Default::default(), Default::default())
+            .write(&mut rgba16f)?;
+
+            encode_bc6h(
+                &rgba16f,
+                std::io::BufWriter::new(std::fs::File::create(&opts.output)?),
+                &key_value_pairs,
+                &Bc6hOptions {
+                    supercompression_scheme,
+                },
+                writer_options,
+            )?;
+
+            return Ok(());
+        }
+        OutputFormat::Astc(block_size) => encode_astc(
+            &image.into_rgba8(),
+            &AstcOptions {
+                block_size,
+                srgb,
+                generate_mipmaps,
+                supercompression_scheme,
+            },
+        )?,
+        OutputFormat::Etc(format) => encode_etc(
+            &image.into_rgba8(),
+            &EtcOptions {
+                format,
+                srgb,
+                generate_mipmaps,
+                supercompression_scheme,
+            },
+        )?,
+        OutputFormat::Uastc => encode_uastc(
+            &[image.into_rgba8()],
+            1,
+            &UastcOptions {
+                quality_level: opts.quality.uastc_level(),
+                srgb,
+                generate_mipmaps,
+                supercompression_scheme,
+                ..Default::default()
+            },
+        )?,
+        OutputFormat::Etc1s => encode_etc1s(
+            &[image.into_rgba8()],
+            1,
+            &Etc1sOptions {
+                quality_level: opts.quality.etc1s_level(),
+                srgb,
+                generate_mipmaps,
+                ..Default::default()
+            },
+        )?,
+    };
+
+    texture
+        .writer(&key_value_pairs, writer_options)
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
+
+/// Load the largest level of the first layer and face of the input, along with whether it is
+/// sRGB if the input says so. Float images are linear, and other plain images are left for the
+/// caller to decide.
+fn load(path: &Path) -> Result<(image::DynamicImage, Option<bool>), Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+
+    if bytes.starts_with(b"DDS ") {
+        let dds = ddsfile::Dds::read(&bytes[..])?;
+        let format = dds_format(&dds)?;
+        let decoder =
+            Ktx2Decoder::from_texels(format, dds.get_data(0)?, dds.get_width(), dds.get_height())?;
+
+        return Ok((
+            image::DynamicImage::from_decoder(decoder)?,
+            BasicDfd::from_format(format).map(|dfd| is_srgb(&dfd)),
+        ));
+    }
+
+    let input_srgb = ktx2::Reader::new(&bytes).ok().and_then(|reader| {
+        let block = reader.dfd_blocks().next()?;
+        BasicDfd::from_bytes(block.data)
+            .ok()
+            .map(|dfd| is_srgb(&dfd))
+    });
+
+    let image = open_image(path)?;
+    let is_float = matches!(
+        image,
+        image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+    );
+
+    Ok((image, input_srgb.or(is_float.then_some(false))))
+}
+
+fn is_srgb(dfd: &BasicDfd) -> bool {
+    dfd.header.transfer_function == Some(ktx2::TransferFunction::SRGB)
+}
+
+/// The KTX2 equivalent of a DDS file's format, for the formats [`Ktx2Decoder`] can decode.
+fn dds_format(dds: &ddsfile::Dds) -> Result<ktx2::Format, String> {
+    use ddsfile::{D3DFormat, DxgiFormat};
+    use ktx2::Format;
+
+    if let Some(format) = dds.get_dxgi_format() {
+        return Ok(match format {
+            DxgiFormat::R8_UNorm => Format::R8_UNORM,
+            DxgiFormat::R8G8B8A8_UNorm => Format::R8G8B8A8_UNORM,
+            DxgiFormat::R8G8B8A8_UNorm_sRGB => Format::R8G8B8A8_SRGB,
+            DxgiFormat::B8G8R8A8_UNorm => Format::B8G8R8A8_UNORM,
+            DxgiFormat::B8G8R8A8_UNorm_sRGB => Format::B8G8R8A8_SRGB,
+            DxgiFormat::R16G16B16A16_Float => Format::R16G16B16A16_SFLOAT,
+            DxgiFormat::R32G32B32A32_Float => Format::R32G32B32A32_SFLOAT,
+            DxgiFormat::R9G9B9E5_SharedExp => Format::E5B9G9R9_UFLOAT_PACK32,
+            DxgiFormat::BC1_UNorm => Format::BC1_RGBA_UNORM_BLOCK,
+            DxgiFormat::BC1_UNorm_sRGB => Format::BC1_RGBA_SRGB_BLOCK,
+            DxgiFormat::BC2_UNorm => Format::BC2_UNORM_BLOCK,
+            DxgiFormat::BC2_UNorm_sRGB => Format::BC2_SRGB_BLOCK,
+            DxgiFormat::BC3_UNorm => Format::BC3_UNORM_BLOCK,
+            DxgiFormat::BC3_UNorm_sRGB => Format::BC3_SRGB_BLOCK,
+            DxgiFormat::BC4_UNorm => Format::BC4_UNORM_BLOCK,
+            DxgiFormat::BC4_SNorm => Format::BC4_SNORM_BLOCK,
+            DxgiFormat::BC5_UNorm => Format::BC5_UNORM_BLOCK,
+            DxgiFormat::BC5_SNorm => Format::BC5_SNORM_BLOCK,
+            DxgiFormat::BC6H_UF16 => Format::BC6H_UFLOAT_BLOCK,
+            DxgiFormat::BC6H_SF16 => Format::BC6H_SFLOAT_BLOCK,
+            DxgiFormat::BC7_UNorm => Format::BC7_UNORM_BLOCK,
+            DxgiFormat::BC7_UNorm_sRGB => Format::BC7_SRGB_BLOCK,
+            _ => return Err(format!("unsupported DXGI format: {:?}", format)),
+        });
+    }
+
+    match dds.get_d3d_format() {
+        Some(D3DFormat::A8B8G8R8) => Ok(Format::R8G8B8A8_UNORM),
+        Some(D3DFormat::A8R8G8B8) => Ok(Format::B8G8R8A8_UNORM),
+        format => Err(format!("unsupported D3D format: {:?}", format)),
+    }
+}
@@ -0,0 +1,51 @@
+use ktx2_tools::{encode_etc1s, Etc1sOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    #[structopt(long)]
+    srgb: bool,
+    /// The ETC1S quality level, from 1 to 255.
+    #[structopt(long, default_value = "128")]
+    quality: u32,
+    #[structopt(long)]
+    normal_map: bool,
+    #[structopt(long)]
+    no_mips: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let image = image::open(&opts.input)?.into_rgba8();
+
+    println!("Width: {}\nHeight: {}", image.width(), image.height());
+
+    let texture = encode_etc1s(
+        &[image],
+        1,
+        &Etc1sOptions {
+            quality_level: opts.quality,
+            srgb: opts.srgb,
+            normal_map: opts.normal_map,
+            generate_mipmaps: !opts.no_mips,
+            ..Default::default()
+        },
+    )?;
+
+    texture
+        .writer(&Default::default(), Default::default())
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
@@ -6,13 +6,29 @@ struct Opts {
     filename: PathBuf,
     #[structopt(long)]
     print_compression_percentage: bool,
+    /// Print the header, levels, data format descriptors and key/value data as JSON.
+    #[structopt(long)]
+    json: bool,
 }
 
 fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::from_args();
 
-    let file = std::fs::read(&opts.filename).unwrap();
-    let ktx2 = ktx2::Reader::new(&file).unwrap();
+    let file = std::fs::read(&opts.filename)?;
+
+    if opts.json {
+        println!("{:#}", ktx2_tools::info_json(&file)?);
+        return Ok(());
+    }
+
+    let ktx2 = ktx2::Reader::new(&file)?;
 
     let header = ktx2.header();
 
@@ -29,15 +45,12 @@ fn main() {
             file.len() as f32 / uncompressed_size as f32 * 100.0
         );
 
-        return;
+        return Ok(());
     }
 
-    let mut width = header.pixel_width;
-    let mut height = header.pixel_height;
-
     println!("[File {}]", &opts.filename.display());
-    println!("Width: {}", width);
-    println!("Height: {}", height);
+    println!("Width: {}", header.pixel_width);
+    println!("Height: {}", header.pixel_height);
     println!("Depth: {}", header.pixel_depth);
     println!("Level Count: {}", header.level_count);
     println!(
@@ -74,7 +87,10 @@ fn main() {
 
     println!();
 
+    let writer_header = ktx2_tools::WriterHeader::from(header);
+
     for (i, level) in ktx2.levels().enumerate() {
+        let [width, height, _] = writer_header.level_dimensions(i as u32);
         println!("[Level {} (width: {}, height: {})]", i, width, height);
         println!("Byte length: {}", level.data.len());
         println!(
@@ -86,8 +102,7 @@ fn main() {
             level.data.len() as f32 / level.uncompressed_byte_length as f32 * 100.0
         );
         println!();
-
-        width >>= 1;
-        height >>= 1;
     }
+
+    Ok(())
 }
@@ -1,8 +1,19 @@
+use ktx2_tools::{decompress_level, Error};
+
 fn main() {
-    let filename = std::env::args().nth(1).unwrap();
-    let bytes = std::fs::read(filename).unwrap();
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
 
-    let ktx2 = ktx2::Reader::new(&bytes).unwrap();
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: ktx2-to-dds <input> <output>";
+    let filename = std::env::args().nth(1).ok_or(usage)?;
+    let output = std::env::args().nth(2).ok_or(usage)?;
+    let bytes = std::fs::read(filename)?;
+
+    let ktx2 = ktx2::Reader::new(&bytes)?;
 
     let header = ktx2.header();
 
@@ -40,7 +51,7 @@ fn main() {
 
             (None, Some(ktx2::TransferFunction::SRGB)) => ddsfile::DxgiFormat::BC7_UNorm_sRGB,
             (None, _) => ddsfile::DxgiFormat::BC7_UNorm,
-            other => unimplemented!("unsupported format: {:?}", other),
+            (format, _) => return Err(Error::UnsupportedFormat(format).into()),
         },
         mipmap_levels: Some(header.level_count).filter(|&count| count != 0),
         array_layers: Some(header.layer_count).filter(|&count| count != 0),
@@ -52,8 +63,7 @@ fn main() {
             ddsfile::D3D10ResourceDimension::Texture2D
         },
         alpha_mode: ddsfile::AlphaMode::Opaque,
-    })
-    .unwrap();
+    })?;
 
     let face_count = header.face_count as usize;
 
@@ -62,14 +72,11 @@ fn main() {
     let transcoder = basis_universal::LowLevelUastcTranscoder::new();
 
     for (level_index, level) in ktx2.levels().enumerate() {
-        let level_bytes = match header.supercompression_scheme {
-            Some(ktx2::SupercompressionScheme::Zstandard) => std::borrow::Cow::Owned(
-                zstd::bulk::decompress(level.data, level.uncompressed_byte_length as usize)
-                    .unwrap(),
-            ),
-            Some(other) => todo!("{:?}", other),
-            None => std::borrow::Cow::Borrowed(level.data),
-        };
+        let level_bytes = decompress_level(
+            header.supercompression_scheme,
+            level.data,
+            level.uncompressed_byte_length,
+        )?;
 
         let slice_width = header.pixel_width >> level_index;
         let slice_height = header.pixel_height >> level_index;
@@ -82,12 +89,8 @@ fn main() {
                     .transcode_slice(
                         &level_bytes,
                         basis_universal::SliceParametersUastc {
-                            num_blocks_x: ((slice_width + block_width_pixels - 1)
-                                / block_width_pixels)
-                                .max(1),
-                            num_blocks_y: ((slice_height + block_height_pixels - 1)
-                                / block_height_pixels)
-                                .max(1),
+                            num_blocks_x: slice_width.div_ceil(block_width_pixels).max(1),
+                            num_blocks_y: slice_height.div_ceil(block_height_pixels).max(1),
                             has_alpha: false,
                             original_width: slice_width,
                             original_height: slice_height,
@@ -95,37 +98,10 @@ fn main() {
                         basis_universal::DecodeFlags::HIGH_QUALITY,
                         basis_universal::transcoding::TranscoderBlockFormat::BC7,
                     )
-                    .unwrap(),
+                    .map_err(|error| format!("failed to transcode UASTC: {:?}", error))?,
             )
         } else if header.format == Some(ktx2::Format::ASTC_4x4_SFLOAT_BLOCK) {
-            /*let mut context = astcenc_rs::Context::new(
-                astcenc_rs::ConfigBuilder::new()
-                    .with_profile(astcenc_rs::Profile::HdrRgba)
-                    .with_preset(astcenc_rs::Preset::Exhaustive)
-                    .with_block_size(astcenc_rs::Extents::default_block_size())
-                    .build()
-                    .unwrap(),
-            )
-            .unwrap();
-
-            dbg!(&header.pixel_depth, header.face_count);
-
-            let image: Vec<f32> = level_bytes
-                .chunks(level_bytes.len() / header.pixel_depth.max(1) as usize)
-                .map(|chunk| {
-                    context
-                        .decompress(
-                            chunk,
-                            astcenc_rs::Extents::new(slice_width, slice_height),
-                            astcenc_rs::Swizzle::rgba(),
-                        )
-                        .unwrap()
-                })
-                .flatten()
-                .collect();
-
-            Cow::Owned(image.iter().flat_map(|float| float.to_le_bytes()).collect())*/
-            panic!("astc disabled for now")
+            return Err(Error::UnsupportedFormat(header.format).into());
         } else {
             level_bytes
         };
@@ -139,9 +115,9 @@ fn main() {
 
     dds.data = faces.concat();
 
-    let output = std::env::args().nth(2).unwrap();
+    let mut output_file = std::fs::File::create(output)?;
 
-    let mut output_file = std::fs::File::create(output).unwrap();
+    dds.write(&mut output_file)?;
 
-    dds.write(&mut output_file).unwrap();
+    Ok(())
 }
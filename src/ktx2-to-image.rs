@@ -0,0 +1,244 @@
+use image::{imageops, DynamicImage, Rgba32FImage, RgbaImage};
+use ktx2_tools::{Ktx2Decoder, SubresourceReader};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Decode a KTX2 file to PNG, OpenEXR or Radiance HDR, depending on the output's extension.
+/// Floating point data written to PNG is clamped to 0-1, and sRGB data is written as-is.
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    #[structopt(long, default_value = "0")]
+    level: u32,
+    #[structopt(long, default_value = "0")]
+    layer: u32,
+    #[structopt(long, default_value = "0")]
+    face: u32,
+    /// The z-slice of 3D textures, in the largest level. Smaller levels use the slice covering
+    /// the same depth.
+    #[structopt(long, default_value = "0")]
+    z_slice: u32,
+    /// Write every level, layer and face, adding them to the output's filename.
+    #[structopt(long)]
+    all: bool,
+    /// Lay out the whole mip chain in one image, with smaller levels stacked to the right.
+    #[structopt(long)]
+    mips: bool,
+    /// Lay out the six faces of a cubemap as a horizontal cross.
+    #[structopt(long)]
+    cross: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let bytes = std::fs::read(&opts.input)?;
+    let reader = SubresourceReader::new(&bytes)?;
+    let header = reader.header();
+    let level_count = reader.level_count();
+
+    println!(
+        "Width: {}\nHeight: {}\nDepth: {}\nLevels: {}\nLayers: {}\nFaces: {}\nFormat: {:?}",
+        header.pixel_width,
+        header.pixel_height,
+        header.pixel_depth,
+        level_count,
+        header.layer_count,
+        header.face_count,
+        header.format
+    );
+
+    if opts.cross && header.face_count != 6 {
+        return Err("--cross needs a cubemap".into());
+    }
+
+    let levels = if opts.all || opts.mips {
+        (0..level_count).collect()
+    } else {
+        vec![opts.level]
+    };
+    let layers = if opts.all {
+        (0..header.layer_count.max(1)).collect()
+    } else {
+        vec![opts.layer]
+    };
+    let faces = if opts.all || opts.cross {
+        (0..header.face_count).collect()
+    } else {
+        vec![opts.face]
+    };
+
+    let level_groups = if opts.mips {
+        vec![levels]
+    } else {
+        levels.into_iter().map(|level| vec![level]).collect()
+    };
+    let face_groups = if opts.cross {
+        vec![faces]
+    } else {
+        faces.into_iter().map(|face| vec![face]).collect()
+    };
+
+    for &layer in &layers {
+        for levels in &level_groups {
+            for faces in &face_groups {
+                let images = levels
+                    .iter()
+                    .map(|&level| {
+                        let z_slice = opts.z_slice.checked_shr(level).unwrap_or(0);
+
+                        let images = faces
+                            .iter()
+                            .map(|&face| {
+                                let decoder = Ktx2Decoder::with_subresource(
+                                    &bytes, level, layer, face, z_slice,
+                                )?;
+                                Ok(DynamicImage::from_decoder(decoder)?)
+                            })
+                            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+
+                        Ok(if opts.cross {
+                            cube_cross(&images)
+                        } else {
+                            images.into_iter().next().unwrap()
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+
+                let image = if opts.mips {
+                    contact_sheet(&images)
+                } else {
+                    images.into_iter().next().unwrap()
+                };
+
+                let mut suffix = String::new();
+
+                if layers.len() > 1 {
+                    suffix += &format!("-layer{}", layer);
+                }
+                if level_groups.len() > 1 {
+                    suffix += &format!("-level{}", levels[0]);
+                }
+                if face_groups.len() > 1 {
+                    suffix += &format!("-face{}", faces[0]);
+                }
+
+                let path = with_suffix(&opts.output, &suffix);
+                save(image, &path)?;
+                println!("Wrote {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Levels from largest to smallest, with the first on the left and the rest stacked in a column
+/// to its right.
+fn contact_sheet(levels: &[DynamicImage]) -> DynamicImage {
+    let (first, rest) = levels.split_first().unwrap();
+    let mut positions = vec![(0, 0)];
+    let mut y = 0;
+
+    for level in rest {
+        positions.push((first.width(), y));
+        y += level.height();
+    }
+
+    let width = first.width() + rest.iter().map(|level| level.width()).max().unwrap_or(0);
+    compose(levels, &positions, width, first.height().max(y))
+}
+
+/// Faces in KTX2 order (+X, -X, +Y, -Y, +Z, -Z), laid out as:
+///
+/// ```text
+///      +Y
+///  -X  +Z  +X  -Z
+///      -Y
+/// ```
+fn cube_cross(faces: &[DynamicImage]) -> DynamicImage {
+    let size = faces[0].width();
+    let cells = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+    let positions = cells.map(|(x, y)| (x * size, y * size));
+
+    compose(faces, &positions, size * 4, size * 3)
+}
+
+/// Copy images onto a transparent canvas, keeping floating point precision if any of them have
+/// it.
+fn compose(
+    images: &[DynamicImage],
+    positions: &[(u32, u32)],
+    width: u32,
+    height: u32,
+) -> DynamicImage {
+    let is_float = images.iter().any(|image| {
+        matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        )
+    });
+
+    if is_float {
+        let mut canvas = Rgba32FImage::new(width, height);
+
+        for (image, &(x, y)) in images.iter().zip(positions) {
+            imageops::replace(&mut canvas, &image.to_rgba32f(), x as i64, y as i64);
+        }
+
+        canvas.into()
+    } else {
+        let mut canvas = RgbaImage::new(width, height);
+
+        for (image, &(x, y)) in images.iter().zip(positions) {
+            imageops::replace(&mut canvas, &image.to_rgba8(), x as i64, y as i64);
+        }
+
+        canvas.into()
+    }
+}
+
+/// `output.png` with a suffix of `-level1` becomes `output-level1.png`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut filename = path.file_stem().unwrap_or_default().to_os_string();
+    filename.push(suffix);
+
+    if let Some(extension) = path.extension() {
+        filename.push(".");
+        filename.push(extension);
+    }
+
+    path.with_file_name(filename)
+}
+
+fn save(image: DynamicImage, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let is_float = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+
+    match image::ImageFormat::from_path(path)? {
+        image::ImageFormat::Hdr => {
+            let rgb = image.into_rgb32f();
+
+            image::codecs::hdr::HdrEncoder::new(std::fs::File::create(path)?).encode(
+                &rgb.pixels().copied().collect::<Vec<_>>(),
+                rgb.width() as usize,
+                rgb.height() as usize,
+            )?;
+        }
+        image::ImageFormat::OpenExr => DynamicImage::from(image.into_rgba32f()).save(path)?,
+        _ if is_float => DynamicImage::from(image.into_rgba8()).save(path)?,
+        _ => image.save(path)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use ktx2_tools::{encode_uastc, UastcOptions, WriterOptions};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    input: PathBuf,
+    output: PathBuf,
+    #[structopt(long)]
+    srgb: bool,
+    /// The UASTC quality level, from 0 (fastest) to 4 (slowest).
+    #[structopt(long, default_value = "2")]
+    quality: u32,
+    /// Enable rate distortion optimization with this lambda, e.g. 1.0. Higher values compress
+    /// better but lose more quality.
+    #[structopt(long)]
+    rdo_lambda: Option<f32>,
+    #[structopt(long)]
+    no_zstd: bool,
+    #[structopt(long, default_value = "0")]
+    zstd_level: i32,
+    #[structopt(long)]
+    no_mips: bool,
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let image = image::open(&opts.input)?.into_rgba8();
+
+    println!("Width: {}\nHeight: {}", image.width(), image.height());
+
+    let texture = encode_uastc(
+        &[image],
+        1,
+        &UastcOptions {
+            quality_level: opts.quality,
+            rdo_lambda: opts.rdo_lambda,
+            srgb: opts.srgb,
+            generate_mipmaps: !opts.no_mips,
+            supercompression_scheme: if opts.no_zstd {
+                None
+            } else {
+                Some(ktx2::SupercompressionScheme::Zstandard)
+            },
+            ..Default::default()
+        },
+    )?;
+
+    texture
+        .writer(
+            &Default::default(),
+            WriterOptions {
+                zstd_level: opts.zstd_level,
+                ..Default::default()
+            },
+        )
+        .write(&mut std::fs::File::create(&opts.output)?)?;
+
+    Ok(())
+}
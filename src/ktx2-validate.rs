@@ -0,0 +1,73 @@
+use ktx2_tools::{validate, Severity};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    #[structopt(required = true)]
+    filenames: Vec<PathBuf>,
+    /// Print a JSON report instead of text.
+    #[structopt(long)]
+    json: bool,
+    /// Fail on warnings as well as errors.
+    #[structopt(long)]
+    warnings_as_errors: bool,
+}
+
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns whether every file passed validation.
+fn run() -> Result<bool, Box<dyn std::error::Error>> {
+    let opts = Opts::from_args();
+
+    let failing_severity = if opts.warnings_as_errors {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let mut passed = true;
+    let mut report = Vec::new();
+
+    for filename in &opts.filenames {
+        let issues = validate(&std::fs::read(filename)?);
+        let file_passed = issues.iter().all(|issue| issue.severity < failing_severity);
+
+        passed &= file_passed;
+
+        if opts.json {
+            report.push(serde_json::json!({
+                "file": filename.display().to_string(),
+                "passed": file_passed,
+                "issues": issues
+                    .iter()
+                    .map(|issue| serde_json::json!({
+                        "severity": issue.severity.to_string(),
+                        "message": issue.message,
+                    }))
+                    .collect::<Vec<_>>(),
+            }));
+        } else if issues.is_empty() {
+            println!("{}: ok", filename.display());
+        } else {
+            for issue in &issues {
+                println!("{}: {}", filename.display(), issue);
+            }
+        }
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    Ok(passed)
+}
@@ -1,139 +1,213 @@
+#[cfg(feature = "astc-encode")]
+mod astc;
+#[cfg(feature = "basis")]
+mod basis;
+#[cfg(feature = "bc-encode")]
+mod bc;
+#[cfg(feature = "bc-decode")]
+mod bc_decode;
+#[cfg(feature = "writer")]
+mod builder;
+mod dfd;
+mod error;
+#[cfg(feature = "etc-encode")]
+mod etc;
+#[cfg(feature = "image-io")]
+mod image_decoder;
+#[cfg(feature = "json")]
+mod info;
+mod metadata;
+#[cfg(feature = "image-io")]
+mod mipmap;
+mod ranges;
+mod reader;
+#[cfg(feature = "writer")]
+mod stream;
+mod supercompression;
+mod texture;
+#[cfg(all(feature = "writer", feature = "image-io"))]
+mod uncompressed;
+mod validate;
+#[cfg(feature = "writer")]
+mod writer;
+
+#[cfg(feature = "astc-encode")]
+pub use astc::{encode_astc, AstcBlockSize, AstcOptions};
+#[cfg(feature = "basis")]
+pub use basis::{encode_etc1s, encode_uastc, Etc1sOptions, UastcOptions};
+#[cfg(feature = "bc-encode")]
+pub use bc::{encode_bc, encode_bc6h, encode_bc7, Bc6hOptions, Bc7Options, BcFormat, BcOptions};
+#[cfg(feature = "bc-decode")]
+pub use bc_decode::{decode_bc, DecodedPixels};
+#[cfg(feature = "writer")]
+pub use builder::{TextureKind, WriterBuilder};
+pub use dfd::BasicDfd;
+pub use error::Error;
+#[cfg(feature = "etc-encode")]
+pub use etc::{encode_etc, EtcFormat, EtcOptions};
+#[cfg(feature = "image-io")]
+pub use image_decoder::{open_image, Ktx2Decoder};
+#[cfg(feature = "json")]
+pub use info::info_json;
 pub use ktx2;
-use std::borrow::Cow;
-use std::collections::BTreeMap;
-
-pub struct Writer<'a> {
-    pub header: WriterHeader,
-    pub dfd_bytes: &'a [u8],
-    pub key_value_pairs: &'a BTreeMap<String, Vec<u8>>,
-    pub sgd_bytes: &'a [u8],
-    pub uncompressed_levels_descending: &'a [Cow<'a, [u8]>],
+pub use metadata::{
+    AnimData, AstcDecodeMode, GlFormat, Metadata, Orientation, OrientationX, OrientationY,
+    OrientationZ, Swizzle,
+};
+#[cfg(feature = "image-io")]
+pub use mipmap::{generate_mip_chain, resize_mip_level};
+pub use ranges::{level_index_length, plan_byte_ranges};
+pub use reader::SubresourceReader;
+#[cfg(feature = "writer")]
+pub use stream::StreamingWriter;
+#[cfg(feature = "writer")]
+pub use supercompression::compress_level;
+pub use supercompression::decompress_level;
+pub use texture::Texture;
+#[cfg(all(feature = "writer", feature = "image-io"))]
+pub use uncompressed::{encode_uncompressed, UncompressedFormat, UncompressedOptions};
+pub use validate::{validate, Issue, Severity};
+#[cfg(feature = "writer")]
+pub use writer::{EncodedTexture, Writer, WriterOptions};
+
+fn align(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
 }
 
-impl<'a> Writer<'a> {
-    pub fn write<T: std::io::Write>(&self, writer: &mut T) -> std::io::Result<()> {
-        let dfd_offset = ktx2::Header::LENGTH
-            + self.uncompressed_levels_descending.len() * ktx2::LevelIndex::LENGTH;
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
 
-        let mut key_value_pairs = self.key_value_pairs.clone();
+    a / gcd(a, b) * b
+}
 
-        key_value_pairs.insert(
-            "KTXwriter".to_string(),
-            concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"), "\0")
-                .as_bytes()
-                .to_vec(),
-        );
+#[derive(Clone, Copy, Debug)]
+pub struct WriterHeader {
+    pub format: Option<ktx2::Format>,
+    pub type_size: u32,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub pixel_depth: u32,
+    pub layer_count: u32,
+    pub face_count: u32,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
 
-        let mut kvd_bytes = Vec::new();
+impl From<ktx2::Header> for WriterHeader {
+    fn from(header: ktx2::Header) -> Self {
+        Self {
+            format: header.format,
+            type_size: header.type_size,
+            pixel_width: header.pixel_width,
+            pixel_height: header.pixel_height,
+            pixel_depth: header.pixel_depth,
+            layer_count: header.layer_count,
+            face_count: header.face_count,
+            supercompression_scheme: header.supercompression_scheme,
+        }
+    }
+}
 
-        for (key, value) in key_value_pairs.iter() {
-            let length = (key.len() + 1 + value.len()) as u32;
+impl WriterHeader {
+    /// The maximum number of mip levels for the header's dimensions.
+    pub fn max_level_count(&self) -> u32 {
+        let largest = self
+            .pixel_width
+            .max(self.pixel_height)
+            .max(self.pixel_depth)
+            .max(1);
 
-            kvd_bytes.extend_from_slice(&length.to_le_bytes());
+        32 - largest.leading_zeros()
+    }
 
-            kvd_bytes.extend_from_slice(key.as_bytes());
+    #[cfg(feature = "writer")]
+    pub(crate) fn check_level_size(&self, level: usize, data: &[u8]) -> Result<(), Error> {
+        match self.level_size(level as u32) {
+            Some(expected) if data.len() as u64 != expected => Err(Error::LevelSizeMismatch {
+                level,
+                expected,
+                actual: data.len() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
 
-            kvd_bytes.push(b'\0');
+    /// The `uncompressedByteLength` to record for a level. BasisLZ levels have no meaningful
+    /// uncompressed size.
+    #[cfg(feature = "writer")]
+    pub(crate) fn uncompressed_byte_length(&self, data: &[u8]) -> u64 {
+        match self.supercompression_scheme {
+            Some(ktx2::SupercompressionScheme::BasisLZ) => 0,
+            _ => data.len() as u64,
+        }
+    }
 
-            kvd_bytes.extend_from_slice(value);
+    /// The width, height and depth of a level, in pixels. Depth is 1 for 2D textures.
+    pub fn level_dimensions(&self, level: u32) -> [u32; 3] {
+        let size = |size: u32| size.checked_shr(level).unwrap_or(0).max(1);
 
-            while kvd_bytes.len() % 4 != 0 {
-                kvd_bytes.push(0);
-            }
-        }
+        [
+            size(self.pixel_width),
+            size(self.pixel_height),
+            size(self.pixel_depth),
+        ]
+    }
 
-        writer.write_all(
-            &ktx2::Header {
-                format: self.header.format,
-                type_size: self.header.type_size,
-                pixel_width: self.header.pixel_width,
-                pixel_height: self.header.pixel_height,
-                pixel_depth: self.header.pixel_depth,
-                layer_count: self.header.layer_count,
-                face_count: self.header.face_count,
-                supercompression_scheme: self.header.supercompression_scheme,
-                level_count: self.uncompressed_levels_descending.len() as u32,
-                index: ktx2::Index {
-                    dfd_byte_length: self.dfd_bytes.len() as u32,
-                    kvd_byte_length: kvd_bytes.len() as u32,
-                    sgd_byte_length: self.sgd_bytes.len() as u64,
-                    dfd_byte_offset: dfd_offset as u32,
-                    kvd_byte_offset: if kvd_bytes.is_empty() {
-                        0
-                    } else {
-                        dfd_offset + self.dfd_bytes.len()
-                    } as u32,
-                    sgd_byte_offset: if self.sgd_bytes.is_empty() {
-                        0
-                    } else {
-                        dfd_offset + self.dfd_bytes.len() + kvd_bytes.len()
-                    } as u64,
-                },
-            }
-            .as_bytes()[..],
-        )?;
-
-        let mut offset = dfd_offset + self.dfd_bytes.len() + kvd_bytes.len() + self.sgd_bytes.len();
-
-        let compressed_levels: Vec<Cow<[u8]>> = self
-            .uncompressed_levels_descending
-            .iter()
-            .map(|level| match self.header.supercompression_scheme {
-                Some(ktx2::SupercompressionScheme::Zstandard) => {
-                    Cow::Owned(zstd::bulk::compress(level, 0).unwrap())
-                }
-                Some(other) => panic!("{:?}", other),
-                None => {
-                    let level: &[u8] = level;
-                    Cow::Borrowed(level)
-                }
-            })
-            .collect();
-
-        let mut levels = self
-            .uncompressed_levels_descending
-            .iter()
-            .zip(&compressed_levels)
-            .rev()
-            .map(|(uncompressed_level, level)| {
-                let index = ktx2::LevelIndex {
-                    byte_offset: offset as u64,
-                    byte_length: level.len() as u64,
-                    uncompressed_byte_length: uncompressed_level.len() as u64,
-                };
-
-                offset += level.len();
-
-                index
-            })
-            .collect::<Vec<_>>();
-
-        levels.reverse();
-
-        for level in levels {
-            writer.write_all(&level.as_bytes())?;
+    /// The byte range of a single z-slice of a face of an array layer within an uncompressed
+    /// level of `level_length` bytes, or `None` if the subresource is out of range.
+    ///
+    /// Levels are laid out by layer, then face, then z-slice, with every subresource in a level
+    /// the same size.
+    pub fn subresource_range(
+        &self,
+        level: u32,
+        level_length: usize,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Option<std::ops::Range<usize>> {
+        let layer_count = self.layer_count.max(1);
+        let depth = self.level_dimensions(level)[2];
+
+        if layer >= layer_count || face >= self.face_count || z_slice >= depth {
+            return None;
         }
 
-        writer.write_all(self.dfd_bytes)?;
-        writer.write_all(&kvd_bytes)?;
-        writer.write_all(self.sgd_bytes)?;
+        let count = layer_count as usize * self.face_count as usize * depth as usize;
 
-        for level in compressed_levels.iter().rev() {
-            writer.write_all(level)?;
+        if count == 0 || !level_length.is_multiple_of(count) {
+            return None;
         }
 
-        Ok(())
+        let size = level_length / count;
+        let index = ((layer as usize * self.face_count as usize) + face as usize) * depth as usize
+            + z_slice as usize;
+
+        Some(index * size..(index + 1) * size)
     }
-}
 
-#[derive(Clone, Copy, Debug)]
-pub struct WriterHeader {
-    pub format: Option<ktx2::Format>,
-    pub type_size: u32,
-    pub pixel_width: u32,
-    pub pixel_height: u32,
-    pub pixel_depth: u32,
-    pub layer_count: u32,
-    pub face_count: u32,
-    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+    /// The expected uncompressed size of a level in bytes, covering all layers, faces and depth
+    /// slices. Returns `None` if the format's texel block size isn't known or the size overflows.
+    pub fn level_size(&self, level: u32) -> Option<u64> {
+        let dfd = BasicDfd::from_format(self.format?)?;
+        let [block_width, block_height, block_depth, _] = dfd.block_dimensions();
+
+        let blocks = |size: u32, block_size: u32| {
+            size.checked_shr(level)
+                .unwrap_or(0)
+                .max(1)
+                .div_ceil(block_size) as u64
+        };
+
+        blocks(self.pixel_width, block_width)
+            .checked_mul(blocks(self.pixel_height, block_height))?
+            .checked_mul(blocks(self.pixel_depth, block_depth))?
+            .checked_mul(dfd.bytes_per_block() as u64)?
+            .checked_mul(self.layer_count.max(1) as u64)?
+            .checked_mul(self.face_count as u64)
+    }
 }
@@ -0,0 +1,374 @@
+use crate::Error;
+use std::collections::BTreeMap;
+
+pub(crate) const KTX_ORIENTATION: &str = "KTXorientation";
+pub(crate) const KTX_SWIZZLE: &str = "KTXswizzle";
+pub(crate) const KTX_GL_FORMAT: &str = "KTXglFormat";
+pub(crate) const KTX_DXGI_FORMAT: &str = "KTXdxgiFormat__";
+pub(crate) const KTX_METAL_PIXEL_FORMAT: &str = "KTXmetalPixelFormat";
+pub(crate) const KTX_CUBEMAP_INCOMPLETE: &str = "KTXcubemapIncomplete";
+pub(crate) const KTX_ANIM_DATA: &str = "KTXanimData";
+pub(crate) const KTX_ASTC_DECODE_MODE: &str = "KTXastcDecodeMode";
+pub(crate) const KTX_WRITER: &str = "KTXwriter";
+pub(crate) const KTX_WRITER_SC_PARAMS: &str = "KTXwriterScParams";
+
+/// The keys defined by the KTX2 specification.
+pub(crate) const STANDARD_KEYS: &[&str] = &[
+    KTX_ORIENTATION,
+    KTX_SWIZZLE,
+    KTX_GL_FORMAT,
+    KTX_DXGI_FORMAT,
+    KTX_METAL_PIXEL_FORMAT,
+    KTX_CUBEMAP_INCOMPLETE,
+    KTX_ANIM_DATA,
+    KTX_ASTC_DECODE_MODE,
+    KTX_WRITER,
+    KTX_WRITER_SC_PARAMS,
+];
+
+/// Typed key/value data. Standard keys are parsed into their own fields and anything else is
+/// kept as raw bytes in `other`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub orientation: Option<Orientation>,
+    pub swizzle: Option<[Swizzle; 4]>,
+    pub gl_format: Option<GlFormat>,
+    pub dxgi_format: Option<u32>,
+    pub metal_pixel_format: Option<u32>,
+    /// A bitmask of the cubemap faces that are present, with +X, -X, +Y, -Y, +Z and -Z in bits 0
+    /// to 5.
+    pub cubemap_incomplete: Option<u8>,
+    pub anim_data: Option<AnimData>,
+    pub astc_decode_mode: Option<AstcDecodeMode>,
+    /// The tools that wrote the file.
+    pub writer: Option<String>,
+    /// The options the writer used for supercompression.
+    pub writer_sc_params: Option<String>,
+    pub other: BTreeMap<String, Vec<u8>>,
+}
+
+impl Metadata {
+    pub fn parse<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Result<Self, Error> {
+        let mut metadata = Self::default();
+
+        for (key, value) in pairs {
+            metadata.insert(key, value)?;
+        }
+
+        Ok(metadata)
+    }
+
+    pub fn from_reader<Data: AsRef<[u8]>>(reader: &ktx2::Reader<Data>) -> Result<Self, Error> {
+        Self::parse(reader.key_value_data())
+    }
+
+    /// Parse and set a single key. Values of standard keys are validated, and an error is
+    /// returned if they are malformed.
+    pub fn insert(&mut self, key: &str, value: &[u8]) -> Result<(), Error> {
+        let invalid = || Error::InvalidMetadata(key.to_string());
+
+        match key {
+            KTX_ORIENTATION => {
+                self.orientation = Some(
+                    Orientation::parse(string(value).ok_or_else(invalid)?).ok_or_else(invalid)?,
+                )
+            }
+            KTX_SWIZZLE => {
+                let swizzle = string(value)
+                    .ok_or_else(invalid)?
+                    .chars()
+                    .map(Swizzle::from_char)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?;
+
+                self.swizzle = Some(swizzle.try_into().map_err(|_| invalid())?);
+            }
+            KTX_GL_FORMAT => {
+                let [internal_format, format, ty] = u32s(value).ok_or_else(invalid)?;
+                self.gl_format = Some(GlFormat {
+                    internal_format,
+                    format,
+                    ty,
+                });
+            }
+            KTX_DXGI_FORMAT => {
+                let [format] = u32s(value).ok_or_else(invalid)?;
+                self.dxgi_format = Some(format);
+            }
+            KTX_METAL_PIXEL_FORMAT => {
+                let [format] = u32s(value).ok_or_else(invalid)?;
+                self.metal_pixel_format = Some(format);
+            }
+            KTX_CUBEMAP_INCOMPLETE => match value {
+                &[faces] if faces != 0 && faces & !0b11_1111 == 0 => {
+                    self.cubemap_incomplete = Some(faces)
+                }
+                _ => return Err(invalid()),
+            },
+            KTX_ANIM_DATA => {
+                let [duration, timescale, loop_count] = u32s(value).ok_or_else(invalid)?;
+                self.anim_data = Some(AnimData {
+                    duration,
+                    timescale,
+                    loop_count,
+                });
+            }
+            KTX_ASTC_DECODE_MODE => {
+                self.astc_decode_mode = Some(match string(value).ok_or_else(invalid)? {
+                    "rgb9e5" => AstcDecodeMode::Rgb9e5,
+                    "unorm8" => AstcDecodeMode::Unorm8,
+                    _ => return Err(invalid()),
+                })
+            }
+            KTX_WRITER => self.writer = Some(string(value).ok_or_else(invalid)?.to_string()),
+            KTX_WRITER_SC_PARAMS => {
+                self.writer_sc_params = Some(string(value).ok_or_else(invalid)?.to_string())
+            }
+            _ => {
+                self.other.insert(key.to_string(), value.to_vec());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serialize the metadata into key/value pairs for a [`Writer`](crate::Writer).
+    pub fn to_key_value_pairs(&self) -> BTreeMap<String, Vec<u8>> {
+        let mut pairs = self.other.clone();
+        let mut insert = |key: &str, value: Vec<u8>| pairs.insert(key.to_string(), value);
+
+        if let Some(orientation) = self.orientation {
+            insert(KTX_ORIENTATION, nul_terminated(&orientation.to_string()));
+        }
+
+        if let Some(swizzle) = self.swizzle {
+            let swizzle = swizzle
+                .iter()
+                .map(|swizzle| swizzle.to_char())
+                .collect::<String>();
+            insert(KTX_SWIZZLE, nul_terminated(&swizzle));
+        }
+
+        if let Some(gl_format) = self.gl_format {
+            insert(
+                KTX_GL_FORMAT,
+                le_bytes(&[gl_format.internal_format, gl_format.format, gl_format.ty]),
+            );
+        }
+
+        if let Some(format) = self.dxgi_format {
+            insert(KTX_DXGI_FORMAT, le_bytes(&[format]));
+        }
+
+        if let Some(format) = self.metal_pixel_format {
+            insert(KTX_METAL_PIXEL_FORMAT, le_bytes(&[format]));
+        }
+
+        if let Some(faces) = self.cubemap_incomplete {
+            insert(KTX_CUBEMAP_INCOMPLETE, vec![faces]);
+        }
+
+        if let Some(anim_data) = self.anim_data {
+            insert(
+                KTX_ANIM_DATA,
+                le_bytes(&[
+                    anim_data.duration,
+                    anim_data.timescale,
+                    anim_data.loop_count,
+                ]),
+            );
+        }
+
+        if let Some(mode) = self.astc_decode_mode {
+            let mode = match mode {
+                AstcDecodeMode::Rgb9e5 => "rgb9e5",
+                AstcDecodeMode::Unorm8 => "unorm8",
+            };
+            insert(KTX_ASTC_DECODE_MODE, nul_terminated(mode));
+        }
+
+        if let Some(writer) = &self.writer {
+            insert(KTX_WRITER, nul_terminated(writer));
+        }
+
+        if let Some(params) = &self.writer_sc_params {
+            insert(KTX_WRITER_SC_PARAMS, nul_terminated(params));
+        }
+
+        pairs
+    }
+}
+
+/// The logical orientation of the texture data, as in `KTXorientation`. 1D textures only have
+/// an `x` orientation and 2D textures have no `z` orientation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Orientation {
+    pub x: OrientationX,
+    pub y: Option<OrientationY>,
+    pub z: Option<OrientationZ>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrientationX {
+    Right,
+    Left,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrientationY {
+    Down,
+    Up,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrientationZ {
+    Out,
+    In,
+}
+
+impl Orientation {
+    fn parse(value: &str) -> Option<Self> {
+        let mut chars = value.chars();
+
+        let x = match chars.next()? {
+            'r' => OrientationX::Right,
+            'l' => OrientationX::Left,
+            _ => return None,
+        };
+
+        let y = match chars.next() {
+            Some('d') => Some(OrientationY::Down),
+            Some('u') => Some(OrientationY::Up),
+            None => None,
+            _ => return None,
+        };
+
+        let z = match chars.next() {
+            Some('o') if y.is_some() => Some(OrientationZ::Out),
+            Some('i') if y.is_some() => Some(OrientationZ::In),
+            None => None,
+            _ => return None,
+        };
+
+        if chars.next().is_some() {
+            return None;
+        }
+
+        Some(Self { x, y, z })
+    }
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let x = match self.x {
+            OrientationX::Right => "r",
+            OrientationX::Left => "l",
+        };
+        let y = match self.y {
+            Some(OrientationY::Down) => "d",
+            Some(OrientationY::Up) => "u",
+            None => "",
+        };
+        let z = match self.z {
+            Some(OrientationZ::Out) => "o",
+            Some(OrientationZ::In) => "i",
+            None => "",
+        };
+
+        write!(f, "{}{}{}", x, y, z)
+    }
+}
+
+/// The source of a component in `KTXswizzle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swizzle {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Zero,
+    One,
+}
+
+impl Swizzle {
+    fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            'r' => Self::Red,
+            'g' => Self::Green,
+            'b' => Self::Blue,
+            'a' => Self::Alpha,
+            '0' => Self::Zero,
+            '1' => Self::One,
+            _ => return None,
+        })
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Self::Red => 'r',
+            Self::Green => 'g',
+            Self::Blue => 'b',
+            Self::Alpha => 'a',
+            Self::Zero => '0',
+            Self::One => '1',
+        }
+    }
+}
+
+/// The OpenGL format of the texture data, for formats that have no `vkFormat`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlFormat {
+    pub internal_format: u32,
+    pub format: u32,
+    pub ty: u32,
+}
+
+/// Animation timing for textures whose array layers are frames, as in `KTXanimData`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimData {
+    /// The duration of each frame, in units of `timescale`.
+    pub duration: u32,
+    /// The number of time units per second.
+    pub timescale: u32,
+    /// The number of times to play the animation, or 0 to loop forever.
+    pub loop_count: u32,
+}
+
+/// The precision that ASTC data should be decoded to, as in `KTXastcDecodeMode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AstcDecodeMode {
+    Rgb9e5,
+    Unorm8,
+}
+
+/// A NUL-terminated UTF-8 value, without the NUL.
+fn string(value: &[u8]) -> Option<&str> {
+    std::str::from_utf8(value.strip_suffix(&[0])?).ok()
+}
+
+fn nul_terminated(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn u32s<const N: usize>(value: &[u8]) -> Option<[u32; N]> {
+    if value.len() != N * 4 {
+        return None;
+    }
+
+    let mut values = [0; N];
+
+    for (value, bytes) in values.iter_mut().zip(value.chunks_exact(4)) {
+        *value = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    Some(values)
+}
+
+fn le_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
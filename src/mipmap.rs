@@ -0,0 +1,84 @@
+use image::imageops::FilterType;
+use image::{Rgba32FImage, RgbaImage};
+
+/// Resize an RGBA image to `width` by `height` for a mip level. When `srgb` is set, the color
+/// channels are converted to linear light before filtering and back to sRGB afterwards, so that
+/// high-contrast detail doesn't darken as it is averaged away. Alpha is always filtered as is.
+pub fn resize_mip_level(image: &RgbaImage, width: u32, height: u32, srgb: bool) -> RgbaImage {
+    if !srgb {
+        return image::imageops::resize(image, width, height, FilterType::Triangle);
+    }
+
+    resize_linear(&to_linear(image), width, height)
+}
+
+/// Resize an RGBA image to each level of a mip chain of `level_count` levels, as
+/// [`resize_mip_level`] does. Every level is resized from the full image so that filtering errors
+/// don't accumulate, and the first level is a copy of the image.
+pub fn generate_mip_chain(image: &RgbaImage, level_count: u32, srgb: bool) -> Vec<RgbaImage> {
+    let size = |size: u32, level: u32| size.checked_shr(level).unwrap_or(0).max(1);
+    // Only linearize once for the whole chain.
+    let linear = srgb.then(|| to_linear(image));
+
+    (0..level_count)
+        .map(|level| {
+            let (width, height) = (size(image.width(), level), size(image.height(), level));
+
+            if level == 0 {
+                return image.clone();
+            }
+
+            match &linear {
+                Some(linear) => resize_linear(linear, width, height),
+                None => image::imageops::resize(image, width, height, FilterType::Triangle),
+            }
+        })
+        .collect()
+}
+
+fn to_linear(image: &RgbaImage) -> Rgba32FImage {
+    let table: [f32; 256] = std::array::from_fn(|value| srgb_to_linear(value as f32 / 255.0));
+
+    Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let [red, green, blue, alpha] = image.get_pixel(x, y).0;
+
+        image::Rgba([
+            table[red as usize],
+            table[green as usize],
+            table[blue as usize],
+            alpha as f32 / 255.0,
+        ])
+    })
+}
+
+fn resize_linear(linear: &Rgba32FImage, width: u32, height: u32) -> RgbaImage {
+    let resized = image::imageops::resize(linear, width, height, FilterType::Triangle);
+    let quantize = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let [red, green, blue, alpha] = resized.get_pixel(x, y).0;
+
+        image::Rgba([
+            quantize(linear_to_srgb(red)),
+            quantize(linear_to_srgb(green)),
+            quantize(linear_to_srgb(blue)),
+            quantize(alpha),
+        ])
+    })
+}
+
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
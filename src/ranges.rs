@@ -0,0 +1,78 @@
+use crate::Error;
+use ktx2::{Header, LevelIndex, ParseError};
+use std::ops::Range;
+
+/// The number of bytes at the start of a file that [`plan_byte_ranges`] needs: the header and
+/// the level index. `header` must hold at least the header itself.
+pub fn level_index_length(header: &[u8]) -> Result<usize, Error> {
+    let header = parse_header(header)?;
+    Ok(Header::LENGTH + header.level_count.max(1) as usize * LevelIndex::LENGTH)
+}
+
+/// The byte ranges of a file needed to load `levels`, sorted and with adjacent ranges merged.
+///
+/// The ranges always start with everything before the level data: the header, level index,
+/// DFD, key/value data and the supercompression global data that BasisLZ levels need to be
+/// transcoded. As levels are stored smallest first, the smallest levels usually merge into
+/// this range. `prefix` must hold at least the first [`level_index_length`] bytes of the file.
+pub fn plan_byte_ranges(
+    prefix: &[u8],
+    levels: impl IntoIterator<Item = u32>,
+) -> Result<Vec<Range<u64>>, Error> {
+    let header = parse_header(prefix)?;
+    let index_end = level_index_length(prefix)?;
+    let level_index = prefix
+        .get(Header::LENGTH..index_end)
+        .ok_or(ParseError::UnexpectedEnd)?
+        .chunks_exact(LevelIndex::LENGTH)
+        .map(|bytes| LevelIndex::from_bytes(bytes.try_into().unwrap()))
+        .collect::<Vec<_>>();
+
+    let end = |offset: u64, length: u64| offset.saturating_add(length);
+
+    let metadata_end = (index_end as u64)
+        .max(end(
+            header.index.dfd_byte_offset as u64,
+            header.index.dfd_byte_length as u64,
+        ))
+        .max(end(
+            header.index.kvd_byte_offset as u64,
+            header.index.kvd_byte_length as u64,
+        ))
+        .max(end(
+            header.index.sgd_byte_offset,
+            header.index.sgd_byte_length,
+        ));
+
+    let mut ranges = Vec::new();
+    ranges.push(0..metadata_end);
+
+    for level in levels {
+        let index = level_index
+            .get(level as usize)
+            .ok_or(Error::LevelOutOfRange(level))?;
+
+        ranges.push(index.byte_offset..end(index.byte_offset, index.byte_length));
+    }
+
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    Ok(merged)
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, Error> {
+    let bytes = bytes
+        .get(..Header::LENGTH)
+        .ok_or(ParseError::UnexpectedEnd)?;
+
+    Ok(Header::from_bytes(bytes.try_into().unwrap())?)
+}
@@ -0,0 +1,123 @@
+use crate::{decompress_level, Error, WriterHeader};
+use std::ops::Range;
+
+/// Random access to individual subresources of a KTX2 file without decompressing all of it.
+///
+/// `Data` can be anything that derefs to bytes, such as a `Vec<u8>` or a memory-mapped file.
+/// Levels without supercompression are returned as slices of `Data` with no copying. Otherwise
+/// only the levels that are asked for are decompressed, and they are cached until
+/// [`clear_cache`](Self::clear_cache) is called.
+pub struct SubresourceReader<Data: AsRef<[u8]>> {
+    reader: ktx2::Reader<Data>,
+    header: WriterHeader,
+    /// Decompressed levels, largest first.
+    cache: Vec<Option<Vec<u8>>>,
+}
+
+impl<Data: AsRef<[u8]>> SubresourceReader<Data> {
+    pub fn new(data: Data) -> Result<Self, Error> {
+        let reader = ktx2::Reader::new(data)?;
+        let header = reader.header();
+
+        Ok(Self {
+            cache: vec![None; header.level_count.max(1) as usize],
+            header: header.into(),
+            reader,
+        })
+    }
+
+    pub fn reader(&self) -> &ktx2::Reader<Data> {
+        &self.reader
+    }
+
+    pub fn header(&self) -> WriterHeader {
+        self.header
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.cache.len() as u32
+    }
+
+    /// The byte range of a subresource within its uncompressed level, as returned by
+    /// [`level`](Self::level). BasisLZ levels can't be split into subresources.
+    pub fn subresource_range(
+        &self,
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Result<Range<usize>, Error> {
+        let index = level_index(&self.reader, level)?;
+
+        let level_length = match self.header.supercompression_scheme {
+            None => index.data.len(),
+            Some(ktx2::SupercompressionScheme::BasisLZ) => {
+                return Err(Error::UnsupportedSupercompression(
+                    ktx2::SupercompressionScheme::BasisLZ,
+                ))
+            }
+            Some(_) => index.uncompressed_byte_length as usize,
+        };
+
+        self.header
+            .subresource_range(level, level_length, layer, face, z_slice)
+            .ok_or(Error::SubresourceOutOfRange {
+                level,
+                layer,
+                face,
+                z_slice,
+            })
+    }
+
+    /// The data for a single z-slice of a face of an array layer, decompressing only its level.
+    pub fn subresource(
+        &mut self,
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Result<&[u8], Error> {
+        let range = self.subresource_range(level, layer, face, z_slice)?;
+        Ok(&self.level(level)?[range])
+    }
+
+    /// The uncompressed data for a level, covering all of its layers, faces and z-slices.
+    pub fn level(&mut self, level: u32) -> Result<&[u8], Error> {
+        let scheme = self.header.supercompression_scheme;
+        let index = level_index(&self.reader, level)?;
+
+        match scheme {
+            None => Ok(index.data),
+            Some(ktx2::SupercompressionScheme::BasisLZ) => Err(Error::UnsupportedSupercompression(
+                ktx2::SupercompressionScheme::BasisLZ,
+            )),
+            Some(_) => {
+                let cached = &mut self.cache[level as usize];
+
+                if cached.is_none() {
+                    *cached = Some(
+                        decompress_level(scheme, index.data, index.uncompressed_byte_length)?
+                            .into_owned(),
+                    );
+                }
+
+                Ok(cached.as_deref().unwrap())
+            }
+        }
+    }
+
+    /// Free any decompressed levels.
+    pub fn clear_cache(&mut self) {
+        self.cache.iter_mut().for_each(|level| *level = None);
+    }
+}
+
+fn level_index<Data: AsRef<[u8]>>(
+    reader: &ktx2::Reader<Data>,
+    level: u32,
+) -> Result<ktx2::Level<'_>, Error> {
+    reader
+        .levels()
+        .nth(level as usize)
+        .ok_or(Error::LevelOutOfRange(level))
+}
@@ -0,0 +1,227 @@
+use crate::writer::Prelude;
+use crate::{align, compress_level, Error, WriterHeader, WriterOptions};
+use std::collections::BTreeMap;
+use std::io::{Seek, SeekFrom, Write};
+
+/// A writer that supercompresses and writes levels one at a time, then back-patches the level
+/// index in [`finish`](Self::finish), so that only one level needs to be in memory at once.
+///
+/// Levels can be written in any order. Without supercompression, the size of every level is
+/// known up front and each level is written straight to its place in the file. Otherwise levels
+/// are stored smallest first, so a compressed level is held in memory until every smaller level
+/// has been written; writing levels smallest first avoids this.
+///
+/// Writing supercompressed levels largest first holds every level but the last in memory. The
+/// held levels are limited to [`max_pending_bytes`](Self::max_pending_bytes), 256 MiB by default,
+/// and a level that would go over the limit is rejected with [`Error::PendingLimitExceeded`].
+pub struct StreamingWriter<'a, W: Write + Seek> {
+    writer: W,
+    prelude: Prelude<'a>,
+    options: WriterOptions,
+    /// The position of the start of the file in `writer`.
+    start: u64,
+    levels: Vec<Option<ktx2::LevelIndex>>,
+    /// The offset of each level, when the layout is known up front.
+    fixed_offsets: Option<Vec<u64>>,
+    /// Compressed levels waiting for the smaller levels to be written.
+    pending: BTreeMap<usize, (Vec<u8>, u64)>,
+    max_pending_bytes: u64,
+    /// The end of the data written so far, relative to `start`.
+    end: u64,
+}
+
+impl<'a, W: Write + Seek> StreamingWriter<'a, W> {
+    pub const DEFAULT_MAX_PENDING_BYTES: u64 = 256 << 20;
+
+    /// Write everything up to the level data, reserving space for the level index. An empty
+    /// `dfd_bytes` generates a Basic DFD from `header.format`, as with [`Writer`](crate::Writer).
+    pub fn new(
+        mut writer: W,
+        header: WriterHeader,
+        level_count: usize,
+        dfd_bytes: &'a [u8],
+        key_value_pairs: &BTreeMap<String, Vec<u8>>,
+        sgd_bytes: &'a [u8],
+        options: WriterOptions,
+    ) -> Result<Self, Error> {
+        let prelude = Prelude::new(
+            header,
+            level_count,
+            dfd_bytes,
+            key_value_pairs,
+            sgd_bytes,
+            &options,
+        )?;
+
+        let start = writer.stream_position()?;
+
+        prelude.write_index(
+            &mut writer,
+            &vec![
+                ktx2::LevelIndex {
+                    byte_offset: 0,
+                    byte_length: 0,
+                    uncompressed_byte_length: 0,
+                };
+                level_count
+            ],
+        )?;
+        prelude.write_metadata(&mut writer)?;
+
+        let level_sizes = (0..level_count as u32)
+            .map(|level| header.level_size(level))
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| header.supercompression_scheme.is_none());
+
+        let level_alignment = prelude.level_alignment() as u64;
+        let mut end = prelude.len() as u64;
+
+        let fixed_offsets = level_sizes.map(|sizes| {
+            let mut offsets = vec![0; level_count];
+
+            for (level, size) in sizes.iter().enumerate().rev() {
+                end = align(end as usize, level_alignment as usize) as u64;
+                offsets[level] = end;
+                end += size;
+            }
+
+            offsets
+        });
+
+        Ok(Self {
+            writer,
+            prelude,
+            options,
+            start,
+            levels: vec![None; level_count],
+            fixed_offsets,
+            pending: BTreeMap::new(),
+            max_pending_bytes: Self::DEFAULT_MAX_PENDING_BYTES,
+            end,
+        })
+    }
+
+    /// Set the limit on the compressed size of the levels held in memory until every smaller
+    /// level has been written.
+    pub fn max_pending_bytes(mut self, max_pending_bytes: u64) -> Self {
+        self.max_pending_bytes = max_pending_bytes;
+        self
+    }
+
+    /// Supercompress and write a level, covering all of its layers, faces and depth slices.
+    pub fn write_level(&mut self, level: usize, data: &[u8]) -> Result<(), Error> {
+        if level >= self.levels.len()
+            || self.levels[level].is_some()
+            || self.pending.contains_key(&level)
+        {
+            return Err(Error::UnexpectedLevel(level));
+        }
+
+        let header = self.prelude.header;
+        header.check_level_size(level, data)?;
+
+        let compressed = compress_level(header.supercompression_scheme, data, &self.options)?;
+        let uncompressed_byte_length = header.uncompressed_byte_length(data);
+
+        if let Some(offsets) = &self.fixed_offsets {
+            let offset = offsets[level];
+            self.write_at(level, offset, &compressed, uncompressed_byte_length)?;
+            return Ok(());
+        }
+
+        // The level is written straight away if it's the smallest one left, and held otherwise.
+        if self.levels.iter().rposition(Option::is_none) != Some(level) {
+            let pending = self
+                .pending
+                .values()
+                .map(|(data, _)| data.len() as u64)
+                .sum::<u64>()
+                + compressed.len() as u64;
+
+            if pending > self.max_pending_bytes {
+                return Err(Error::PendingLimitExceeded {
+                    level,
+                    pending,
+                    limit: self.max_pending_bytes,
+                });
+            }
+        }
+
+        self.pending
+            .insert(level, (compressed.into_owned(), uncompressed_byte_length));
+
+        // Levels are stored smallest first, so write out everything that is now unblocked.
+        while let Some(next) = self.levels.iter().rposition(Option::is_none) {
+            let Some((compressed, uncompressed_byte_length)) = self.pending.remove(&next) else {
+                break;
+            };
+
+            let offset = align(self.end as usize, self.prelude.level_alignment()) as u64;
+            self.write_at(next, offset, &compressed, uncompressed_byte_length)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_at(
+        &mut self,
+        level: usize,
+        offset: u64,
+        data: &[u8],
+        uncompressed_byte_length: u64,
+    ) -> Result<(), Error> {
+        // Fill alignment padding explicitly rather than relying on the writer to zero the gap.
+        let padding_start = if self.fixed_offsets.is_some() {
+            offset
+        } else {
+            self.end
+        };
+
+        self.writer
+            .seek(SeekFrom::Start(self.start + padding_start))?;
+        self.writer
+            .write_all(&vec![0; (offset - padding_start) as usize])?;
+        self.writer.write_all(data)?;
+
+        self.levels[level] = Some(ktx2::LevelIndex {
+            byte_offset: offset,
+            byte_length: data.len() as u64,
+            uncompressed_byte_length,
+        });
+
+        self.end = self.end.max(offset + data.len() as u64);
+
+        Ok(())
+    }
+
+    /// Back-patch the level index once every level has been written, and return the underlying
+    /// writer, positioned at the end of the file.
+    pub fn finish(mut self) -> Result<W, Error> {
+        let levels = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level, index)| index.ok_or(Error::MissingLevel(level)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // With a fixed layout, alignment padding between levels may be unwritten if the levels
+        // arrived out of order, so zero it.
+        if self.fixed_offsets.is_some() {
+            let mut position = self.prelude.len() as u64;
+
+            for index in levels.iter().rev() {
+                self.writer.seek(SeekFrom::Start(self.start + position))?;
+                self.writer
+                    .write_all(&vec![0; (index.byte_offset - position) as usize])?;
+                position = index.byte_offset + index.byte_length;
+            }
+        }
+
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        self.prelude.write_index(&mut self.writer, &levels)?;
+        self.writer.seek(SeekFrom::Start(self.start + self.end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
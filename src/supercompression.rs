@@ -0,0 +1,169 @@
+use crate::Error;
+#[cfg(feature = "writer")]
+use crate::WriterOptions;
+use ktx2::SupercompressionScheme;
+use std::borrow::Cow;
+
+/// Supercompress a single level. Levels are borrowed unchanged when there is no supercompression,
+/// or for BasisLZ, where the ETC1S encoder has already produced the supercompressed data.
+/// Zstandard needs the `zstd` or `zstd-pure` feature and ZLIB needs the `zlib` feature.
+#[cfg(feature = "writer")]
+#[cfg_attr(
+    not(any(feature = "zstd", feature = "zstd-pure", feature = "zlib")),
+    allow(unused_variables)
+)]
+pub fn compress_level<'a>(
+    scheme: Option<SupercompressionScheme>,
+    level: &'a [u8],
+    options: &WriterOptions,
+) -> Result<Cow<'a, [u8]>, Error> {
+    match scheme {
+        #[cfg(any(feature = "zstd", feature = "zstd-pure"))]
+        Some(SupercompressionScheme::Zstandard) => Ok(Cow::Owned(
+            compress_zstd(level, options).map_err(Error::Compression)?,
+        )),
+        #[cfg(feature = "zlib")]
+        Some(SupercompressionScheme::ZLIB) => Ok(Cow::Owned(
+            compress_zlib(level, options).map_err(Error::Compression)?,
+        )),
+        Some(SupercompressionScheme::BasisLZ) | None => Ok(Cow::Borrowed(level)),
+        Some(other) => Err(Error::UnsupportedSupercompression(other)),
+    }
+}
+
+/// Decompress a single level from a reader, checking that it inflates to
+/// `uncompressed_byte_length`. BasisLZ levels can't be decompressed without transcoding, and
+/// Zstandard needs the `zstd` or `zstd-pure` feature and ZLIB needs the `zlib` feature.
+#[cfg_attr(
+    not(any(feature = "zstd", feature = "zstd-pure", feature = "zlib")),
+    allow(unused_variables)
+)]
+pub fn decompress_level(
+    scheme: Option<SupercompressionScheme>,
+    level: &[u8],
+    uncompressed_byte_length: u64,
+) -> Result<Cow<'_, [u8]>, Error> {
+    match scheme {
+        #[cfg(any(feature = "zstd", feature = "zstd-pure"))]
+        Some(SupercompressionScheme::Zstandard) => check_decompressed_length(
+            decompress_zstd(level, uncompressed_byte_length).map_err(Error::Compression)?,
+            uncompressed_byte_length,
+        ),
+        #[cfg(feature = "zlib")]
+        Some(SupercompressionScheme::ZLIB) => check_decompressed_length(
+            read_limited(
+                flate2::read::ZlibDecoder::new(level),
+                level.len(),
+                uncompressed_byte_length,
+            )
+            .map_err(Error::Compression)?,
+            uncompressed_byte_length,
+        ),
+        Some(other) => Err(Error::UnsupportedSupercompression(other)),
+        None => Ok(Cow::Borrowed(level)),
+    }
+}
+
+/// The most bytes reserved per compressed byte before decompressing. `uncompressedByteLength`
+/// comes from the file, so it can't be trusted for the reservation, but the output still grows as
+/// needed.
+#[cfg(any(feature = "zstd", feature = "zstd-pure", feature = "zlib"))]
+const MAX_RESERVED_RATIO: usize = 8;
+
+/// Read at most one byte more than `uncompressed_byte_length` from a decoder, which is enough for
+/// [`check_decompressed_length`] to catch a level that inflates too far without decompressing all
+/// of it.
+#[cfg(any(feature = "zstd", feature = "zstd-pure", feature = "zlib"))]
+fn read_limited(
+    decoder: impl std::io::Read,
+    compressed_length: usize,
+    uncompressed_byte_length: u64,
+) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let reserved =
+        uncompressed_byte_length.min(compressed_length.saturating_mul(MAX_RESERVED_RATIO) as u64);
+    let mut decompressed = Vec::with_capacity(reserved as usize);
+
+    decoder
+        .take(uncompressed_byte_length.saturating_add(1))
+        .read_to_end(&mut decompressed)?;
+
+    Ok(decompressed)
+}
+
+#[cfg(any(feature = "zstd", feature = "zstd-pure", feature = "zlib"))]
+fn check_decompressed_length(
+    decompressed: Vec<u8>,
+    uncompressed_byte_length: u64,
+) -> Result<Cow<'static, [u8]>, Error> {
+    if decompressed.len() as u64 != uncompressed_byte_length {
+        return Err(Error::Compression(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "level decompressed to {} bytes instead of {}",
+                decompressed.len(),
+                uncompressed_byte_length
+            ),
+        )));
+    }
+
+    Ok(Cow::Owned(decompressed))
+}
+
+#[cfg(all(feature = "writer", feature = "zstd"))]
+fn compress_zstd(level: &[u8], options: &WriterOptions) -> std::io::Result<Vec<u8>> {
+    let mut compressor = zstd::bulk::Compressor::new(options.zstd_level)?;
+
+    if options.zstd_long_distance_matching {
+        compressor.set_parameter(zstd::stream::raw::CParameter::EnableLongDistanceMatching(
+            true,
+        ))?;
+    }
+
+    if let Some(window_log) = options.zstd_window_log {
+        compressor.set_parameter(zstd::stream::raw::CParameter::WindowLog(window_log))?;
+    }
+
+    compressor.compress(level)
+}
+
+/// Compress with ruzstd, which only implements its fastest level, so the zstd options are ignored.
+#[cfg(all(feature = "writer", feature = "zstd-pure", not(feature = "zstd")))]
+fn compress_zstd(level: &[u8], _options: &WriterOptions) -> std::io::Result<Vec<u8>> {
+    Ok(ruzstd::encoding::compress_to_vec(
+        level,
+        ruzstd::encoding::CompressionLevel::Fastest,
+    ))
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(level: &[u8], uncompressed_byte_length: u64) -> std::io::Result<Vec<u8>> {
+    read_limited(
+        zstd::stream::read::Decoder::with_buffer(level)?,
+        level.len(),
+        uncompressed_byte_length,
+    )
+}
+
+#[cfg(all(feature = "zstd-pure", not(feature = "zstd")))]
+fn decompress_zstd(level: &[u8], uncompressed_byte_length: u64) -> std::io::Result<Vec<u8>> {
+    let decoder = ruzstd::decoding::StreamingDecoder::new(level)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()))?;
+
+    read_limited(decoder, level.len(), uncompressed_byte_length)
+}
+
+#[cfg(all(feature = "writer", feature = "zlib"))]
+fn compress_zlib(level: &[u8], options: &WriterOptions) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let compression = options
+        .zlib_level
+        .map(flate2::Compression::new)
+        .unwrap_or_default();
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), compression);
+    encoder.write_all(level)?;
+    encoder.finish()
+}
@@ -0,0 +1,161 @@
+use crate::{decompress_level, BasicDfd, Error, WriterHeader};
+#[cfg(feature = "writer")]
+use crate::{Writer, WriterOptions};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+// The offset of `bytesPlane0..7` in the DFD, past `dfdTotalSize`, the descriptor block header
+// and the first 8 bytes of the Basic DFD header.
+const DFD_BYTES_PLANES: std::ops::Range<usize> = 20..28;
+
+/// An in-memory KTX2 texture with decompressed levels, for reading, editing and re-writing files
+/// without dealing with byte offsets.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub header: WriterHeader,
+    /// The raw data format descriptor, including `dfdTotalSize`.
+    pub dfd_bytes: Vec<u8>,
+    pub key_value_pairs: BTreeMap<String, Vec<u8>>,
+    pub sgd_bytes: Vec<u8>,
+    /// The levels, largest first. These are decompressed, except for BasisLZ where the level data
+    /// can only be decoded together with the global data.
+    pub levels: Vec<Cow<'static, [u8]>>,
+}
+
+impl Texture {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let ktx2 = ktx2::Reader::new(bytes)?;
+        let header = ktx2.header();
+
+        // The reader has already checked that these ranges are in bounds.
+        let dfd_start = header.index.dfd_byte_offset as usize;
+        let dfd_bytes =
+            bytes[dfd_start..dfd_start + header.index.dfd_byte_length as usize].to_vec();
+
+        let key_value_pairs = ktx2
+            .key_value_data()
+            .map(|(key, value)| (key.to_string(), value.to_vec()))
+            .collect();
+
+        let levels = ktx2
+            .levels()
+            .map(|level| match header.supercompression_scheme {
+                Some(ktx2::SupercompressionScheme::BasisLZ) => Ok(Cow::Owned(level.data.to_vec())),
+                scheme => decompress_level(scheme, level.data, level.uncompressed_byte_length)
+                    .map(|level| Cow::Owned(level.into_owned())),
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self {
+            header: header.into(),
+            dfd_bytes,
+            key_value_pairs,
+            sgd_bytes: ktx2.supercompression_global_data().to_vec(),
+            levels,
+        })
+    }
+
+    /// A writer for the texture, supercompressing the levels with
+    /// `header.supercompression_scheme`.
+    #[cfg(feature = "writer")]
+    pub fn to_writer(&self, options: WriterOptions) -> Writer<'_> {
+        Writer {
+            header: self.header,
+            dfd_bytes: &self.dfd_bytes,
+            key_value_pairs: &self.key_value_pairs,
+            sgd_bytes: &self.sgd_bytes,
+            uncompressed_levels_descending: &self.levels,
+            options,
+        }
+    }
+
+    /// Change the scheme that the levels will be supercompressed with when written, updating
+    /// `bytesPlane0` in the DFD to match. BasisLZ levels can't be converted.
+    pub fn set_supercompression_scheme(
+        &mut self,
+        scheme: Option<ktx2::SupercompressionScheme>,
+    ) -> Result<(), Error> {
+        let basis_lz = Some(ktx2::SupercompressionScheme::BasisLZ);
+
+        if scheme != self.header.supercompression_scheme
+            && (scheme == basis_lz || self.header.supercompression_scheme == basis_lz)
+        {
+            return Err(Error::UnsupportedSupercompression(
+                ktx2::SupercompressionScheme::BasisLZ,
+            ));
+        }
+
+        let bytes_planes = match scheme {
+            Some(_) => [0; 8],
+            None => {
+                self.header
+                    .format
+                    .and_then(BasicDfd::from_format)
+                    .ok_or(Error::UnsupportedFormat(self.header.format))?
+                    .header
+                    .bytes_planes
+            }
+        };
+
+        if let Some(dfd_bytes_planes) = self.dfd_bytes.get_mut(DFD_BYTES_PLANES) {
+            dfd_bytes_planes.copy_from_slice(&bytes_planes);
+        }
+
+        self.header.supercompression_scheme = scheme;
+
+        Ok(())
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// The number of array layers, treating non-array textures as having one.
+    pub fn layer_count(&self) -> u32 {
+        self.header.layer_count.max(1)
+    }
+
+    pub fn face_count(&self) -> u32 {
+        self.header.face_count
+    }
+
+    /// The width, height and depth of a level, in pixels. Depth is 1 for 2D textures.
+    pub fn level_dimensions(&self, level: u32) -> [u32; 3] {
+        self.header.level_dimensions(level)
+    }
+
+    /// The data for a single z-slice of a face of an array layer. Returns `None` if the
+    /// subresource is out of range, or for BasisLZ textures, whose levels aren't decoded.
+    pub fn subresource(&self, level: u32, layer: u32, face: u32, z_slice: u32) -> Option<&[u8]> {
+        let range = self.subresource_range(level, layer, face, z_slice)?;
+        Some(&self.levels[level as usize][range])
+    }
+
+    pub fn subresource_mut(
+        &mut self,
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Option<&mut [u8]> {
+        let range = self.subresource_range(level, layer, face, z_slice)?;
+        Some(&mut self.levels[level as usize].to_mut()[range])
+    }
+
+    fn subresource_range(
+        &self,
+        level: u32,
+        layer: u32,
+        face: u32,
+        z_slice: u32,
+    ) -> Option<std::ops::Range<usize>> {
+        if let Some(ktx2::SupercompressionScheme::BasisLZ) = self.header.supercompression_scheme {
+            return None;
+        }
+
+        let level_data = self.levels.get(level as usize)?;
+
+        self.header
+            .subresource_range(level, level_data.len(), layer, face, z_slice)
+    }
+}
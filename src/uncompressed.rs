@@ -0,0 +1,187 @@
+use crate::mipmap::srgb_to_linear;
+use crate::{generate_mip_chain, EncodedTexture, Error, TextureKind, WriterBuilder};
+use std::borrow::Cow;
+
+/// An uncompressed format that [`encode_uncompressed`] can produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UncompressedFormat {
+    /// 8 bits per channel.
+    Rgba8,
+    /// Half floats.
+    Rgba16Float,
+    /// Unsigned floats with a shared exponent. Alpha is dropped.
+    Rgb9e5,
+}
+
+impl UncompressedFormat {
+    /// The `vkFormat` for this format. Only RGBA8 has an sRGB variant, so `None` is returned for
+    /// the float formats when `srgb` is set.
+    pub fn ktx2_format(self, srgb: bool) -> Option<ktx2::Format> {
+        use ktx2::Format;
+
+        Some(match (self, srgb) {
+            (Self::Rgba8, false) => Format::R8G8B8A8_UNORM,
+            (Self::Rgba8, true) => Format::R8G8B8A8_SRGB,
+            (Self::Rgba16Float, false) => Format::R16G16B16A16_SFLOAT,
+            (Self::Rgb9e5, false) => Format::E5B9G9R9_UFLOAT_PACK32,
+            (Self::Rgba16Float | Self::Rgb9e5, true) => return None,
+        })
+    }
+}
+
+impl std::str::FromStr for UncompressedFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rgba8" => Ok(Self::Rgba8),
+            "rgba16f" => Ok(Self::Rgba16Float),
+            "rgb9e5" => Ok(Self::Rgb9e5),
+            _ => Err(format!(
+                "unknown format {:?}, expected rgba8, rgba16f or rgb9e5",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UncompressedOptions {
+    pub format: UncompressedFormat,
+    /// Whether the image is sRGB-encoded color data. The float formats are always linear, so sRGB
+    /// images are converted to linear light for them.
+    pub srgb: bool,
+    pub generate_mipmaps: bool,
+    pub supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+}
+
+impl Default for UncompressedOptions {
+    fn default() -> Self {
+        Self {
+            format: UncompressedFormat::Rgba8,
+            srgb: false,
+            generate_mipmaps: true,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+        }
+    }
+}
+
+/// Encode an image to RGBA8, RGBA16F or RGB9E5. Mipmaps are resized from the full image, in linear
+/// light if it is sRGB, down to 1x1. Float formats take linear images' values as they are, so
+/// 8-bit images give values from 0 to 1, and negative values are clamped to 0 for RGB9E5.
+pub fn encode_uncompressed(
+    image: &image::DynamicImage,
+    options: &UncompressedOptions,
+) -> Result<EncodedTexture, Error> {
+    let format = match options.format.ktx2_format(options.srgb) {
+        Some(format) => format,
+        // The float formats are linear, so the image is linearized below instead.
+        None => options.format.ktx2_format(false).unwrap(),
+    };
+
+    let header = WriterBuilder::new(
+        format,
+        image.width(),
+        image.height(),
+        TextureKind::Texture2D,
+    )?
+    .supercompression_scheme(options.supercompression_scheme)
+    .header();
+
+    let level_count = if options.generate_mipmaps {
+        header.max_level_count()
+    } else {
+        1
+    };
+
+    let levels = if options.format == UncompressedFormat::Rgba8 {
+        generate_mip_chain(&image.to_rgba8(), level_count, options.srgb)
+            .into_iter()
+            .map(image::RgbaImage::into_raw)
+            .collect::<Vec<_>>()
+    } else {
+        // Resizing in f32 keeps float images from being clamped to 0-1.
+        let mut image = image.to_rgba32f();
+
+        if options.srgb {
+            for pixel in image.pixels_mut() {
+                for value in &mut pixel.0[..3] {
+                    *value = srgb_to_linear(*value);
+                }
+            }
+        }
+
+        (0..level_count)
+            .map(|level| {
+                let [width, height, _] = header.level_dimensions(level);
+
+                let resized = if level == 0 {
+                    Cow::Borrowed(&image)
+                } else {
+                    Cow::Owned(image::imageops::resize(
+                        &image,
+                        width,
+                        height,
+                        image::imageops::FilterType::Triangle,
+                    ))
+                };
+
+                match options.format {
+                    UncompressedFormat::Rgba16Float => resized
+                        .iter()
+                        .flat_map(|value| half::f16::from_f32(*value).to_le_bytes())
+                        .collect(),
+                    _ => resized
+                        .pixels()
+                        .flat_map(|pixel| rgb9e5([pixel[0], pixel[1], pixel[2]]).to_le_bytes())
+                        .collect(),
+                }
+            })
+            .collect()
+    };
+
+    for (i, level) in levels.iter().enumerate() {
+        header.check_level_size(i, level)?;
+    }
+
+    Ok(EncodedTexture {
+        header,
+        dfd_bytes: Vec::new(),
+        sgd_bytes: Vec::new(),
+        levels: levels.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
+/// Pack a color into RGB9E5, rounding to the nearest representable value. This follows the
+/// conversion in the Vulkan specification.
+fn rgb9e5(color: [f32; 3]) -> u32 {
+    const MANTISSA_BITS: i32 = 9;
+    const BIAS: i32 = 15;
+    const MAX_EXPONENT: i32 = 31;
+
+    let max_value = ((1 << MANTISSA_BITS) - 1) as f32 / (1 << MANTISSA_BITS) as f32
+        * 2f32.powi(MAX_EXPONENT - BIAS);
+
+    // `max` and `min` also turn NaN into 0.
+    let color = color.map(|value| value.max(0.0).min(max_value));
+    let max_channel = color[0].max(color[1]).max(color[2]);
+
+    let mut exponent = if max_channel > 0.0 {
+        max_channel.log2().floor() as i32
+    } else {
+        -BIAS - 1
+    }
+    .max(-BIAS - 1)
+        + 1
+        + BIAS;
+
+    let scale = |exponent: i32| 2f32.powi(exponent - BIAS - MANTISSA_BITS);
+
+    if (max_channel / scale(exponent) + 0.5).floor() as i32 == 1 << MANTISSA_BITS {
+        exponent += 1;
+    }
+
+    let [red, green, blue] = color.map(|value| (value / scale(exponent) + 0.5).floor() as u32);
+
+    red | (green << 9) | (blue << 18) | ((exponent as u32) << 27)
+}
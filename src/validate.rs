@@ -0,0 +1,700 @@
+use crate::metadata::{self, Metadata};
+use crate::{BasicDfd, WriterHeader};
+use ktx2::{ColorModel, DfdHeader, Format, Header, LevelIndex, SupercompressionScheme};
+use std::fmt;
+
+pub(crate) const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// The BasisLZ global data header, followed by one image descriptor per image.
+const BASIS_LZ_GLOBAL_HEADER_LENGTH: u64 = 20;
+const BASIS_LZ_IMAGE_DESC_LENGTH: u64 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The file is valid, but is likely to cause problems or doesn't follow a recommendation.
+    Warning,
+    /// The file violates the specification.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Check a KTX2 file against the specification, returning every problem found. Validation
+/// continues past most errors, but stops early if the header or level index can't be read.
+pub fn validate(bytes: &[u8]) -> Vec<Issue> {
+    let mut validator = Validator {
+        bytes,
+        issues: Vec::new(),
+    };
+
+    if let Some(header) = validator.header() {
+        let dfd = validator.dfd(&header);
+        validator.levels(&header, dfd.as_ref());
+        validator.key_value_data(&header);
+        validator.supercompression_global_data(&header);
+    }
+
+    validator.issues
+}
+
+struct Validator<'a> {
+    bytes: &'a [u8],
+    issues: Vec<Issue>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    fn warning(&mut self, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+
+    /// The bytes in `offset..offset + length`, or `None` if the range is out of bounds.
+    fn range(&self, offset: u64, length: u64) -> Option<&'a [u8]> {
+        let end = offset.checked_add(length)?;
+        self.bytes.get(offset as usize..end as usize)
+    }
+
+    fn header(&mut self) -> Option<Header> {
+        if self.bytes.len() < Header::LENGTH {
+            self.error(format!(
+                "the file is {} bytes, which is too short for a KTX2 header",
+                self.bytes.len()
+            ));
+            return None;
+        }
+
+        if self.bytes[..12] != KTX2_IDENTIFIER {
+            self.error("the file doesn't start with the KTX2 identifier".to_string());
+            return None;
+        }
+
+        let header = parse_header(self.bytes[..Header::LENGTH].try_into().unwrap());
+        let writer_header = WriterHeader::from(header);
+        let format_dfd = header.format.and_then(BasicDfd::from_format);
+
+        if let (Some(format), None) = (header.format, &format_dfd) {
+            self.warning(format!(
+                "vkFormat {:?} isn't known, so levels and the DFD can't be fully checked",
+                format
+            ));
+        }
+
+        let is_block_compressed = format_dfd
+            .as_ref()
+            .is_some_and(|dfd| dfd.block_dimensions() != [1; 4]);
+
+        if let Some(expected) = format_dfd.as_ref().map(BasicDfd::type_size) {
+            if header.type_size != expected {
+                self.error(format!(
+                    "typeSize is {} but vkFormat {:?} requires {}",
+                    header.type_size,
+                    header.format.unwrap(),
+                    expected
+                ));
+            }
+        } else if header.format.is_none() && header.type_size != 1 {
+            self.error(format!(
+                "typeSize is {} but must be 1 when vkFormat is undefined",
+                header.type_size
+            ));
+        }
+
+        if header.pixel_width == 0 {
+            self.error("pixelWidth is 0".to_string());
+        }
+
+        if header.pixel_depth != 0 && header.pixel_height == 0 {
+            self.error("pixelDepth is non-zero but pixelHeight is 0".to_string());
+        }
+
+        if is_block_compressed && header.pixel_height == 0 {
+            self.error("block-compressed formats can't be used for 1D textures".to_string());
+        }
+
+        match header.face_count {
+            1 => {}
+            6 => {
+                if header.pixel_width != header.pixel_height || header.pixel_depth != 0 {
+                    self.error(format!(
+                        "cubemap faces must be square and 2D, but are {}x{}x{}",
+                        header.pixel_width, header.pixel_height, header.pixel_depth
+                    ));
+                }
+            }
+            face_count => self.error(format!("faceCount is {} but must be 1 or 6", face_count)),
+        }
+
+        let max_level_count = writer_header.max_level_count();
+
+        if header.level_count > max_level_count {
+            self.error(format!(
+                "levelCount is {} but the dimensions allow at most {}",
+                header.level_count, max_level_count
+            ));
+        }
+
+        match header.supercompression_scheme {
+            None
+            | Some(SupercompressionScheme::BasisLZ)
+            | Some(SupercompressionScheme::Zstandard)
+            | Some(SupercompressionScheme::ZLIB) => {}
+            Some(scheme) if scheme.value() >= 0x10000 => self.warning(format!(
+                "supercompressionScheme {:?} is vendor-specific, so levels can't be fully checked",
+                scheme
+            )),
+            Some(scheme) => self.error(format!("supercompressionScheme {:?} is reserved", scheme)),
+        }
+
+        if header.level_count == 0
+            && (is_block_compressed
+                || header.supercompression_scheme == Some(SupercompressionScheme::BasisLZ))
+        {
+            self.error(
+                "levelCount is 0, but mipmaps can't be generated for block-compressed or BasisLZ \
+                 data"
+                    .to_string(),
+            );
+        }
+
+        if header.supercompression_scheme == Some(SupercompressionScheme::BasisLZ) {
+            if header.format.is_some() {
+                self.error("vkFormat must be undefined for BasisLZ".to_string());
+            }
+
+            if header.pixel_depth != 0 {
+                self.error("BasisLZ can't be used for 3D textures".to_string());
+            }
+        }
+
+        // The level index always has at least one entry.
+        let level_index_end =
+            Header::LENGTH as u64 + header.level_count.max(1) as u64 * LevelIndex::LENGTH as u64;
+
+        if level_index_end > self.bytes.len() as u64 {
+            self.error("the level index extends past the end of the file".to_string());
+            return None;
+        }
+
+        let index = &header.index;
+
+        if index.dfd_byte_offset as u64 != level_index_end {
+            self.error(format!(
+                "dfdByteOffset is {} but the DFD must immediately follow the level index at {}",
+                index.dfd_byte_offset, level_index_end
+            ));
+        }
+
+        let dfd_end = index.dfd_byte_offset as u64 + index.dfd_byte_length as u64;
+
+        if index.kvd_byte_length == 0 {
+            if index.kvd_byte_offset != 0 {
+                self.error("kvdByteOffset must be 0 when kvdByteLength is 0".to_string());
+            }
+        } else if index.kvd_byte_offset as u64 != dfd_end {
+            self.error(format!(
+                "kvdByteOffset is {} but the key/value data must immediately follow the DFD at {}",
+                index.kvd_byte_offset, dfd_end
+            ));
+        }
+
+        if index.sgd_byte_length == 0 {
+            if index.sgd_byte_offset != 0 {
+                self.error("sgdByteOffset must be 0 when sgdByteLength is 0".to_string());
+            }
+        } else if !index.sgd_byte_offset.is_multiple_of(8) {
+            self.error(format!(
+                "sgdByteOffset {} isn't 8-byte aligned",
+                index.sgd_byte_offset
+            ));
+        }
+
+        for (name, offset, length) in [
+            (
+                "DFD",
+                index.dfd_byte_offset as u64,
+                index.dfd_byte_length as u64,
+            ),
+            (
+                "key/value data",
+                index.kvd_byte_offset as u64,
+                index.kvd_byte_length as u64,
+            ),
+            (
+                "supercompression global data",
+                index.sgd_byte_offset,
+                index.sgd_byte_length,
+            ),
+        ] {
+            if self.range(offset, length).is_none() {
+                self.error(format!("the {} extends past the end of the file", name));
+            }
+        }
+
+        Some(header)
+    }
+
+    fn dfd(&mut self, header: &Header) -> Option<BasicDfd> {
+        let index = &header.index;
+        let bytes = self.range(index.dfd_byte_offset as u64, index.dfd_byte_length as u64)?;
+
+        if bytes.len() < 4 {
+            self.error(format!(
+                "dfdByteLength is {}, which is too short for a DFD",
+                bytes.len()
+            ));
+            return None;
+        }
+
+        let total_size = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+
+        if total_size as usize != bytes.len() {
+            self.error(format!(
+                "dfdTotalSize is {} but dfdByteLength is {}",
+                total_size,
+                bytes.len()
+            ));
+        }
+
+        let mut blocks = &bytes[4..];
+        let mut basic = None;
+
+        while !blocks.is_empty() {
+            let Some(block_header) = blocks.get(..DfdHeader::LENGTH) else {
+                self.error("the DFD ends partway through a descriptor block header".to_string());
+                break;
+            };
+
+            let first_word = u32::from_le_bytes(block_header[..4].try_into().unwrap());
+            let vendor_id = first_word & ((1 << 17) - 1);
+            let descriptor_type = first_word >> 17;
+            let version_number = u16::from_le_bytes(block_header[4..6].try_into().unwrap());
+            let block_size = u16::from_le_bytes(block_header[6..8].try_into().unwrap()) as usize;
+
+            if block_size < DfdHeader::LENGTH || block_size > blocks.len() {
+                self.error(format!(
+                    "descriptorBlockSize {} doesn't fit the DFD",
+                    block_size
+                ));
+                break;
+            }
+
+            let is_basic = vendor_id == DfdHeader::BASIC.vendor_id
+                && descriptor_type == DfdHeader::BASIC.descriptor_type;
+
+            if basic.is_none() && !is_basic {
+                self.error("the first descriptor block isn't a Khronos Basic DFD".to_string());
+            }
+
+            if is_basic && basic.is_none() {
+                basic = self.basic_dfd(version_number, &blocks[DfdHeader::LENGTH..block_size]);
+            }
+
+            blocks = &blocks[block_size..];
+        }
+
+        let dfd = basic?;
+        self.compare_dfd(header, &dfd);
+        Some(dfd)
+    }
+
+    fn basic_dfd(&mut self, version_number: u16, bytes: &[u8]) -> Option<BasicDfd> {
+        if version_number != DfdHeader::BASIC.version_number {
+            self.error(format!(
+                "the Basic DFD has versionNumber {} but KTX2 requires {}",
+                version_number,
+                DfdHeader::BASIC.version_number
+            ));
+        }
+
+        if bytes.len() < ktx2::DfdBlockHeaderBasic::LENGTH
+            || !(bytes.len() - ktx2::DfdBlockHeaderBasic::LENGTH)
+                .is_multiple_of(ktx2::SampleInformation::LENGTH)
+        {
+            self.error(format!(
+                "the Basic DFD is {} bytes, which isn't a whole number of samples",
+                bytes.len() + DfdHeader::LENGTH
+            ));
+            return None;
+        }
+
+        match BasicDfd::from_bytes(bytes) {
+            Ok(dfd) => Some(dfd),
+            Err(error) => {
+                self.error(format!("the Basic DFD is invalid: {}", error));
+                None
+            }
+        }
+    }
+
+    /// Check the Basic DFD against the one implied by `vkFormat` and the supercompression scheme.
+    fn compare_dfd(&mut self, header: &Header, dfd: &BasicDfd) {
+        let supercompressed = header.supercompression_scheme.is_some();
+
+        if supercompressed && dfd.header.bytes_planes != [0; 8] {
+            self.error(format!(
+                "bytesPlane0 is {} but must be 0 for supercompressed data",
+                dfd.header.bytes_planes[0]
+            ));
+        }
+
+        if header.supercompression_scheme == Some(SupercompressionScheme::BasisLZ)
+            && dfd.header.color_model != Some(ColorModel::ETC1S)
+        {
+            self.error(format!(
+                "the DFD color model is {:?} but BasisLZ requires ETC1S",
+                dfd.header.color_model
+            ));
+        }
+
+        let Some(format) = header.format else {
+            if !matches!(
+                dfd.header.color_model,
+                Some(ColorModel::ETC1S) | Some(ColorModel::UASTC)
+            ) {
+                self.warning(format!(
+                    "vkFormat is undefined for a {:?} DFD, so readers must interpret the DFD",
+                    dfd.header.color_model
+                ));
+            }
+            return;
+        };
+
+        let Some(mut expected) = BasicDfd::from_format(format) else {
+            return;
+        };
+
+        if supercompressed {
+            expected.header.bytes_planes = [0; 8];
+        }
+
+        if dfd.header.color_model != expected.header.color_model {
+            self.error(format!(
+                "the DFD color model is {:?} but vkFormat {:?} requires {:?}",
+                dfd.header.color_model, format, expected.header.color_model
+            ));
+        }
+
+        if dfd.header.texel_block_dimensions != expected.header.texel_block_dimensions {
+            self.error(format!(
+                "the DFD texel block dimensions are {:?} but vkFormat {:?} requires {:?}",
+                dfd.block_dimensions(),
+                format,
+                expected.block_dimensions()
+            ));
+        }
+
+        if !supercompressed && dfd.header.bytes_planes != expected.header.bytes_planes {
+            self.error(format!(
+                "bytesPlane0 is {} but vkFormat {:?} requires {}",
+                dfd.header.bytes_planes[0], format, expected.header.bytes_planes[0]
+            ));
+        }
+
+        if dfd.header.transfer_function != expected.header.transfer_function {
+            self.error(format!(
+                "the DFD transfer function is {:?} but vkFormat {:?} requires {:?}",
+                dfd.header.transfer_function, format, expected.header.transfer_function
+            ));
+        }
+
+        let layout = |dfd: &BasicDfd| {
+            dfd.samples
+                .iter()
+                .map(|sample| (sample.channel_type, sample.bit_offset, sample.bit_length))
+                .collect::<Vec<_>>()
+        };
+
+        if layout(dfd) != layout(&expected) {
+            self.error(format!(
+                "the DFD samples don't match the channel layout of vkFormat {:?}",
+                format
+            ));
+        }
+    }
+
+    fn levels(&mut self, header: &Header, dfd: Option<&BasicDfd>) {
+        let levels = self.bytes[Header::LENGTH..]
+            .chunks_exact(LevelIndex::LENGTH)
+            .take(header.level_count.max(1) as usize)
+            .map(|bytes| LevelIndex::from_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        let writer_header = WriterHeader::from(*header);
+        let scheme = header.supercompression_scheme;
+
+        let alignment = match (scheme, dfd) {
+            (None, Some(dfd)) => crate::lcm(dfd.bytes_per_block().max(1) as usize, 4) as u64,
+            (None, None) => 4,
+            (Some(_), _) => 1,
+        };
+
+        let index = &header.index;
+        let metadata_end = [
+            index.dfd_byte_offset as u64 + index.dfd_byte_length as u64,
+            index.kvd_byte_offset as u64 + index.kvd_byte_length as u64,
+            index.sgd_byte_offset.saturating_add(index.sgd_byte_length),
+        ]
+        .into_iter()
+        .max()
+        .unwrap();
+
+        for (i, level) in levels.iter().enumerate() {
+            if level.byte_length == 0 {
+                self.error(format!("level {} has a byteLength of 0", i));
+            }
+
+            if self.range(level.byte_offset, level.byte_length).is_none() {
+                self.error(format!("level {} extends past the end of the file", i));
+            }
+
+            if level.byte_offset < metadata_end {
+                self.error(format!(
+                    "level {} at byteOffset {} overlaps the file's metadata",
+                    i, level.byte_offset
+                ));
+            }
+
+            if !level.byte_offset.is_multiple_of(alignment) {
+                self.error(format!(
+                    "level {} at byteOffset {} isn't aligned to {} bytes",
+                    i, level.byte_offset, alignment
+                ));
+            }
+
+            match scheme {
+                None if level.uncompressed_byte_length != level.byte_length => self.error(format!(
+                    "level {} has uncompressedByteLength {} but byteLength {}, which must \
+                         match without supercompression",
+                    i, level.uncompressed_byte_length, level.byte_length
+                )),
+                Some(SupercompressionScheme::BasisLZ) if level.uncompressed_byte_length != 0 => {
+                    self.error(format!(
+                        "level {} has uncompressedByteLength {} but it must be 0 for BasisLZ",
+                        i, level.uncompressed_byte_length
+                    ))
+                }
+                _ => {}
+            }
+
+            let checks_size = matches!(
+                scheme,
+                None | Some(SupercompressionScheme::Zstandard) | Some(SupercompressionScheme::ZLIB)
+            );
+
+            if let (true, Some(expected)) = (checks_size, writer_header.level_size(i as u32)) {
+                if level.uncompressed_byte_length != expected {
+                    self.error(format!(
+                        "level {} is {} bytes uncompressed but vkFormat {:?} requires {}",
+                        i,
+                        level.uncompressed_byte_length,
+                        header.format.unwrap(),
+                        expected
+                    ));
+                }
+            }
+        }
+
+        // Levels are stored smallest first, so each level must end before the previous one.
+        for (i, pair) in levels.windows(2).enumerate() {
+            if pair[1].byte_offset.saturating_add(pair[1].byte_length) > pair[0].byte_offset {
+                self.error(format!(
+                    "level {} must be stored before level {} without overlapping it",
+                    i + 1,
+                    i
+                ));
+            }
+        }
+    }
+
+    fn key_value_data(&mut self, header: &Header) {
+        let Some(mut bytes) = self.range(
+            header.index.kvd_byte_offset as u64,
+            header.index.kvd_byte_length as u64,
+        ) else {
+            return;
+        };
+
+        let mut keys = Vec::new();
+
+        while !bytes.is_empty() {
+            let Some(length) = bytes.get(..4) else {
+                self.error("the key/value data ends partway through a length".to_string());
+                break;
+            };
+
+            let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+
+            let Some(key_and_value) = 4usize.checked_add(length).and_then(|end| bytes.get(4..end))
+            else {
+                self.error(format!(
+                    "a key/value pair of {} bytes extends past the key/value data",
+                    length
+                ));
+                break;
+            };
+
+            let padded_end = crate::align(4 + length, 4);
+            let padding = bytes.get(4 + length..padded_end).unwrap_or_default();
+
+            if padding.len() != padded_end - 4 - length {
+                self.error("the last key/value pair isn't padded to 4 bytes".to_string());
+            } else if padding.iter().any(|&byte| byte != 0) {
+                self.warning("key/value padding bytes aren't zero".to_string());
+            }
+
+            bytes = bytes.get(padded_end..).unwrap_or_default();
+
+            let Some(key_end) = key_and_value.iter().position(|&byte| byte == 0) else {
+                self.error("a key isn't NUL-terminated".to_string());
+                continue;
+            };
+
+            let Ok(key) = std::str::from_utf8(&key_and_value[..key_end]) else {
+                self.error("a key isn't valid UTF-8".to_string());
+                continue;
+            };
+
+            let value = &key_and_value[key_end + 1..];
+
+            if key.is_empty() {
+                self.error("a key is empty".to_string());
+            }
+
+            if metadata::STANDARD_KEYS.contains(&key) {
+                if let Err(error) = Metadata::default().insert(key, value) {
+                    self.error(error.to_string());
+                }
+            } else if key.starts_with("KTX") || key.starts_with("ktx") {
+                self.warning(format!(
+                    "{} uses the reserved KTX prefix but isn't a known key",
+                    key
+                ));
+            }
+
+            keys.push(key);
+        }
+
+        for pair in keys.windows(2) {
+            if pair[0] == pair[1] {
+                self.error(format!("the key {} appears more than once", pair[0]));
+            } else if pair[0].as_bytes() > pair[1].as_bytes() {
+                self.error(format!(
+                    "keys must be sorted by their bytes, but {} comes before {}",
+                    pair[0], pair[1]
+                ));
+            }
+        }
+
+        if !keys.contains(&metadata::KTX_WRITER) {
+            self.warning("the KTXwriter key is missing".to_string());
+        }
+    }
+
+    fn supercompression_global_data(&mut self, header: &Header) {
+        let length = header.index.sgd_byte_length;
+
+        if header.supercompression_scheme != Some(SupercompressionScheme::BasisLZ) {
+            let is_vendor_scheme = header
+                .supercompression_scheme
+                .is_some_and(|scheme| scheme.value() >= 0x10000);
+
+            if length != 0 && !is_vendor_scheme {
+                self.error(format!(
+                    "there are {} bytes of supercompression global data but {:?} doesn't use any",
+                    length, header.supercompression_scheme
+                ));
+            }
+            return;
+        }
+
+        let Some(bytes) = self.range(header.index.sgd_byte_offset, length) else {
+            return;
+        };
+
+        if (bytes.len() as u64) < BASIS_LZ_GLOBAL_HEADER_LENGTH {
+            self.error("the BasisLZ global data is too short for its header".to_string());
+            return;
+        }
+
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        let image_count = (header.level_count.max(1) as u64)
+            .saturating_mul(header.layer_count.max(1) as u64)
+            .saturating_mul(header.face_count as u64);
+        // The header's counts and section lengths come from the file, so saturate rather than
+        // overflow.
+        let expected = [4, 8, 12, 16].into_iter().fold(
+            image_count
+                .saturating_mul(BASIS_LZ_IMAGE_DESC_LENGTH)
+                .saturating_add(BASIS_LZ_GLOBAL_HEADER_LENGTH),
+            |expected, offset| expected.saturating_add(read_u32(offset) as u64),
+        );
+
+        if bytes.len() as u64 != expected {
+            self.error(format!(
+                "the BasisLZ global data is {} bytes but its header and {} images imply {}",
+                bytes.len(),
+                image_count,
+                expected
+            ));
+        }
+    }
+}
+
+fn parse_header(bytes: &[u8; Header::LENGTH]) -> Header {
+    // `Header::from_bytes` rejects some invalid values outright, so parse the fields directly to
+    // be able to report them.
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+
+    Header {
+        format: Format::new(u32_at(12)),
+        type_size: u32_at(16),
+        pixel_width: u32_at(20),
+        pixel_height: u32_at(24),
+        pixel_depth: u32_at(28),
+        layer_count: u32_at(32),
+        face_count: u32_at(36),
+        level_count: u32_at(40),
+        supercompression_scheme: SupercompressionScheme::new(u32_at(44)),
+        index: ktx2::Index {
+            dfd_byte_offset: u32_at(48),
+            dfd_byte_length: u32_at(52),
+            kvd_byte_offset: u32_at(56),
+            kvd_byte_length: u32_at(60),
+            sgd_byte_offset: u64_at(64),
+            sgd_byte_length: u64_at(72),
+        },
+    }
+}
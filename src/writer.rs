@@ -0,0 +1,406 @@
+use crate::{align, compress_level, lcm, metadata, BasicDfd, Error, WriterHeader};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+pub struct Writer<'a> {
+    pub header: WriterHeader,
+    /// The raw data format descriptor. If this is empty, a Basic DFD is generated from
+    /// `header.format`.
+    pub dfd_bytes: &'a [u8],
+    pub key_value_pairs: &'a BTreeMap<String, Vec<u8>>,
+    pub sgd_bytes: &'a [u8],
+    pub uncompressed_levels_descending: &'a [Cow<'a, [u8]>],
+    pub options: WriterOptions,
+}
+
+impl<'a> Writer<'a> {
+    pub fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), Error> {
+        let Layout {
+            prelude,
+            levels,
+            compressed_levels,
+        } = self.layout()?;
+
+        prelude.write_index(writer, &levels)?;
+        prelude.write_metadata(writer)?;
+        write_levels(writer, prelude.len(), &levels, &compressed_levels)?;
+
+        Ok(())
+    }
+
+    /// Write the file split for progressive streaming. `prefix` gets the header, metadata and
+    /// the tail of the mip chain from `first_tail_level` down to the smallest level, and the
+    /// supercompressed data of each larger level is returned separately, largest first.
+    ///
+    /// The level index in `prefix` still describes the whole file, so each returned payload is
+    /// exactly the byte range that its level's index entry gives, and appending them to `prefix`
+    /// smallest first, with the alignment padding in between, reproduces the output of
+    /// [`write`](Self::write).
+    pub fn write_split<T: std::io::Write>(
+        &self,
+        prefix: &mut T,
+        first_tail_level: u32,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let Layout {
+            prelude,
+            levels,
+            compressed_levels,
+        } = self.layout()?;
+        let first_tail_level = first_tail_level as usize;
+
+        if first_tail_level > levels.len() {
+            return Err(Error::LevelOutOfRange(first_tail_level as u32));
+        }
+
+        prelude.write_index(prefix, &levels)?;
+        prelude.write_metadata(prefix)?;
+        write_levels(
+            prefix,
+            prelude.len(),
+            &levels[first_tail_level..],
+            &compressed_levels[first_tail_level..],
+        )?;
+
+        Ok(compressed_levels
+            .into_iter()
+            .take(first_tail_level)
+            .map(Cow::into_owned)
+            .collect())
+    }
+
+    /// Supercompress the levels and work out where they go in the file.
+    fn layout(&self) -> Result<Layout<'_>, Error> {
+        let prelude = Prelude::new(
+            self.header,
+            self.uncompressed_levels_descending.len(),
+            self.dfd_bytes,
+            self.key_value_pairs,
+            self.sgd_bytes,
+            &self.options,
+        )?;
+
+        for (i, level) in self.uncompressed_levels_descending.iter().enumerate() {
+            self.header.check_level_size(i, level)?;
+        }
+
+        let compress_levels = || {
+            self.uncompressed_levels_descending
+                .par_iter()
+                .map(|level| {
+                    compress_level(self.header.supercompression_scheme, level, &self.options)
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        };
+
+        let compressed_levels = match self.options.threads {
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(std::io::Error::other)?
+                .install(compress_levels)?,
+            None => compress_levels()?,
+        };
+
+        let level_alignment = prelude.level_alignment();
+
+        let mut offset = prelude.len();
+
+        let mut levels = self
+            .uncompressed_levels_descending
+            .iter()
+            .zip(&compressed_levels)
+            .rev()
+            .map(|(uncompressed_level, level)| {
+                offset = align(offset, level_alignment);
+
+                let index = ktx2::LevelIndex {
+                    byte_offset: offset as u64,
+                    byte_length: level.len() as u64,
+                    uncompressed_byte_length: self
+                        .header
+                        .uncompressed_byte_length(uncompressed_level),
+                };
+
+                offset += level.len();
+
+                index
+            })
+            .collect::<Vec<_>>();
+
+        levels.reverse();
+
+        Ok(Layout {
+            prelude,
+            levels,
+            compressed_levels,
+        })
+    }
+}
+
+struct Layout<'a> {
+    prelude: Prelude<'a>,
+    levels: Vec<ktx2::LevelIndex>,
+    compressed_levels: Vec<Cow<'a, [u8]>>,
+}
+
+/// Write levels smallest first, starting at `position` in the file, padding each to its offset.
+fn write_levels<T: std::io::Write>(
+    writer: &mut T,
+    mut position: usize,
+    levels: &[ktx2::LevelIndex],
+    compressed_levels: &[Cow<[u8]>],
+) -> std::io::Result<()> {
+    for (index, level) in levels.iter().zip(compressed_levels).rev() {
+        writer.write_all(&vec![0; index.byte_offset as usize - position])?;
+        writer.write_all(level)?;
+        position = index.byte_offset as usize + level.len();
+    }
+
+    Ok(())
+}
+
+/// Everything in a KTX2 file before the level data: the header, level index, DFD, key/value
+/// data and supercompression global data.
+pub(crate) struct Prelude<'a> {
+    pub(crate) header: WriterHeader,
+    level_count: usize,
+    dfd_bytes: Cow<'a, [u8]>,
+    kvd_bytes: Vec<u8>,
+    sgd_bytes: &'a [u8],
+}
+
+impl<'a> Prelude<'a> {
+    pub(crate) fn new(
+        header: WriterHeader,
+        level_count: usize,
+        dfd_bytes: &'a [u8],
+        key_value_pairs: &BTreeMap<String, Vec<u8>>,
+        sgd_bytes: &'a [u8],
+        options: &WriterOptions,
+    ) -> Result<Self, Error> {
+        let max_level_count = header.max_level_count();
+
+        if level_count == 0 || level_count > max_level_count as usize {
+            return Err(Error::InconsistentLevelCount {
+                level_count,
+                max_level_count,
+            });
+        }
+
+        let dfd_bytes = if dfd_bytes.is_empty() {
+            let mut dfd = header
+                .format
+                .and_then(BasicDfd::from_format)
+                .ok_or(Error::UnsupportedFormat(header.format))?;
+
+            // `bytesPlane0` must be zero when the levels are supercompressed.
+            if header.supercompression_scheme.is_some() {
+                dfd.header.bytes_planes = [0; 8];
+            }
+
+            Cow::Owned(dfd.to_bytes())
+        } else {
+            Cow::Borrowed(dfd_bytes)
+        };
+
+        let mut key_value_pairs = key_value_pairs.clone();
+
+        let ktx_writer = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+        let existing = key_value_pairs
+            .get(metadata::KTX_WRITER)
+            .map(|existing| {
+                String::from_utf8_lossy(existing)
+                    .trim_end_matches('\0')
+                    .to_string()
+            })
+            .filter(|existing| options.preserve_ktx_writer_history && !existing.is_empty());
+
+        let ktx_writer = match existing {
+            Some(existing) if existing.ends_with(ktx_writer) => existing,
+            Some(existing) => format!("{} / {}", existing, ktx_writer),
+            None => ktx_writer.to_string(),
+        };
+
+        key_value_pairs.insert(
+            metadata::KTX_WRITER.to_string(),
+            [ktx_writer.as_bytes(), b"\0"].concat(),
+        );
+
+        let mut kvd_bytes = Vec::new();
+
+        for (key, value) in key_value_pairs.iter() {
+            let length = (key.len() + 1 + value.len()) as u32;
+
+            kvd_bytes.extend_from_slice(&length.to_le_bytes());
+
+            kvd_bytes.extend_from_slice(key.as_bytes());
+
+            kvd_bytes.push(b'\0');
+
+            kvd_bytes.extend_from_slice(value);
+
+            while kvd_bytes.len() % 4 != 0 {
+                kvd_bytes.push(0);
+            }
+        }
+
+        Ok(Self {
+            header,
+            level_count,
+            dfd_bytes,
+            kvd_bytes,
+            sgd_bytes,
+        })
+    }
+
+    fn dfd_offset(&self) -> usize {
+        ktx2::Header::LENGTH + self.level_count * ktx2::LevelIndex::LENGTH
+    }
+
+    fn kvd_end(&self) -> usize {
+        self.dfd_offset() + self.dfd_bytes.len() + self.kvd_bytes.len()
+    }
+
+    fn sgd_offset(&self) -> usize {
+        if self.sgd_bytes.is_empty() {
+            self.kvd_end()
+        } else {
+            align(self.kvd_end(), 8)
+        }
+    }
+
+    /// The length of the prelude, where the level data starts.
+    pub(crate) fn len(&self) -> usize {
+        self.sgd_offset() + self.sgd_bytes.len()
+    }
+
+    pub(crate) fn level_alignment(&self) -> usize {
+        level_alignment(self.header.supercompression_scheme, &self.dfd_bytes)
+    }
+
+    /// Write the header and the level index.
+    pub(crate) fn write_index<T: std::io::Write>(
+        &self,
+        writer: &mut T,
+        levels: &[ktx2::LevelIndex],
+    ) -> std::io::Result<()> {
+        let dfd_offset = self.dfd_offset();
+
+        writer.write_all(
+            &ktx2::Header {
+                format: self.header.format,
+                type_size: self.header.type_size,
+                pixel_width: self.header.pixel_width,
+                pixel_height: self.header.pixel_height,
+                pixel_depth: self.header.pixel_depth,
+                layer_count: self.header.layer_count,
+                face_count: self.header.face_count,
+                supercompression_scheme: self.header.supercompression_scheme,
+                level_count: self.level_count as u32,
+                index: ktx2::Index {
+                    dfd_byte_length: self.dfd_bytes.len() as u32,
+                    kvd_byte_length: self.kvd_bytes.len() as u32,
+                    sgd_byte_length: self.sgd_bytes.len() as u64,
+                    dfd_byte_offset: dfd_offset as u32,
+                    kvd_byte_offset: if self.kvd_bytes.is_empty() {
+                        0
+                    } else {
+                        dfd_offset + self.dfd_bytes.len()
+                    } as u32,
+                    sgd_byte_offset: if self.sgd_bytes.is_empty() {
+                        0
+                    } else {
+                        self.sgd_offset()
+                    } as u64,
+                },
+            }
+            .as_bytes()[..],
+        )?;
+
+        for level in levels {
+            writer.write_all(&level.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Write everything following the level index, up to the level data.
+    pub(crate) fn write_metadata<T: std::io::Write>(&self, writer: &mut T) -> std::io::Result<()> {
+        writer.write_all(&self.dfd_bytes)?;
+        writer.write_all(&self.kvd_bytes)?;
+
+        if !self.sgd_bytes.is_empty() {
+            writer.write_all(&vec![0; self.sgd_offset() - self.kvd_end()])?;
+            writer.write_all(self.sgd_bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriterOptions {
+    /// The Zstandard compression level. `0` selects zstd's default level.
+    pub zstd_level: i32,
+    /// Enable zstd's long-distance matching, which helps large levels with repeated content.
+    pub zstd_long_distance_matching: bool,
+    /// Override the zstd window size, as a power of two. Windows larger than 2^27 require
+    /// readers to raise their decoder's window limit.
+    pub zstd_window_log: Option<u32>,
+    /// The ZLIB (deflate) compression level, from 0 to 9. `None` selects flate2's default level.
+    pub zlib_level: Option<u32>,
+    /// The number of levels to compress at once. `None` uses rayon's global thread pool.
+    pub threads: Option<usize>,
+    /// Append to an existing `KTXwriter` value, e.g. `toktx v4.3 / ktx2-tools 0.1.0`, instead of
+    /// replacing it.
+    pub preserve_ktx_writer_history: bool,
+}
+
+/// The alignment of each mip level's data within the file. Supercompressed levels only need
+/// byte alignment, otherwise levels are aligned to `lcm(texel block size, 4)` so that they can
+/// be copied (or memory-mapped) straight into upload buffers.
+fn level_alignment(
+    supercompression_scheme: Option<ktx2::SupercompressionScheme>,
+    dfd_bytes: &[u8],
+) -> usize {
+    if supercompression_scheme.is_some() {
+        return 1;
+    }
+
+    // Skip `dfdTotalSize` and the descriptor block header to get to the Basic DFD.
+    let texel_block_size = dfd_bytes
+        .get(4 + ktx2::DfdHeader::LENGTH..)
+        .and_then(|bytes| BasicDfd::from_bytes(bytes).ok())
+        .map(|dfd| dfd.bytes_per_block() as usize)
+        .filter(|&size| size != 0)
+        .unwrap_or(1);
+
+    lcm(texel_block_size, 4)
+}
+
+/// A texture produced by one of the encoders, ready to be handed to a [`Writer`].
+pub struct EncodedTexture {
+    pub header: WriterHeader,
+    pub dfd_bytes: Vec<u8>,
+    pub sgd_bytes: Vec<u8>,
+    pub levels: Vec<Cow<'static, [u8]>>,
+}
+
+impl EncodedTexture {
+    pub fn writer<'a>(
+        &'a self,
+        key_value_pairs: &'a BTreeMap<String, Vec<u8>>,
+        options: WriterOptions,
+    ) -> Writer<'a> {
+        Writer {
+            header: self.header,
+            dfd_bytes: &self.dfd_bytes,
+            key_value_pairs,
+            sgd_bytes: &self.sgd_bytes,
+            uncompressed_levels_descending: &self.levels,
+            options,
+        }
+    }
+}
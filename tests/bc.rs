@@ -0,0 +1,78 @@
+#![cfg(feature = "bc-encode")]
+
+use ktx2_tools::{decode_bc, encode_bc, BcFormat, BcOptions, EncodedTexture};
+
+fn encode(image: &image::RgbaImage, format: BcFormat, generate_mipmaps: bool) -> EncodedTexture {
+    encode_bc(
+        image,
+        &BcOptions {
+            format,
+            srgb: false,
+            generate_mipmaps,
+            supercompression_scheme: None,
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn small_images_keep_their_dimensions() {
+    let image = image::RgbaImage::from_fn(2, 3, |x, y| {
+        image::Rgba([x as u8 * 200, y as u8 * 100, 50, 255])
+    });
+
+    for (format, block_size) in [
+        (BcFormat::Bc1, 8),
+        (BcFormat::Bc3, 16),
+        (BcFormat::Bc4, 8),
+        (BcFormat::Bc5, 16),
+        (BcFormat::Bc7, 16),
+    ] {
+        let encoded = encode(&image, format, true);
+
+        assert_eq!(
+            (encoded.header.pixel_width, encoded.header.pixel_height),
+            (2, 3)
+        );
+        assert_eq!(encoded.levels.len(), 1);
+        assert_eq!(encoded.levels[0].len(), block_size);
+    }
+
+    // Only the block data is padded, so decoding at the real size gives the image back.
+    let encoded = encode(&image, BcFormat::Bc7, true);
+    let decoded = decode_bc(encoded.header.format.unwrap(), &encoded.levels[0], 2, 3)
+        .unwrap()
+        .into_rgba8();
+
+    for (decoded, pixel) in decoded.chunks(4).zip(image.pixels()) {
+        assert!(decoded
+            .iter()
+            .zip(pixel.0)
+            .all(|(&decoded, expected)| decoded.abs_diff(expected) <= 4));
+    }
+}
+
+#[test]
+fn mipmaps_stop_at_4x4() {
+    let image = image::RgbaImage::from_pixel(20, 9, image::Rgba([10, 20, 30, 255]));
+    let encoded = encode(&image, BcFormat::Bc1, true);
+
+    // 20x9 and 10x4, padded to 5x3 and 3x1 blocks.
+    let sizes = encoded.levels.iter().map(|level| level.len());
+    assert_eq!(sizes.collect::<Vec<_>>(), [15 * 8, 3 * 8]);
+}
+
+#[test]
+fn bc7_alpha_is_kept() {
+    let image =
+        image::RgbaImage::from_fn(8, 8, |x, y| image::Rgba([200, 100, 50, (x * 32 + y) as u8]));
+
+    let encoded = encode(&image, BcFormat::Bc7, false);
+    let decoded = decode_bc(encoded.header.format.unwrap(), &encoded.levels[0], 8, 8)
+        .unwrap()
+        .into_rgba8();
+
+    for (decoded, pixel) in decoded.chunks(4).zip(image.pixels()) {
+        assert!(decoded[3].abs_diff(pixel[3]) <= 4);
+    }
+}
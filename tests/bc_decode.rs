@@ -0,0 +1,481 @@
+#![cfg(feature = "bc-decode")]
+
+use ktx2::Format;
+use ktx2_tools::{decode_bc, DecodedPixels, Error};
+
+/// Pack `(value, bit count)` fields into a block, least significant bit first.
+fn pack(fields: &[(u128, u32)]) -> [u8; 16] {
+    let mut bits = 0u128;
+    let mut position = 0;
+
+    for &(value, count) in fields {
+        assert!(value < 1 << count);
+        bits |= value << position;
+        position += count;
+    }
+
+    assert!(position <= 128);
+    bits.to_le_bytes()
+}
+
+fn rgba8(format: Format, block: &[u8]) -> Vec<[u8; 4]> {
+    match decode_bc(format, block, 4, 4).unwrap() {
+        DecodedPixels::Rgba8(pixels) => pixels.chunks(4).map(|p| p.try_into().unwrap()).collect(),
+        DecodedPixels::RgbaF32(_) => panic!("expected RGBA8"),
+    }
+}
+
+fn rgba_f32(format: Format, block: &[u8]) -> Vec<[f32; 4]> {
+    match decode_bc(format, block, 4, 4).unwrap() {
+        DecodedPixels::RgbaF32(pixels) => pixels.chunks(4).map(|p| p.try_into().unwrap()).collect(),
+        DecodedPixels::Rgba8(_) => panic!("expected RGBA f32"),
+    }
+}
+
+/// 2-bit BC1 indices, pixel 0 first.
+fn bc1_indices(indices: [u32; 16]) -> [u8; 4] {
+    indices
+        .iter()
+        .enumerate()
+        .fold(0u32, |bits, (i, index)| bits | index << (i * 2))
+        .to_le_bytes()
+}
+
+/// A BC4 block with 3-bit indices, pixel 0 first.
+fn bc4_block(a: u8, b: u8, indices: [u64; 16]) -> [u8; 8] {
+    let indices = indices
+        .iter()
+        .enumerate()
+        .fold(0u64, |bits, (i, index)| bits | index << (i * 3));
+
+    (a as u64 | (b as u64) << 8 | indices << 16).to_le_bytes()
+}
+
+#[test]
+fn bc1_four_colors() {
+    let mut block = [0xff, 0xff, 0x00, 0x00, 0, 0, 0, 0];
+    block[4..].copy_from_slice(&bc1_indices(std::array::from_fn(|i| i as u32 % 4)));
+
+    let pixels = rgba8(Format::BC1_RGBA_UNORM_BLOCK, &block);
+
+    assert_eq!(pixels[0], [255, 255, 255, 255]);
+    assert_eq!(pixels[1], [0, 0, 0, 255]);
+    assert_eq!(pixels[2], [170, 170, 170, 255]);
+    assert_eq!(pixels[3], [85, 85, 85, 255]);
+}
+
+#[test]
+fn bc1_three_colors_and_transparent_black() {
+    // 0x8410 is 5-6-5 (16, 32, 16), which expands to (132, 130, 132).
+    let mut block = [0x00, 0x00, 0x10, 0x84, 0, 0, 0, 0];
+    block[4..].copy_from_slice(&bc1_indices(std::array::from_fn(|i| i as u32 % 4)));
+
+    let pixels = rgba8(Format::BC1_RGBA_UNORM_BLOCK, &block);
+
+    assert_eq!(pixels[0], [0, 0, 0, 255]);
+    assert_eq!(pixels[1], [132, 130, 132, 255]);
+    assert_eq!(pixels[2], [66, 65, 66, 255]);
+    assert_eq!(pixels[3], [0, 0, 0, 0]);
+}
+
+#[test]
+fn bc2_explicit_alpha() {
+    let alpha = (0..16u64).fold(0, |bits, i| bits | i << (i * 4));
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&alpha.to_le_bytes());
+    // BC2 color always has four colors, even when the first endpoint is smaller.
+    block[8..12].copy_from_slice(&[0x00, 0x00, 0xff, 0xff]);
+    block[12..].copy_from_slice(&bc1_indices([3; 16]));
+
+    let pixels = rgba8(Format::BC2_UNORM_BLOCK, &block);
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        assert_eq!(*pixel, [170, 170, 170, i as u8 * 17]);
+    }
+}
+
+#[test]
+fn bc3_interpolated_alpha() {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&bc4_block(252, 0, std::array::from_fn(|i| i as u64 % 8)));
+    block[8..12].copy_from_slice(&[0xff, 0xff, 0x00, 0x00]);
+
+    let alpha = rgba8(Format::BC3_UNORM_BLOCK, &block)
+        .iter()
+        .map(|pixel| pixel[3])
+        .collect::<Vec<_>>();
+
+    assert_eq!(&alpha[..8], [252, 0, 216, 180, 144, 108, 72, 36]);
+}
+
+#[test]
+fn bc4_unorm_six_values_with_extremes() {
+    let block = bc4_block(0, 250, std::array::from_fn(|i| i as u64 % 8));
+
+    let red = rgba8(Format::BC4_UNORM_BLOCK, &block)
+        .iter()
+        .map(|pixel| {
+            assert_eq!(pixel[1..], [0, 0, 255]);
+            pixel[0]
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(&red[..8], [0, 250, 50, 100, 150, 200, 0, 255]);
+}
+
+#[test]
+fn bc4_snorm() {
+    let block = bc4_block(127, -127i8 as u8, std::array::from_fn(|i| i as u64 % 8));
+    let red = rgba_f32(Format::BC4_SNORM_BLOCK, &block);
+    let expected = [
+        1.0,
+        -1.0,
+        5.0 / 7.0,
+        3.0 / 7.0,
+        1.0 / 7.0,
+        -1.0 / 7.0,
+        -3.0 / 7.0,
+        -5.0 / 7.0,
+    ];
+
+    for (pixel, expected) in red.iter().zip(expected) {
+        assert!((pixel[0] - expected).abs() < 1e-6, "{:?}", pixel);
+        assert_eq!(pixel[1..], [0.0, 0.0, 1.0]);
+    }
+
+    // -128 is clamped to -1, and -1 isn't greater than 1, so this block has six values.
+    let block = bc4_block(-128i8 as u8, 127, std::array::from_fn(|i| i as u64 % 8));
+    let red = rgba_f32(Format::BC4_SNORM_BLOCK, &block);
+    let expected = [-1.0, 1.0, -0.6, -0.2, 0.2, 0.6, -1.0, 1.0];
+
+    for (pixel, expected) in red.iter().zip(expected) {
+        assert!((pixel[0] - expected).abs() < 1e-6, "{:?}", pixel);
+    }
+}
+
+#[test]
+fn bc5_unorm() {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&bc4_block(252, 0, [2; 16]));
+    block[8..].copy_from_slice(&bc4_block(0, 250, [5; 16]));
+
+    assert!(rgba8(Format::BC5_UNORM_BLOCK, &block)
+        .iter()
+        .all(|pixel| *pixel == [216, 200, 0, 255]));
+}
+
+#[test]
+fn bc7_mode_6_interpolation() {
+    let mut fields = vec![(1 << 6, 7)];
+    // R, G, B and A for both endpoints.
+    fields.extend([
+        (127, 7),
+        (0, 7),
+        (0, 7),
+        (127, 7),
+        (64, 7),
+        (64, 7),
+        (127, 7),
+        (127, 7),
+    ]);
+    // P-bits, one per endpoint.
+    fields.extend([(1, 1), (0, 1)]);
+    // Pixel 0 is the anchor, with a 3-bit index.
+    fields.push((0, 3));
+    fields.extend([(15, 4), (8, 4), (1, 4)]);
+    fields.extend([(0, 4); 12]);
+
+    let pixels = rgba8(Format::BC7_UNORM_BLOCK, &pack(&fields));
+
+    assert_eq!(pixels[0], [255, 1, 129, 255]);
+    assert_eq!(pixels[1], [0, 254, 128, 254]);
+    assert_eq!(pixels[2], [120, 135, 128, 254]);
+    assert_eq!(pixels[3], [239, 17, 129, 255]);
+}
+
+#[test]
+fn bc7_mode_1_partition_and_shared_p_bits() {
+    // Partition 0 puts the two right columns in the second subset.
+    let mut fields = vec![(1 << 1, 2), (0, 6)];
+    // Two endpoints per subset for each channel: red in the first subset, blue in the second.
+    fields.extend([(63, 6), (63, 6), (0, 6), (0, 6)]);
+    fields.extend([(0, 6); 4]);
+    fields.extend([(0, 6), (0, 6), (63, 6), (63, 6)]);
+    // Shared p-bits, one per subset.
+    fields.extend([(1, 1), (1, 1)]);
+    // The anchors are pixels 0 and 15.
+    fields.push((0, 2));
+    fields.extend([(0, 3); 14]);
+    fields.push((0, 2));
+
+    let pixels = rgba8(Format::BC7_UNORM_BLOCK, &pack(&fields));
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        let expected = if i % 4 < 2 {
+            [255, 2, 2, 255]
+        } else {
+            [2, 2, 255, 255]
+        };
+
+        assert_eq!(*pixel, expected, "pixel {}", i);
+    }
+}
+
+#[test]
+fn bc7_mode_5_rotation() {
+    // Rotation 1 swaps red and alpha.
+    let mut fields = vec![(1 << 5, 6), (1, 2)];
+    fields.extend([(0, 7); 6]);
+    fields.extend([(255, 8), (255, 8)]);
+
+    let pixels = rgba8(Format::BC7_UNORM_BLOCK, &pack(&fields));
+
+    assert!(pixels.iter().all(|pixel| *pixel == [255, 0, 0, 0]));
+}
+
+#[test]
+fn bc7_reserved_mode() {
+    let block = pack(&[(0, 8), (u128::MAX >> 8, 120)]);
+
+    assert!(rgba8(Format::BC7_UNORM_BLOCK, &block)
+        .iter()
+        .all(|pixel| *pixel == [0; 4]));
+}
+
+#[test]
+fn bc6h_mode_11_unsigned() {
+    let mut fields = vec![(0b00011, 5)];
+    fields.extend([
+        (1023, 10),
+        (0, 10),
+        (512, 10),
+        (0, 10),
+        (1023, 10),
+        (512, 10),
+    ]);
+    // Pixel 0 is the anchor, with a 3-bit index.
+    fields.push((0, 3));
+    fields.push((15, 4));
+
+    let pixels = rgba_f32(Format::BC6H_UFLOAT_BLOCK, &pack(&fields));
+
+    // The largest endpoint unquantizes to the largest finite half, and 512 to 0x3e0f.
+    assert_eq!(pixels[0], [65504.0, 0.0, 1551.0 / 1024.0, 1.0]);
+    assert_eq!(pixels[1], [0.0, 65504.0, 1551.0 / 1024.0, 1.0]);
+}
+
+#[test]
+fn bc6h_mode_11_signed() {
+    let mut fields = vec![(0b00011, 5)];
+    // 511 is the largest positive 10-bit value, and 0x3ff is -1.
+    fields.extend([(511, 10), (0x3ff, 10), (0, 10), (0, 10), (0, 10), (0, 10)]);
+
+    let pixels = rgba_f32(Format::BC6H_SFLOAT_BLOCK, &pack(&fields));
+
+    // -1 unquantizes to -96, which finishes as the half 0x805d.
+    assert_eq!(pixels[0], [65504.0, -93.0 / 16777216.0, 0.0, 1.0]);
+}
+
+#[test]
+fn bc6h_mode_14_reversed_bits_and_negative_delta() {
+    let mut fields = vec![(0b01111, 5)];
+    fields.extend([(0, 10), (0, 10), (0, 10)]);
+    // Red's delta is -1. The high bits of the base endpoints are stored in reverse, so this sets
+    // bit 15 of red.
+    fields.extend([(0xf, 4), (1, 6), (0, 4), (0, 6), (0, 4), (0, 6)]);
+    fields.push((0, 3));
+    fields.push((15, 4));
+
+    let pixels = rgba_f32(Format::BC6H_UFLOAT_BLOCK, &pack(&fields));
+
+    // 0x8000 and 0x7fff finish as the halves 0x3e00 and 0x3dff.
+    assert_eq!(pixels[0], [1.5, 0.0, 0.0, 1.0]);
+    assert_eq!(pixels[1], [1535.0 / 1024.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn bc6h_reserved_mode() {
+    let block = pack(&[(0b10011, 5), (u128::MAX >> 5, 123)]);
+
+    assert!(rgba_f32(Format::BC6H_UFLOAT_BLOCK, &block)
+        .iter()
+        .all(|pixel| *pixel == [0.0, 0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn partial_blocks_are_cropped() {
+    let mut blocks = [0; 16];
+    blocks[..8].copy_from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+    blocks[8..].copy_from_slice(&[0x00, 0xf8, 0x00, 0xf8, 0, 0, 0, 0]);
+
+    let pixels = decode_bc(Format::BC1_RGB_UNORM_BLOCK, &blocks, 5, 3)
+        .unwrap()
+        .into_rgba8();
+
+    assert_eq!(pixels.len(), 5 * 3 * 4);
+
+    for row in pixels.chunks(5 * 4) {
+        assert_eq!(row[..16], [255; 16]);
+        assert_eq!(row[16..], [255, 0, 0, 255]);
+    }
+}
+
+#[test]
+fn conversions() {
+    let pixels = DecodedPixels::RgbaF32(vec![-1.0, 0.5, 2.0, 1.0]);
+    assert_eq!(pixels.into_rgba8(), [0, 128, 255, 255]);
+
+    let pixels = DecodedPixels::Rgba8(vec![0, 51, 255, 255]);
+    assert_eq!(pixels.into_rgba_f32(), [0.0, 0.2, 1.0, 1.0]);
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        decode_bc(Format::BC7_UNORM_BLOCK, &[0; 16], 8, 4),
+        Err(Error::InvalidDimensions(_))
+    ));
+
+    assert!(matches!(
+        decode_bc(Format::R8G8B8A8_UNORM, &[0; 64], 4, 4),
+        Err(Error::UnsupportedFormat(Some(Format::R8G8B8A8_UNORM)))
+    ));
+}
+
+/// Blocks from ISPC Texture Compressor should decode close to the image they were encoded from.
+#[cfg(feature = "bc-encode")]
+mod round_trip {
+    use super::*;
+
+    const SIZE: u32 = 32;
+
+    fn image() -> Vec<u8> {
+        (0..SIZE * SIZE)
+            .flat_map(|i| {
+                let (x, y) = (i % SIZE, i / SIZE);
+                let edge = if (x + y) % 11 < 4 { 60 } else { 0 };
+                [x * 6 + edge, y * 7, (x + y) * 3 + edge, 255 - x * 4]
+            })
+            .map(|value| value.min(255) as u8)
+            .collect()
+    }
+
+    fn psnr(a: impl Iterator<Item = f64>, b: impl Iterator<Item = f64>, peak: f64) -> f64 {
+        let (sum, count) = a.zip(b).fold((0.0, 0), |(sum, count), (a, b)| {
+            (sum + (a - b) * (a - b), count + 1)
+        });
+
+        10.0 * (peak * peak / (sum / count as f64)).log10()
+    }
+
+    fn check(format: Format, blocks: Vec<u8>, channels: &[usize], min_psnr: f64) {
+        let source = image();
+        let decoded = decode_bc(format, &blocks, SIZE, SIZE).unwrap().into_rgba8();
+
+        for &channel in channels {
+            let psnr = psnr(
+                source.iter().skip(channel).step_by(4).map(|&v| v as f64),
+                decoded.iter().skip(channel).step_by(4).map(|&v| v as f64),
+                255.0,
+            );
+
+            assert!(
+                psnr > min_psnr,
+                "{:?} channel {}: {}",
+                format,
+                channel,
+                psnr
+            );
+        }
+    }
+
+    fn surface(data: &[u8]) -> intel_tex_2::RgbaSurface<'_> {
+        intel_tex_2::RgbaSurface {
+            data,
+            width: SIZE,
+            height: SIZE,
+            stride: SIZE * 4,
+        }
+    }
+
+    #[test]
+    fn bc1_to_bc5() {
+        let image = image();
+        let surface = surface(&image);
+
+        let bc1 = intel_tex_2::bc1::compress_blocks(&surface);
+        check(Format::BC1_RGB_UNORM_BLOCK, bc1, &[0, 1, 2], 30.0);
+        let bc3 = intel_tex_2::bc3::compress_blocks(&surface);
+        check(Format::BC3_UNORM_BLOCK, bc3, &[0, 1, 2, 3], 30.0);
+        let bc4 = intel_tex_2::bc4::compress_blocks(&surface);
+        check(Format::BC4_UNORM_BLOCK, bc4, &[0], 38.0);
+        let bc5 = intel_tex_2::bc5::compress_blocks(&surface);
+        check(Format::BC5_UNORM_BLOCK, bc5, &[0, 1], 38.0);
+    }
+
+    #[test]
+    fn bc7() {
+        let image = image();
+
+        for settings in [
+            intel_tex_2::bc7::alpha_ultra_fast_settings(),
+            intel_tex_2::bc7::alpha_slow_settings(),
+            intel_tex_2::bc7::opaque_slow_settings(),
+        ] {
+            let blocks = intel_tex_2::bc7::compress_blocks(&settings, &surface(&image));
+            check(Format::BC7_UNORM_BLOCK, blocks, &[0, 1, 2], 30.0);
+        }
+    }
+
+    #[test]
+    fn bc6h() {
+        let values = (0..SIZE * SIZE)
+            .flat_map(|i| {
+                let (x, y) = (i % SIZE, i / SIZE);
+                [
+                    x as f32 / 4.0,
+                    y as f32 / 8.0,
+                    ((x + y) as f32 / 8.0).powi(2),
+                    1.0,
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let halves = values
+            .iter()
+            .flat_map(|&value| half::f16::from_f32(value).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        for settings in [
+            intel_tex_2::bc6h::very_fast_settings(),
+            intel_tex_2::bc6h::very_slow_settings(),
+        ] {
+            let blocks = intel_tex_2::bc6h::compress_blocks(
+                &settings,
+                &intel_tex_2::RgbaSurface {
+                    data: &halves,
+                    width: SIZE,
+                    height: SIZE,
+                    stride: SIZE * 8,
+                },
+            );
+
+            let decoded = decode_bc(Format::BC6H_UFLOAT_BLOCK, &blocks, SIZE, SIZE)
+                .unwrap()
+                .into_rgba_f32();
+
+            for channel in 0..3 {
+                let source = values.iter().skip(channel).step_by(4);
+                let peak = source.clone().fold(0.0f32, |a, &b| a.max(b)) as f64;
+
+                let psnr = psnr(
+                    source.map(|&v| v as f64),
+                    decoded.iter().skip(channel).step_by(4).map(|&v| v as f64),
+                    peak,
+                );
+
+                assert!(psnr > 30.0, "channel {}: {}", channel, psnr);
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+#![cfg(feature = "etc-encode")]
+
+use ktx2_tools::{encode_etc, EtcFormat, EtcOptions};
+
+/// The ETC1 intensity modifiers for selectors 0 and 1. Selectors 2 and 3 negate them.
+const ETC1_TABLES: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+const EAC_TABLES: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Decode an ETC1 block, or an ETC2 block in the individual or differential mode, to 16 pixels in
+/// row-major order. Differential blocks whose second color overflows are T, H or planar blocks in
+/// ETC2, which the encoder must never produce.
+fn decode_etc1(block: &[u8]) -> [[u8; 3]; 16] {
+    let bits = u64::from_be_bytes(block.try_into().unwrap());
+    let field = |low: u32, count: u32| ((bits >> low) & ((1 << count) - 1)) as i32;
+
+    let (first, second) = if field(33, 1) == 1 {
+        let base = [field(59, 5), field(51, 5), field(43, 5)];
+        let delta = [field(56, 3), field(48, 3), field(40, 3)].map(|d| (d ^ 4) - 4);
+        let second: [i32; 3] = std::array::from_fn(|c| base[c] + delta[c]);
+        assert!(
+            second.iter().all(|c| (0..32).contains(c)),
+            "T, H or planar block"
+        );
+
+        let expand = |c: i32| (c << 3) | (c >> 2);
+        (base.map(expand), second.map(expand))
+    } else {
+        (
+            [field(60, 4), field(52, 4), field(44, 4)].map(|c| c * 17),
+            [field(56, 4), field(48, 4), field(40, 4)].map(|c| c * 17),
+        )
+    };
+
+    let tables = [field(37, 3), field(34, 3)];
+    let flip = field(32, 1) == 1;
+
+    std::array::from_fn(|p| {
+        let (x, y) = (p as u32 % 4, p as u32 / 4);
+        let i = x * 4 + y;
+        let selector = field(16 + i, 1) << 1 | field(i, 1);
+        let subblock = if flip { y >= 2 } else { x >= 2 } as usize;
+
+        let [a, b] = ETC1_TABLES[tables[subblock] as usize];
+        let modifier = [a, b, -a, -b][selector as usize];
+        let base = if subblock == 0 { first } else { second };
+
+        base.map(|c| (c + modifier).clamp(0, 255) as u8)
+    })
+}
+
+/// Decode an EAC alpha or 11-bit block to 16 values in row-major order.
+fn decode_eac(block: &[u8], eleven_bit: bool) -> [u16; 16] {
+    let bits = u64::from_be_bytes(block.try_into().unwrap());
+    let base = (bits >> 56) as i32;
+    let multiplier = (bits >> 52 & 15) as i32;
+    let table = EAC_TABLES[(bits >> 48 & 15) as usize];
+
+    std::array::from_fn(|p| {
+        let i = p % 4 * 4 + p / 4;
+        let modifier = table[(bits >> (45 - 3 * i) & 7) as usize];
+
+        if eleven_bit {
+            let step = if multiplier == 0 { 1 } else { multiplier * 8 };
+            (base * 8 + 4 + modifier * step).clamp(0, 2047) as u16
+        } else {
+            (base + modifier * multiplier).clamp(0, 255) as u16
+        }
+    })
+}
+
+/// An EAC block with 3-bit selectors in column order.
+fn eac_block(base: u8, multiplier: u8, table: u8, selectors: [u64; 16]) -> [u8; 8] {
+    let selectors = selectors
+        .iter()
+        .enumerate()
+        .fold(0u64, |bits, (i, &selector)| bits | selector << (45 - 3 * i));
+
+    ((base as u64) << 56 | (multiplier as u64) << 52 | (table as u64) << 48 | selectors)
+        .to_be_bytes()
+}
+
+/// Selectors 0 to 7 in column order, so that row `y` alternates selectors `y` and `y + 4`.
+fn counting_selectors() -> [u64; 16] {
+    std::array::from_fn(|i| i as u64 % 8)
+}
+
+fn unorm11_to_unorm8(value: u16) -> u8 {
+    ((value as u32 * 255 + 1023) / 2047) as u8
+}
+
+fn encode(image: &image::RgbaImage, format: EtcFormat) -> Vec<Vec<u8>> {
+    let encoded = encode_etc(
+        image,
+        &EtcOptions {
+            format,
+            srgb: false,
+            generate_mipmaps: false,
+            supercompression_scheme: None,
+        },
+    )
+    .unwrap();
+
+    encoded
+        .levels
+        .into_iter()
+        .map(|level| level.into_owned())
+        .collect()
+}
+
+#[test]
+fn etc1_individual_block() {
+    // Left colors (8, 4, 2) with table 0 and right colors (1, 2, 3) with table 1, and selector
+    // `x` in every row.
+    let block = [0x81, 0x42, 0x23, 0x04, 0xff, 0x00, 0xf0, 0xf0];
+    let row = [[138, 70, 36], [144, 76, 42], [12, 29, 46], [0, 17, 34]];
+
+    assert_eq!(decode_etc1(&block), std::array::from_fn(|p| row[p % 4]));
+}
+
+#[test]
+fn etc1_differential_flipped_block() {
+    // Base (20, 10, 31) with table 3 on top and base + (-3, 3, 0) with table 5 on the bottom, and
+    // selector `y` in every column.
+    let block = [0xa5, 0x53, 0xf8, 0x77, 0xcc, 0xcc, 0xaa, 0xaa];
+    let column = [
+        [178, 95, 255],
+        [207, 124, 255],
+        [116, 83, 231],
+        [60, 27, 175],
+    ];
+
+    assert_eq!(decode_etc1(&block), std::array::from_fn(|p| column[p / 4]));
+}
+
+#[test]
+fn eac_alpha_block() {
+    let block = eac_block(128, 2, 13, counting_selectors());
+
+    assert_eq!(
+        decode_eac(&block, false),
+        [
+            126, 128, 126, 128, //
+            124, 130, 124, 130, //
+            122, 132, 122, 132, //
+            108, 146, 108, 146,
+        ]
+    );
+}
+
+#[test]
+fn eac_r11_blocks() {
+    // A multiplier of 0 steps by 1.
+    let block = eac_block(200, 0, 14, counting_selectors());
+
+    assert_eq!(
+        decode_eac(&block, true),
+        [
+            1600, 1607, 1600, 1607, //
+            1598, 1609, 1598, 1609, //
+            1596, 1611, 1596, 1611, //
+            1595, 1612, 1595, 1612,
+        ]
+    );
+
+    let block = eac_block(128, 15, 0, counting_selectors());
+
+    assert_eq!(
+        decode_eac(&block, true),
+        [
+            668, 1268, 668, 1268, //
+            308, 1628, 308, 1628, //
+            0, 1988, 0, 1988, //
+            0, 2047, 0, 2047,
+        ]
+    );
+}
+
+#[test]
+fn etc2_rgba_block() {
+    let mut block = eac_block(128, 2, 13, counting_selectors()).to_vec();
+    block.extend_from_slice(&[0x81, 0x42, 0x23, 0x04, 0xff, 0x00, 0xf0, 0xf0]);
+
+    let alpha = decode_eac(&block[..8], false);
+    let color = decode_etc1(&block[8..]);
+
+    assert_eq!(alpha[5], 130);
+    assert_eq!(color[5], [144, 76, 42]);
+}
+
+#[test]
+fn etc2_rgb_round_trip() {
+    let image = image::RgbaImage::from_fn(16, 12, |x, y| {
+        image::Rgba([x as u8 * 16, y as u8 * 20, 128, 255])
+    });
+
+    let levels = encode(&image, EtcFormat::Etc2Rgb);
+    assert_eq!(levels[0].len(), 4 * 3 * 8);
+
+    let mut squared_error = 0;
+
+    for (i, block) in levels[0].chunks(8).enumerate() {
+        let (x, y) = (i as u32 % 4 * 4, i as u32 / 4 * 4);
+
+        for (p, decoded) in decode_etc1(block).iter().enumerate() {
+            let pixel = image.get_pixel(x + p as u32 % 4, y + p as u32 / 4);
+
+            for c in 0..3 {
+                squared_error += (decoded[c].abs_diff(pixel[c]) as u32).pow(2);
+            }
+        }
+    }
+
+    let mse = squared_error as f64 / (16 * 12 * 3) as f64;
+    let psnr = 10.0 * (255.0 * 255.0 / mse).log10();
+    assert!(psnr > 25.0, "PSNR of {:.1} dB", psnr);
+}
+
+#[test]
+fn etc2_rgba_has_alpha_before_color() {
+    let image = image::RgbaImage::from_fn(8, 4, |x, y| {
+        image::Rgba([200, 100, 50, (x * 4 + y) as u8 * 8])
+    });
+
+    let levels = encode(&image, EtcFormat::Etc2Rgba);
+    assert_eq!(levels[0].len(), 2 * 16);
+
+    for (i, block) in levels[0].chunks(16).enumerate() {
+        let alpha = decode_eac(&block[..8], false);
+        let color = decode_etc1(&block[8..]);
+
+        for p in 0..16 {
+            let pixel = image.get_pixel(i as u32 * 4 + p as u32 % 4, p as u32 / 4);
+
+            // Neighboring alpha values are 8 apart.
+            assert!(alpha[p].abs_diff(pixel[3] as u16) < 8);
+            assert!((0..3).all(|c| color[p][c].abs_diff(pixel[c]) <= 2));
+        }
+    }
+}
+
+#[test]
+fn eac_uniform_values_are_exact() {
+    for value in [0, 1, 37, 128, 254, 255] {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([value, 255 - value, 0, 255]));
+
+        let r11 = encode(&image, EtcFormat::EacR11);
+        assert!(decode_eac(&r11[0], true)
+            .iter()
+            .all(|&red| unorm11_to_unorm8(red) == value));
+
+        let rg11 = encode(&image, EtcFormat::EacRg11);
+        assert!(decode_eac(&rg11[0][..8], true)
+            .iter()
+            .all(|&red| unorm11_to_unorm8(red) == value));
+        assert!(decode_eac(&rg11[0][8..], true)
+            .iter()
+            .all(|&green| unorm11_to_unorm8(green) == 255 - value));
+    }
+}
+
+#[test]
+fn odd_sizes_are_padded() {
+    let image = image::RgbaImage::from_pixel(5, 3, image::Rgba([90, 180, 30, 255]));
+
+    let encoded = encode_etc(
+        &image,
+        &EtcOptions {
+            format: EtcFormat::Etc2Rgb,
+            supercompression_scheme: None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // 5x3, 2x1 and 1x1.
+    let sizes = encoded.levels.iter().map(|level| level.len());
+    assert_eq!(sizes.collect::<Vec<_>>(), [16, 8, 8]);
+
+    for pixel in decode_etc1(&encoded.levels[0][8..]) {
+        assert!(pixel
+            .iter()
+            .zip([90, 180, 30])
+            .all(|(decoded, expected)| decoded.abs_diff(expected) <= 4));
+    }
+}
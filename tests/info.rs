@@ -0,0 +1,93 @@
+#![cfg(all(feature = "json", feature = "writer"))]
+
+use ktx2_tools::{info_json, TextureKind, WriterBuilder};
+
+fn rgba_texture(width: u32, height: u32, kind: TextureKind) -> Vec<u8> {
+    let mut builder =
+        WriterBuilder::new(ktx2::Format::R8G8B8A8_UNORM, width, height, kind).unwrap();
+
+    for level in 0..builder.max_level_count() {
+        let size = builder.level_size(level) as usize;
+        builder
+            .push_level((0..size).map(|i| (i % 7) as u8).collect::<Vec<_>>())
+            .unwrap();
+    }
+
+    let mut bytes = Vec::new();
+    builder.writer().write(&mut bytes).unwrap();
+    bytes
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn describes_header_levels_and_metadata() {
+    let info = info_json(&rgba_texture(16, 8, TextureKind::Texture2D)).unwrap();
+
+    assert_eq!(info["vkFormat"], "R8G8B8A8_UNORM");
+    assert_eq!(info["pixelWidth"], 16);
+    assert_eq!(info["levelCount"], 5);
+    assert_eq!(info["supercompressionScheme"], serde_json::Value::Null);
+    assert_eq!(info["levels"][0]["byteLength"], 16 * 8 * 4);
+    assert_eq!(info["levels"][4]["pixelWidth"], 1);
+    assert_eq!(info["dataFormatDescriptors"][0]["vendorId"], 0);
+    assert!(info["keyValueData"]["KTXwriter"]
+        .as_str()
+        .unwrap()
+        .starts_with("ktx2-tools"));
+}
+
+#[test]
+fn levels_of_3d_textures_have_a_depth() {
+    let info = info_json(&rgba_texture(8, 2, TextureKind::Texture3D { depth: 4 })).unwrap();
+    let levels = info["levels"].as_array().unwrap();
+
+    let dimensions = levels.iter().map(|level| {
+        [
+            level["pixelWidth"].as_u64().unwrap(),
+            level["pixelHeight"].as_u64().unwrap(),
+            level["pixelDepth"].as_u64().unwrap(),
+        ]
+    });
+
+    assert_eq!(
+        dimensions.collect::<Vec<_>>(),
+        [[8, 2, 4], [4, 1, 2], [2, 1, 1], [1, 1, 1]]
+    );
+    assert_eq!(levels[1]["byteLength"], 4 * 2 * 4);
+}
+
+#[test]
+fn more_than_32_levels_dont_overflow() {
+    // Claim 40 levels by repeating the only level index entry of a 1x1 texture.
+    let bytes = rgba_texture(1, 1, TextureKind::Texture2D);
+    let extra = 39 * 24;
+
+    let mut patched = bytes[..104].to_vec();
+    patched[40..44].copy_from_slice(&40u32.to_le_bytes());
+
+    // The data format descriptor and key/value data move back by the extra entries.
+    for offset in [48, 56] {
+        let value = u32_at(&bytes, offset) + extra as u32;
+        patched[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    let level_offset = u64::from_le_bytes(bytes[80..88].try_into().unwrap()) + extra as u64;
+    patched[80..88].copy_from_slice(&level_offset.to_le_bytes());
+
+    let entry = patched[80..104].to_vec();
+    for _ in 0..39 {
+        patched.extend_from_slice(&entry);
+    }
+    patched.extend_from_slice(&bytes[104..]);
+
+    let info = info_json(&patched).unwrap();
+    let levels = info["levels"].as_array().unwrap();
+
+    assert_eq!(levels.len(), 40);
+    assert!(levels.iter().all(|level| level["pixelWidth"] == 1
+        && level["pixelHeight"] == 1
+        && level["pixelDepth"] == 1));
+}
@@ -0,0 +1,198 @@
+use ktx2_tools::{
+    AnimData, Error, GlFormat, Metadata, Orientation, OrientationX, OrientationY, OrientationZ,
+    Swizzle,
+};
+
+fn parse(key: &str, value: &[u8]) -> Result<Metadata, Error> {
+    Metadata::parse([(key, value)])
+}
+
+/// Serialize `metadata`, check that `key` has the value `bytes` and parse it back.
+fn round_trip(metadata: &Metadata, key: &str, bytes: &[u8]) -> Metadata {
+    let pairs = metadata.to_key_value_pairs();
+    assert_eq!(pairs[key], bytes);
+
+    Metadata::parse(
+        pairs
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_slice())),
+    )
+    .unwrap()
+}
+
+fn assert_invalid(key: &str, value: &[u8]) {
+    match parse(key, value) {
+        Err(Error::InvalidMetadata(invalid_key)) => assert_eq!(invalid_key, key),
+        other => panic!("{:?} parsed as {:?}", value, other),
+    }
+}
+
+#[test]
+fn orientation() {
+    for (value, x, y, z) in [
+        ("r", OrientationX::Right, None, None),
+        ("ld", OrientationX::Left, Some(OrientationY::Down), None),
+        (
+            "rui",
+            OrientationX::Right,
+            Some(OrientationY::Up),
+            Some(OrientationZ::In),
+        ),
+        (
+            "ldo",
+            OrientationX::Left,
+            Some(OrientationY::Down),
+            Some(OrientationZ::Out),
+        ),
+    ] {
+        let orientation = Orientation { x, y, z };
+        let metadata = Metadata {
+            orientation: Some(orientation),
+            ..Default::default()
+        };
+
+        let bytes = format!("{}\0", value);
+        assert_eq!(
+            round_trip(&metadata, "KTXorientation", bytes.as_bytes()),
+            metadata
+        );
+        assert_eq!(
+            parse("KTXorientation", bytes.as_bytes())
+                .unwrap()
+                .orientation,
+            Some(orientation)
+        );
+    }
+
+    // Unknown letters, z without y, trailing letters, no value and no NUL terminator.
+    for value in [&b"x\0"[..], b"ro\0", b"rdoi\0", b"RD\0", b"\0", b"rd", b""] {
+        assert_invalid("KTXorientation", value);
+    }
+}
+
+#[test]
+fn swizzle() {
+    let metadata = Metadata {
+        swizzle: Some([Swizzle::Blue, Swizzle::Green, Swizzle::Red, Swizzle::One]),
+        ..Default::default()
+    };
+    assert_eq!(round_trip(&metadata, "KTXswizzle", b"bgr1\0"), metadata);
+
+    let metadata = Metadata {
+        swizzle: Some([Swizzle::Red, Swizzle::Zero, Swizzle::Zero, Swizzle::Alpha]),
+        ..Default::default()
+    };
+    assert_eq!(round_trip(&metadata, "KTXswizzle", b"r00a\0"), metadata);
+
+    // Too few or too many components, unknown components and no NUL terminator.
+    for value in [&b"rgb\0"[..], b"rgbar\0", b"rgbx\0", b"rgba"] {
+        assert_invalid("KTXswizzle", value);
+    }
+}
+
+#[test]
+fn gl_format() {
+    let gl_format = GlFormat {
+        internal_format: 0x8058,
+        format: 0x1908,
+        ty: 0x1401,
+    };
+    let metadata = Metadata {
+        gl_format: Some(gl_format),
+        ..Default::default()
+    };
+
+    let bytes = [0x58, 0x80, 0, 0, 0x08, 0x19, 0, 0, 0x01, 0x14, 0, 0];
+    assert_eq!(round_trip(&metadata, "KTXglFormat", &bytes), metadata);
+    assert_eq!(
+        parse("KTXglFormat", &bytes).unwrap().gl_format,
+        Some(gl_format)
+    );
+
+    for value in [&bytes[..8], &[0; 16], &[]] {
+        assert_invalid("KTXglFormat", value);
+    }
+}
+
+#[test]
+fn dxgi_format() {
+    let metadata = Metadata {
+        dxgi_format: Some(98),
+        ..Default::default()
+    };
+    assert_eq!(
+        round_trip(&metadata, "KTXdxgiFormat__", &[98, 0, 0, 0]),
+        metadata
+    );
+
+    for value in [&[98, 0, 0][..], &[98, 0, 0, 0, 0], &[]] {
+        assert_invalid("KTXdxgiFormat__", value);
+    }
+}
+
+#[test]
+fn anim_data() {
+    let anim_data = AnimData {
+        duration: 1,
+        timescale: 30,
+        loop_count: 0,
+    };
+    let metadata = Metadata {
+        anim_data: Some(anim_data),
+        ..Default::default()
+    };
+
+    let bytes = [1, 0, 0, 0, 30, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(round_trip(&metadata, "KTXanimData", &bytes), metadata);
+    assert_eq!(
+        parse("KTXanimData", &bytes).unwrap().anim_data,
+        Some(anim_data)
+    );
+
+    for value in [&bytes[..8], &[0; 13], &[]] {
+        assert_invalid("KTXanimData", value);
+    }
+}
+
+#[test]
+fn cubemap_incomplete() {
+    for faces in [0b00_0001, 0b10_1101, 0b11_1111] {
+        let metadata = Metadata {
+            cubemap_incomplete: Some(faces),
+            ..Default::default()
+        };
+        assert_eq!(
+            round_trip(&metadata, "KTXcubemapIncomplete", &[faces]),
+            metadata
+        );
+    }
+
+    // No faces, a bit past -Z, and values that aren't a single byte.
+    for value in [&[0][..], &[0b100_0001], &[0b11_1111, 0], &[]] {
+        assert_invalid("KTXcubemapIncomplete", value);
+    }
+}
+
+#[test]
+fn all_keys_round_trip_together() {
+    let metadata = Metadata {
+        orientation: Some(Orientation {
+            x: OrientationX::Right,
+            y: Some(OrientationY::Down),
+            z: None,
+        }),
+        swizzle: Some([Swizzle::Red, Swizzle::Green, Swizzle::Blue, Swizzle::One]),
+        dxgi_format: Some(71),
+        cubemap_incomplete: Some(0b11),
+        anim_data: Some(AnimData {
+            duration: 2,
+            timescale: 60,
+            loop_count: 3,
+        }),
+        writer: Some("ktx2-tools".to_string()),
+        other: [("custom".to_string(), vec![1, 2, 3])].into(),
+        ..Default::default()
+    };
+
+    assert_eq!(round_trip(&metadata, "custom", &[1, 2, 3]), metadata);
+}
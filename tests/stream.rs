@@ -0,0 +1,91 @@
+#![cfg(all(feature = "writer", feature = "zlib"))]
+
+use ktx2::SupercompressionScheme;
+use ktx2_tools::{decompress_level, validate, Error, TextureKind, WriterBuilder};
+use std::io::Cursor;
+
+/// The levels of a 32x32 RGBA8 texture, filled with noise so that they don't compress well.
+fn noise_levels(builder: &WriterBuilder) -> Vec<Vec<u8>> {
+    let mut state = 1u32;
+
+    (0..builder.max_level_count())
+        .map(|level| {
+            (0..builder.level_size(level))
+                .map(|_| {
+                    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                    (state >> 24) as u8
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn builder() -> WriterBuilder<'static> {
+    WriterBuilder::new(ktx2::Format::R8G8B8A8_UNORM, 32, 32, TextureKind::Texture2D)
+        .unwrap()
+        .supercompression_scheme(Some(SupercompressionScheme::ZLIB))
+}
+
+fn assert_levels(bytes: &[u8], levels: &[Vec<u8>]) {
+    assert!(validate(bytes).is_empty());
+
+    let reader = ktx2::Reader::new(bytes).unwrap();
+    for (level, expected) in reader.levels().zip(levels) {
+        let data = decompress_level(
+            Some(SupercompressionScheme::ZLIB),
+            level.data,
+            level.uncompressed_byte_length,
+        )
+        .unwrap();
+        assert_eq!(&*data, expected.as_slice());
+    }
+}
+
+#[test]
+fn levels_largest_first_are_held_up_to_the_limit() {
+    let builder = builder();
+    let levels = noise_levels(&builder);
+
+    let mut writer = builder
+        .streaming_writer(Cursor::new(Vec::new()), levels.len())
+        .unwrap()
+        .max_pending_bytes(5000);
+
+    // Level 0 is 4096 bytes and level 1 is 1024, which don't compress.
+    writer.write_level(0, &levels[0]).unwrap();
+
+    match writer.write_level(1, &levels[1]) {
+        Err(Error::PendingLimitExceeded {
+            level: 1,
+            pending,
+            limit: 5000,
+        }) => assert!(pending > 5000),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    // The rejected level wasn't kept, and the smallest level is never held.
+    for level in (1..levels.len()).rev() {
+        writer.write_level(level, &levels[level]).unwrap();
+    }
+
+    let bytes = writer.finish().unwrap().into_inner();
+    assert_levels(&bytes, &levels);
+}
+
+#[test]
+fn levels_smallest_first_ignore_the_limit() {
+    let builder = builder();
+    let levels = noise_levels(&builder);
+
+    let mut writer = builder
+        .streaming_writer(Cursor::new(Vec::new()), levels.len())
+        .unwrap()
+        .max_pending_bytes(0);
+
+    for (level, data) in levels.iter().enumerate().rev() {
+        writer.write_level(level, data).unwrap();
+    }
+
+    let bytes = writer.finish().unwrap().into_inner();
+    assert_levels(&bytes, &levels);
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "writer")]
+
+use ktx2::SupercompressionScheme;
+use ktx2_tools::{compress_level, decompress_level, Error, WriterOptions};
+
+const LEVEL: &[u8] = &[7; 4096];
+
+/// Lengths that are wrong for `LEVEL`, including ones too large to allocate or add to.
+const BAD_LENGTHS: &[u64] = &[0, 4095, 4097, 0xFFFF_FFFF_FFFF_FFF0, u64::MAX];
+
+fn assert_bad_lengths_rejected(scheme: SupercompressionScheme) {
+    let compressed = compress_level(Some(scheme), LEVEL, &WriterOptions::default()).unwrap();
+
+    assert_eq!(
+        decompress_level(Some(scheme), &compressed, LEVEL.len() as u64).unwrap(),
+        LEVEL
+    );
+
+    for &length in BAD_LENGTHS {
+        assert!(
+            matches!(
+                decompress_level(Some(scheme), &compressed, length),
+                Err(Error::Compression(_))
+            ),
+            "{:?} with uncompressedByteLength {}",
+            scheme,
+            length
+        );
+    }
+}
+
+#[cfg(feature = "zlib")]
+#[test]
+fn zlib_rejects_wrong_uncompressed_length() {
+    assert_bad_lengths_rejected(SupercompressionScheme::ZLIB);
+}
+
+#[cfg(any(feature = "zstd", feature = "zstd-pure"))]
+#[test]
+fn zstd_rejects_wrong_uncompressed_length() {
+    assert_bad_lengths_rejected(SupercompressionScheme::Zstandard);
+}
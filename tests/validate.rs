@@ -0,0 +1,160 @@
+#![cfg(feature = "writer")]
+
+use ktx2_tools::{validate, BasicDfd, Severity, TextureKind, WriterBuilder};
+use std::collections::BTreeMap;
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn set_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// A valid 2x2 RGBA8 texture with a key/value pair whose value needs 2 bytes of padding.
+fn rgba8_texture() -> Vec<u8> {
+    let mut key_value_pairs = BTreeMap::new();
+    key_value_pairs.insert("zz".to_string(), b"abc".to_vec());
+
+    let mut builder =
+        WriterBuilder::new(ktx2::Format::R8G8B8A8_UNORM, 2, 2, TextureKind::Texture2D)
+            .unwrap()
+            .key_value_pairs(key_value_pairs);
+    builder.push_level(vec![0; 16]).unwrap();
+
+    let mut bytes = Vec::new();
+    builder.writer().write(&mut bytes).unwrap();
+    bytes
+}
+
+fn messages(bytes: &[u8], severity: Severity) -> Vec<String> {
+    validate(bytes)
+        .into_iter()
+        .filter(|issue| issue.severity == severity)
+        .map(|issue| issue.message)
+        .collect()
+}
+
+fn has_error(bytes: &[u8], fragment: &str) -> bool {
+    messages(bytes, Severity::Error)
+        .iter()
+        .any(|message| message.contains(fragment))
+}
+
+#[test]
+fn valid_texture_has_no_errors() {
+    assert_eq!(
+        messages(&rgba8_texture(), Severity::Error),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn empty_and_short_files() {
+    assert!(has_error(&[], "too short for a KTX2 header"));
+    assert!(has_error(
+        &rgba8_texture()[..40],
+        "too short for a KTX2 header"
+    ));
+    assert!(has_error(&[0; 80], "KTX2 identifier"));
+}
+
+#[test]
+fn truncated_level_index() {
+    let mut bytes = rgba8_texture();
+    bytes.truncate(80 + 12);
+    assert!(has_error(
+        &bytes,
+        "level index extends past the end of the file"
+    ));
+
+    // A level count that claims far more entries than the file holds.
+    let mut bytes = rgba8_texture();
+    set_u32(&mut bytes, 40, u32::MAX);
+    assert!(has_error(
+        &bytes,
+        "level index extends past the end of the file"
+    ));
+}
+
+#[test]
+fn unpadded_key_value_data() {
+    let bytes = rgba8_texture();
+    let kvd_length = u32_at(&bytes, 60);
+
+    // Cut the padding after the last pair.
+    let mut unpadded = bytes.clone();
+    set_u32(&mut unpadded, 60, kvd_length - 2);
+    assert!(has_error(&unpadded, "isn't padded to 4 bytes"));
+
+    // Non-zero padding is only a warning.
+    let mut dirty = bytes.clone();
+    let padding = (u32_at(&bytes, 56) + kvd_length - 1) as usize;
+    dirty[padding] = 0xff;
+    assert_eq!(messages(&dirty, Severity::Error), Vec::<String>::new());
+    assert!(messages(&dirty, Severity::Warning)
+        .iter()
+        .any(|message| message.contains("padding bytes aren't zero")));
+
+    // A pair whose length runs past the end of the key/value data.
+    let mut overlong = bytes;
+    let kvd_offset = u32_at(&overlong, 56) as usize;
+    set_u32(&mut overlong, kvd_offset, u32::MAX);
+    assert!(has_error(&overlong, "extends past the key/value data"));
+}
+
+#[test]
+fn basis_lz_global_data_with_huge_counts() {
+    // A header, one level index entry, 40 bytes of global data and a 4-byte level.
+    let mut bytes = vec![0; 80 + 24 + 40 + 4];
+    bytes[..12].copy_from_slice(&[
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ]);
+    set_u32(&mut bytes, 16, 1); // typeSize
+    set_u32(&mut bytes, 20, 4); // pixelWidth
+    set_u32(&mut bytes, 24, 4); // pixelHeight
+    set_u32(&mut bytes, 32, u32::MAX); // layerCount
+    set_u32(&mut bytes, 36, u32::MAX); // faceCount
+    set_u32(&mut bytes, 40, 1); // levelCount
+    set_u32(&mut bytes, 44, 1); // BasisLZ
+    set_u32(&mut bytes, 48, 104); // dfdByteOffset
+    set_u64(&mut bytes, 64, 104); // sgdByteOffset
+    set_u64(&mut bytes, 72, 40); // sgdByteLength
+    set_u64(&mut bytes, 80, 144); // level 0 byteOffset
+    set_u64(&mut bytes, 88, 4); // level 0 byteLength
+
+    for offset in [4, 8, 12, 16] {
+        set_u32(&mut bytes, 104 + offset, u32::MAX);
+    }
+
+    assert!(has_error(&bytes, "faceCount is 4294967295"));
+    assert!(has_error(&bytes, "the BasisLZ global data is 40 bytes"));
+}
+
+#[test]
+fn texel_block_dimension_of_256() {
+    let mut bytes = rgba8_texture();
+
+    // Skip dfdTotalSize, the descriptor block header and the first 4 bytes of the Basic DFD.
+    let dimensions = u32_at(&bytes, 48) as usize + 4 + 8 + 4;
+
+    for i in 0..4 {
+        let mut patched = bytes.clone();
+        patched[dimensions + i] = 0xff;
+
+        assert!(has_error(
+            &patched,
+            &format!("texelBlockDimension{} is 255", i)
+        ));
+        assert!(BasicDfd::from_bytes(&patched[dimensions - 4..]).is_err());
+    }
+
+    // 255 is the largest dimension that still fits.
+    bytes[dimensions] = 0xfe;
+    let dfd = BasicDfd::from_bytes(&bytes[dimensions - 4..]).unwrap();
+    assert_eq!(dfd.block_dimensions(), [255, 1, 1, 1]);
+}
@@ -0,0 +1,45 @@
+use ktx2_tools::{Texture, WriterOptions};
+use wasm_bindgen::prelude::*;
+
+/// Describe a KTX2 file as JSON, like `ktx2-info --json`. See `ktx2_tools::info_json`.
+#[wasm_bindgen]
+pub fn info(bytes: &[u8]) -> Result<String, JsError> {
+    Ok(ktx2_tools::info_json(bytes)?.to_string())
+}
+
+/// Re-supercompress a KTX2 file, like `ktx2-compress`. Levels are compressed with Zstandard, or
+/// with ZLIB if `zlib` is set, at `level` if given. Zstandard uses a pure-Rust encoder here,
+/// which only has one compression level.
+#[wasm_bindgen]
+pub fn repack(bytes: &[u8], zlib: bool, level: Option<i32>) -> Result<Vec<u8>, JsError> {
+    let mut texture = Texture::from_bytes(bytes)?;
+
+    texture.set_supercompression_scheme(Some(if zlib {
+        ktx2::SupercompressionScheme::ZLIB
+    } else {
+        ktx2::SupercompressionScheme::Zstandard
+    }))?;
+
+    let zlib_level = match (zlib, level) {
+        (true, Some(level)) => Some(
+            u32::try_from(level)
+                .ok()
+                .filter(|&level| level <= 9)
+                .ok_or_else(|| JsError::new("ZLIB levels range from 0 to 9"))?,
+        ),
+        _ => None,
+    };
+
+    let mut output = Vec::new();
+
+    texture
+        .to_writer(WriterOptions {
+            zstd_level: level.filter(|_| !zlib).unwrap_or(0),
+            zlib_level,
+            preserve_ktx_writer_history: true,
+            ..Default::default()
+        })
+        .write(&mut output)?;
+
+    Ok(output)
+}
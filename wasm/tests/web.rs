@@ -0,0 +1,62 @@
+#![cfg(target_arch = "wasm32")]
+
+use ktx2_tools::{Texture, TextureKind, WriterBuilder};
+use wasm_bindgen_test::*;
+
+fn rgba_texture() -> Vec<u8> {
+    let mut builder =
+        WriterBuilder::new(ktx2::Format::R8G8B8A8_UNORM, 16, 8, TextureKind::Texture2D).unwrap();
+
+    for level in 0..builder.max_level_count() {
+        let size = builder.level_size(level) as usize;
+        builder
+            .push_level((0..size).map(|i| (i % 7) as u8).collect::<Vec<_>>())
+            .unwrap();
+    }
+
+    let mut bytes = Vec::new();
+    builder.writer().write(&mut bytes).unwrap();
+    bytes
+}
+
+#[wasm_bindgen_test]
+fn info_describes_levels_and_metadata() {
+    let info: serde_json::Value =
+        serde_json::from_str(&ktx2_tools_wasm::info(&rgba_texture()).unwrap()).unwrap();
+
+    assert_eq!(info["vkFormat"], "R8G8B8A8_UNORM");
+    assert_eq!(info["pixelWidth"], 16);
+    assert_eq!(info["levelCount"], 5);
+    assert_eq!(info["levels"][4]["pixelWidth"], 1);
+    assert_eq!(info["levels"][0]["byteLength"], 16 * 8 * 4);
+    assert!(info["keyValueData"]["KTXwriter"]
+        .as_str()
+        .unwrap()
+        .starts_with("ktx2-tools"));
+}
+
+#[wasm_bindgen_test]
+fn repack_round_trips() {
+    let bytes = rgba_texture();
+    let original = Texture::from_bytes(&bytes).unwrap();
+
+    for (zlib, scheme) in [(false, "Zstandard"), (true, "ZLIB")] {
+        let repacked = ktx2_tools_wasm::repack(&bytes, zlib, None).unwrap();
+
+        assert!(ktx2_tools::validate(&repacked).is_empty());
+        assert_eq!(
+            Texture::from_bytes(&repacked).unwrap().levels,
+            original.levels
+        );
+
+        let info: serde_json::Value =
+            serde_json::from_str(&ktx2_tools_wasm::info(&repacked).unwrap()).unwrap();
+        assert_eq!(info["supercompressionScheme"], scheme);
+    }
+}
+
+#[wasm_bindgen_test]
+fn invalid_input_is_an_error() {
+    assert!(ktx2_tools_wasm::info(b"not a ktx2 file").is_err());
+    assert!(ktx2_tools_wasm::repack(&rgba_texture(), true, Some(10)).is_err());
+}